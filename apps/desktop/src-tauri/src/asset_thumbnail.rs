@@ -0,0 +1,99 @@
+use std::io::Cursor;
+use std::path::PathBuf;
+
+use image::{ImageFormat, ImageReader};
+
+use crate::maple_fs;
+
+pub const DEFAULT_THUMBNAIL_WIDTH: u32 = 320;
+const MIN_THUMBNAIL_WIDTH: u32 = 16;
+const MAX_THUMBNAIL_WIDTH: u32 = 1600;
+/// Sources above this are not decoded; a huge image could stall the protocol thread.
+const MAX_SOURCE_BYTES: u64 = 40 * 1024 * 1024;
+
+pub enum ThumbnailError {
+  InvalidName,
+  NotFound,
+  Unsupported,
+  TooLarge,
+  Failed(String),
+}
+
+impl ThumbnailError {
+  pub fn message(&self) -> String {
+    match self {
+      ThumbnailError::InvalidName => "无效的 asset 文件名。".to_string(),
+      ThumbnailError::NotFound => "asset 文件不存在。".to_string(),
+      ThumbnailError::Unsupported => "该 asset 不是可生成缩略图的图片。".to_string(),
+      ThumbnailError::TooLarge => format!("源图片过大（上限 {MAX_SOURCE_BYTES} bytes）。"),
+      ThumbnailError::Failed(message) => message.clone(),
+    }
+  }
+}
+
+fn source_format(ext: &str) -> Option<ImageFormat> {
+  match ext.trim().to_ascii_lowercase().as_str() {
+    "png" => Some(ImageFormat::Png),
+    "jpg" | "jpeg" => Some(ImageFormat::Jpeg),
+    "webp" => Some(ImageFormat::WebP),
+    "gif" => Some(ImageFormat::Gif),
+    _ => None,
+  }
+}
+
+pub fn clamp_width(width: Option<u32>) -> u32 {
+  width
+    .unwrap_or(DEFAULT_THUMBNAIL_WIDTH)
+    .clamp(MIN_THUMBNAIL_WIDTH, MAX_THUMBNAIL_WIDTH)
+}
+
+fn thumbnail_dir() -> Result<PathBuf, ThumbnailError> {
+  let dir = maple_fs::asset_dir()
+    .map_err(ThumbnailError::Failed)?
+    .join(".thumbs");
+  std::fs::create_dir_all(&dir).map_err(|e| ThumbnailError::Failed(format!("创建缩略图目录失败: {e}")))?;
+  Ok(dir)
+}
+
+/// Returns a PNG of `file_name` scaled down to at most `width` pixels wide,
+/// generating it into `assets/.thumbs/` on first use. Images already narrower
+/// than `width` keep their size.
+pub fn load_or_create(file_name: &str, width: u32) -> Result<Vec<u8>, ThumbnailError> {
+  let file_name = file_name.trim();
+  if !maple_fs::is_valid_asset_file_name(file_name) {
+    return Err(ThumbnailError::InvalidName);
+  }
+  let (hash, ext) = file_name.split_once('.').ok_or(ThumbnailError::InvalidName)?;
+  let format = source_format(ext).ok_or(ThumbnailError::Unsupported)?;
+
+  let cached = thumbnail_dir()?.join(format!("{hash}-w{width}.png"));
+  if let Ok(bytes) = std::fs::read(&cached) {
+    return Ok(bytes);
+  }
+
+  let source = maple_fs::asset_dir().map_err(ThumbnailError::Failed)?.join(file_name);
+  let meta = std::fs::metadata(&source).map_err(|_| ThumbnailError::NotFound)?;
+  if meta.len() > MAX_SOURCE_BYTES {
+    return Err(ThumbnailError::TooLarge);
+  }
+  let bytes = std::fs::read(&source).map_err(|e| ThumbnailError::Failed(format!("读取 asset 失败: {e}")))?;
+
+  let image = ImageReader::with_format(Cursor::new(bytes), format)
+    .decode()
+    .map_err(|e| ThumbnailError::Failed(format!("解码图片失败: {e}")))?;
+  let scaled = if image.width() > width {
+    image.thumbnail(width, u32::MAX)
+  } else {
+    image
+  };
+
+  let mut encoded = Vec::new();
+  scaled
+    .write_to(&mut Cursor::new(&mut encoded), ImageFormat::Png)
+    .map_err(|e| ThumbnailError::Failed(format!("编码缩略图失败: {e}")))?;
+  // The cache is only an optimisation; serve the fresh thumbnail even if it can't be stored.
+  if let Err(e) = maple_fs::write_file_atomic(&cached, &encoded) {
+    eprintln!("[asset-thumbnail] failed to cache {}: {e}", cached.display());
+  }
+  Ok(encoded)
+}
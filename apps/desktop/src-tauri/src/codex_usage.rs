@@ -1,6 +1,13 @@
 use serde::Serialize;
 use serde_json::Value;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+const MAX_ATTEMPTS: u32 = 3;
+const DEFAULT_TIMEOUT_SECS: u64 = 12;
+const MAX_TIMEOUT_SECS: u64 = 120;
+/// Upper bound for all attempts together, so a flaky endpoint can't hang the UI.
+const TOTAL_DEADLINE: Duration = Duration::from_secs(25);
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
 
 #[derive(Debug, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -9,6 +16,105 @@ pub struct CodexUsageHttpResult {
   pub status: u16,
   pub body: Option<Value>,
   pub text: Option<String>,
+  /// Requests sent, including retries.
+  pub attempts: u32,
+  /// Typed view of `body` when it is a usage object; `body` stays for fields not modelled here.
+  pub usage: Option<CodexUsage>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CodexUsageSubscription {
+  pub plan_id: Option<String>,
+  pub plan_name: Option<String>,
+  pub today_remaining_with_carryover: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CodexUsage {
+  pub is_valid: bool,
+  pub invalid_code: Option<String>,
+  pub invalid_message: Option<String>,
+  pub unit: String,
+  /// `remaining`, or `balance + plan_remaining` when the provider only reports those.
+  pub remaining: Option<f64>,
+  pub balance: Option<f64>,
+  pub plan_remaining: Option<f64>,
+  pub plan_name: Option<String>,
+  pub today_limit: Option<f64>,
+  pub today_remaining: Option<f64>,
+  pub today_remaining_with_carryover: Option<f64>,
+  pub subscriptions: Vec<CodexUsageSubscription>,
+}
+
+/// Providers send amounts as numbers or numeric strings.
+fn number_field(value: &Value, key: &str) -> Option<f64> {
+  match value.get(key)? {
+    Value::Number(number) => number.as_f64(),
+    Value::String(text) => text.trim().parse::<f64>().ok(),
+    _ => None,
+  }
+  .filter(|number| number.is_finite())
+}
+
+fn string_field(value: &Value, key: &str) -> Option<String> {
+  value
+    .get(key)
+    .and_then(|v| v.as_str())
+    .map(str::trim)
+    .filter(|text| !text.is_empty())
+    .map(str::to_string)
+}
+
+fn parse_usage(body: &Value) -> Option<CodexUsage> {
+  if !body.is_object() {
+    return None;
+  }
+  let error = body.get("error").filter(|error| !error.is_null());
+  let invalid_code = string_field(body, "invalidCode");
+  let invalid_message = string_field(body, "invalidMessage").or_else(|| match error {
+    Some(Value::String(message)) => Some(message.clone()),
+    Some(error) => string_field(error, "message"),
+    None => None,
+  });
+  let is_valid = error.is_none()
+    && body.get("isValid").and_then(|v| v.as_bool()) != Some(false)
+    && invalid_code.as_deref() != Some("NO_QUOTA");
+
+  let balance = number_field(body, "balance");
+  let plan_remaining = number_field(body, "plan_remaining");
+  let remaining = number_field(body, "remaining").or_else(|| Some(balance? + plan_remaining?));
+
+  let subscriptions = body
+    .get("subscriptions")
+    .and_then(|v| v.as_array())
+    .map(|items| {
+      items
+        .iter()
+        .map(|item| CodexUsageSubscription {
+          plan_id: string_field(item, "planId"),
+          plan_name: string_field(item, "planName"),
+          today_remaining_with_carryover: number_field(item, "todayRemainingWithCarryover"),
+        })
+        .collect()
+    })
+    .unwrap_or_default();
+
+  Some(CodexUsage {
+    is_valid,
+    invalid_code,
+    invalid_message,
+    unit: string_field(body, "unit").unwrap_or_else(|| "USD".to_string()),
+    remaining,
+    balance,
+    plan_remaining,
+    plan_name: string_field(body, "planName"),
+    today_limit: number_field(body, "todayLimit"),
+    today_remaining: number_field(body, "todayRemaining"),
+    today_remaining_with_carryover: number_field(body, "todayRemainingWithCarryover"),
+    subscriptions,
+  })
 }
 
 fn build_usage_url(base_url: &str) -> Result<String, String> {
@@ -22,24 +128,98 @@ fn build_usage_url(base_url: &str) -> Result<String, String> {
   Ok(format!("{trimmed}/codex/v1/usage"))
 }
 
-pub fn query_codex_usage(base_url: String, api_key: String) -> Result<CodexUsageHttpResult, String> {
+fn read_env_non_empty(keys: &[&str]) -> Option<String> {
+  keys
+    .iter()
+    .filter_map(|key| std::env::var(key).ok())
+    .map(|value| value.trim().to_string())
+    .find(|value| !value.is_empty())
+}
+
+/// The proxy to use: the explicit `proxy` argument, else the standard
+/// `HTTPS_PROXY`/`HTTP_PROXY`/`ALL_PROXY` variables. Loopback hosts and
+/// `NO_PROXY` entries always bypass it.
+fn resolve_proxy(proxy: Option<String>) -> Result<Option<reqwest::Proxy>, String> {
+  let url = proxy
+    .map(|value| value.trim().to_string())
+    .filter(|value| !value.is_empty())
+    .or_else(|| {
+      read_env_non_empty(&[
+        "HTTPS_PROXY",
+        "https_proxy",
+        "HTTP_PROXY",
+        "http_proxy",
+        "ALL_PROXY",
+        "all_proxy",
+      ])
+    });
+  let Some(url) = url else {
+    return Ok(None);
+  };
+
+  let mut bypass = "localhost,127.0.0.1,::1".to_string();
+  if let Some(extra) = read_env_non_empty(&["NO_PROXY", "no_proxy"]) {
+    bypass.push(',');
+    bypass.push_str(&extra);
+  }
+  let proxy = reqwest::Proxy::all(&url)
+    .map_err(|error| format!("Invalid proxy URL {url}: {error}"))?
+    .no_proxy(reqwest::NoProxy::from_string(&bypass));
+  Ok(Some(proxy))
+}
+
+/// `timeout_secs` bounds each request (default 12s, 1-120s).
+pub fn query_codex_usage(
+  base_url: String,
+  api_key: String,
+  timeout_secs: Option<u64>,
+  proxy: Option<String>,
+) -> Result<CodexUsageHttpResult, String> {
   let url = build_usage_url(&base_url)?;
   let token = api_key.trim();
   if token.is_empty() {
     return Err("API key cannot be empty.".to_string());
   }
+  let timeout_secs = timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS);
+  if !(1..=MAX_TIMEOUT_SECS).contains(&timeout_secs) {
+    return Err(format!("Timeout must be between 1 and {MAX_TIMEOUT_SECS} seconds."));
+  }
+  let request_timeout = Duration::from_secs(timeout_secs);
 
-  let client = reqwest::blocking::Client::builder()
-    .timeout(Duration::from_secs(12))
+  let builder = reqwest::blocking::Client::builder().timeout(request_timeout);
+  let builder = match resolve_proxy(proxy)? {
+    Some(proxy) => builder.proxy(proxy),
+    None => builder.no_proxy(),
+  };
+  let client = builder
     .build()
     .map_err(|error| format!("Failed to create HTTP client: {error}"))?;
 
-  let response = client
-    .get(&url)
-    .header("Authorization", format!("Bearer {token}"))
-    .header("User-Agent", "cc-switch/1.0")
-    .send()
-    .map_err(|error| format!("Request failed: {error}"))?;
+  // Connection errors, timeouts and 5xx are retried with exponential backoff;
+  // 4xx answers are final.
+  let deadline = Instant::now() + TOTAL_DEADLINE.max(request_timeout);
+  let mut backoff = INITIAL_BACKOFF;
+  let mut attempts = 0;
+  let response = loop {
+    attempts += 1;
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    let result = client
+      .get(&url)
+      .timeout(request_timeout.min(remaining))
+      .header("Authorization", format!("Bearer {token}"))
+      .header("User-Agent", "cc-switch/1.0")
+      .send();
+    let retryable = match &result {
+      Ok(response) => response.status().is_server_error(),
+      Err(error) => error.is_connect() || error.is_timeout(),
+    };
+    let has_time = deadline.saturating_duration_since(Instant::now()) > backoff;
+    if !retryable || attempts >= MAX_ATTEMPTS || !has_time {
+      break result.map_err(|error| format!("Request failed after {attempts} attempt(s): {error}"))?;
+    }
+    std::thread::sleep(backoff);
+    backoff *= 2;
+  };
 
   let status = response.status().as_u16();
   let ok = response.status().is_success();
@@ -51,6 +231,8 @@ pub fn query_codex_usage(base_url: String, api_key: String) -> Result<CodexUsage
       status,
       body: None,
       text: None,
+      attempts,
+      usage: None,
     });
   }
 
@@ -58,14 +240,18 @@ pub fn query_codex_usage(base_url: String, api_key: String) -> Result<CodexUsage
     Ok(parsed) => Ok(CodexUsageHttpResult {
       ok,
       status,
+      usage: parse_usage(&parsed),
       body: Some(parsed),
       text: None,
+      attempts,
     }),
     Err(_) => Ok(CodexUsageHttpResult {
       ok,
       status,
       body: None,
       text: Some(trimmed.to_string()),
+      attempts,
+      usage: None,
     }),
   }
 }
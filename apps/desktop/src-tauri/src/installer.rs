@@ -4,15 +4,75 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
-use std::sync::Arc;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 use crate::maple_fs;
+use crate::mcp_http;
 use crate::process_utils;
+use crate::worker_probe;
 use chrono::Utc;
 
-const MAPLE_MCP_URL: &str = "http://localhost:45819/mcp";
-const SKILLS_VERSION: u32 = 3;
+fn maple_mcp_url() -> String {
+  format!("http://localhost:{}/mcp", mcp_http::mcp_port())
+}
+
+fn resolve_mcp_url(custom: Option<&str>) -> Result<String, String> {
+  let Some(url) = custom.map(str::trim).filter(|url| !url.is_empty()) else {
+    return Ok(maple_mcp_url());
+  };
+  if !url.starts_with("http://") && !url.starts_with("https://") {
+    return Err(format!("MCP 地址必须以 http:// 或 https:// 开头：{url}"));
+  }
+  Ok(url.to_string())
+}
+
+/// `Authorization` header value clients must send when `MAPLE_MCP_TOKEN` is set.
+fn maple_mcp_bearer() -> Option<String> {
+  mcp_http::mcp_token().map(|token| format!("Bearer {token}"))
+}
+
+/// `--header "Authorization: Bearer …"` for CLIs that take headers on `mcp add`;
+/// must be placed before the server name since trailing args go to the server.
+fn maple_mcp_header_args() -> Vec<String> {
+  match maple_mcp_bearer() {
+    Some(bearer) => vec!["--header".into(), format!("Authorization: {bearer}")],
+    None => Vec::new(),
+  }
+}
+
+fn maple_mcp_header_shell_args() -> String {
+  let Some(bearer) = maple_mcp_bearer() else {
+    return String::new();
+  };
+  let header = format!("Authorization: {bearer}").replace('\'', "'\\''");
+  format!(" --header '{header}'")
+}
+
+/// Codex reads the token from the environment at connect time instead of storing it.
+fn maple_mcp_codex_token_args() -> Vec<String> {
+  if mcp_http::mcp_token().is_some() {
+    vec!["--bearer-token-env-var".into(), mcp_http::MCP_TOKEN_ENV.into()]
+  } else {
+    Vec::new()
+  }
+}
+
+fn maple_mcp_headers_json() -> Option<serde_json::Value> {
+  maple_mcp_bearer().map(|bearer| json!({ "Authorization": bearer }))
+}
+
+/// Masks `token` in install log lines, so echoed `--header` args read `Bearer ***`.
+fn redact_token(text: &str, token: Option<&str>) -> String {
+  match token {
+    Some(token) if !token.is_empty() => text.replace(token, "***"),
+    _ => text.to_string(),
+  }
+}
+pub const SKILLS_VERSION: u32 = 3;
 const ENABLE_WSL_INTEGRATION: bool = false;
 
 fn should_enable_wsl_integration() -> bool {
@@ -37,6 +97,23 @@ pub struct InstallMcpSkillsOptions {
   #[serde(default)]
   pub wsl_opencode: bool,
   pub windsurf: bool,
+  #[serde(default)]
+  pub cursor: bool,
+  #[serde(default)]
+  pub vscode: bool,
+  /// Overrides the local `http://localhost:<port>/mcp` address, e.g. for a remote Maple.
+  #[serde(default)]
+  pub mcp_url: Option<String>,
+  /// Log what would be written/run without touching the filesystem or CLIs.
+  #[serde(default)]
+  pub dry_run: bool,
+  /// Skip CLI targets whose skills and `maple` MCP server are already current
+  /// instead of rewriting and re-registering them.
+  #[serde(default)]
+  pub skip_if_installed: bool,
+  /// WSL distribution to install into (`wsl -d <distro>`); the default distro when unset.
+  #[serde(default)]
+  pub wsl_distro: Option<String>,
   pub install_id: Option<String>,
 }
 
@@ -54,6 +131,12 @@ impl Default for InstallMcpSkillsOptions {
       wsl_gemini: false,
       wsl_opencode: false,
       windsurf: true,
+      cursor: true,
+      vscode: true,
+      mcp_url: None,
+      dry_run: false,
+      skip_if_installed: false,
+      wsl_distro: None,
       install_id: None,
     }
   }
@@ -118,6 +201,12 @@ impl InstallTaskEvent {
 struct InstallEventEmitter {
   install_id: String,
   emit: Option<Arc<dyn Fn(InstallTaskEvent) + Send + Sync>>,
+  /// Per-run settings; they travel with the emitter because every install step
+  /// already receives it.
+  mcp_url: String,
+  dry_run: bool,
+  skip_if_installed: bool,
+  wsl_distro: Option<String>,
 }
 
 impl InstallEventEmitter {
@@ -127,7 +216,7 @@ impl InstallEventEmitter {
   }
 
   fn log(&self, target_id: Option<&str>, stream: &str, line: impl Into<String>) {
-    let text = line.into();
+    let text = redact_token(&line.into(), mcp_http::mcp_token().as_deref());
     if text.trim().is_empty() {
       return;
     }
@@ -150,6 +239,14 @@ impl InstallEventEmitter {
   fn target_result(&self, target: InstallTargetResult) {
     self.emit(InstallTaskEvent::target_result(&self.install_id, target));
   }
+
+  /// `write_text_file` that becomes a no-op in dry-run mode.
+  fn write_text_file(&self, path: &Path, content: &str) -> Result<(), String> {
+    if self.dry_run {
+      return Ok(());
+    }
+    write_text_file(path, content)
+  }
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -165,6 +262,12 @@ pub struct InstallTargetResult {
   pub stdout: String,
   pub stderr: String,
   pub error: Option<String>,
+  /// Non-fatal hint, e.g. the CLI is older than the version Maple's MCP setup needs.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub warning: Option<String>,
+  /// Wall-clock time the install job took, CLI detection included; unset for uninstalls.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub duration_ms: Option<u64>,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -183,6 +286,9 @@ pub struct InstallTargetProbe {
   pub cli_found: bool,
   pub installed: bool,
   pub npm_found: bool,
+  /// WSL distribution the probe ran against; `None` for native targets and the default distro.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub distro: Option<String>,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -239,6 +345,84 @@ fn run_cli(executable: &str, args: &[String], cwd: Option<&Path>) -> Result<CliO
   })
 }
 
+/// Like `run_cli`, but hands stdout/stderr chunks to `on_output(stream, text)` as
+/// they arrive so slow commands (npm-backed `mcp add`) show live progress. The
+/// returned `CliOutput` still carries the full output.
+fn run_cli_streaming(
+  executable: &str,
+  args: &[String],
+  cwd: Option<&Path>,
+  on_output: &(dyn Fn(&str, &str) + Sync),
+) -> Result<CliOutput, String> {
+  let mut command = process_utils::build_cli_command(executable, args);
+  if let Some(dir) = cwd {
+    command.current_dir(dir);
+  }
+  let mut child = command
+    .stdin(Stdio::null())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .map_err(|error| format!("执行命令失败: {error}"))?;
+  let stdout_pipe = child.stdout.take();
+  let stderr_pipe = child.stderr.take();
+
+  let (stdout, stderr) = std::thread::scope(|scope| {
+    let stdout = scope.spawn(|| forward_cli_stream(stdout_pipe, "stdout", on_output));
+    let stderr = scope.spawn(|| forward_cli_stream(stderr_pipe, "stderr", on_output));
+    (
+      stdout.join().unwrap_or_default(),
+      stderr.join().unwrap_or_default(),
+    )
+  });
+  let status = child
+    .wait()
+    .map_err(|error| format!("等待命令结束失败: {error}"))?;
+  Ok(CliOutput {
+    success: status.success(),
+    code: status.code(),
+    stdout: stdout.trim().to_string(),
+    stderr: stderr.trim().to_string(),
+  })
+}
+
+/// Reads `pipe` to the end, forwarding each chunk and returning everything read.
+/// A UTF-8 sequence split across reads is held back until it is complete.
+fn forward_cli_stream(
+  pipe: Option<impl Read>,
+  stream: &str,
+  on_output: &(dyn Fn(&str, &str) + Sync),
+) -> String {
+  let Some(mut pipe) = pipe else {
+    return String::new();
+  };
+  let mut collected = String::new();
+  let mut pending: Vec<u8> = Vec::new();
+  let mut buffer = [0u8; 4096];
+  loop {
+    let read = match pipe.read(&mut buffer) {
+      Ok(0) | Err(_) => break,
+      Ok(read) => read,
+    };
+    pending.extend_from_slice(&buffer[..read]);
+    let complete = match std::str::from_utf8(&pending) {
+      Ok(_) => pending.len(),
+      Err(error) if error.error_len().is_none() => error.valid_up_to(),
+      Err(_) => pending.len(),
+    };
+    let chunk = String::from_utf8_lossy(&pending[..complete]).into_owned();
+    pending.drain(..complete);
+    on_output(stream, &chunk);
+    collected.push_str(&chunk);
+  }
+  if !pending.is_empty() {
+    let chunk = String::from_utf8_lossy(&pending).into_owned();
+    on_output(stream, &chunk);
+    collected.push_str(&chunk);
+  }
+  collected
+}
+
 fn is_windows_cli_not_found(output: &CliOutput) -> bool {
   #[cfg(target_os = "windows")]
   {
@@ -399,7 +583,7 @@ fn detect_cli_native(executable: &str) -> bool {
   }
 }
 
-fn detect_cli_wsl(executable: &str) -> bool {
+fn detect_cli_wsl(executable: &str, distro: Option<&str>) -> bool {
   #[cfg(target_os = "windows")]
   {
     let trimmed = executable.trim();
@@ -414,13 +598,13 @@ fn detect_cli_wsl(executable: &str) -> bool {
       "p=$(command -v {} 2>/dev/null) && [ -n \"$p\" ] && case \"$p\" in /mnt/*) false;; *.exe) false;; *) true;; esac",
       sh_quote(trimmed)
     );
-    let args = vec!["-e".to_string(), "bash".to_string(), "-lic".to_string(), script];
+    let args = wsl_args(distro, vec!["-e".to_string(), "bash".to_string(), "-lic".to_string(), script]);
     return run_cli("wsl", &args, None).map(|out| out.success).unwrap_or(false);
   }
 
   #[cfg(not(target_os = "windows"))]
   {
-    let _ = executable;
+    let _ = (executable, distro);
     false
   }
 }
@@ -443,6 +627,10 @@ fn gemini_settings_has_maple_server(settings_path: &Path) -> bool {
   let Ok(raw) = fs::read_to_string(settings_path) else {
     return false;
   };
+  gemini_settings_text_has_maple_server(&raw)
+}
+
+fn gemini_settings_text_has_maple_server(raw: &str) -> bool {
   let trimmed = raw.trim();
   if trimmed.is_empty() {
     return false;
@@ -474,6 +662,69 @@ fn is_gemini_installed_native(home: &Path) -> bool {
     && gemini_settings_has_maple_server(&home.join(".gemini").join("settings.json"))
 }
 
+fn cursor_mcp_config_path(home: &Path) -> PathBuf {
+  home.join(".cursor").join("mcp.json")
+}
+
+fn is_cursor_detected_native(home: &Path) -> bool {
+  home.join(".cursor").is_dir() || detect_cli_native("cursor")
+}
+
+fn is_cursor_installed_native(home: &Path) -> bool {
+  let Ok(raw) = fs::read_to_string(cursor_mcp_config_path(home)) else {
+    return false;
+  };
+  let Some(root) = parse_json_or_jsonc_value(&raw) else {
+    return false;
+  };
+  root
+    .get("mcpServers")
+    .and_then(|servers| servers.get("maple"))
+    .and_then(|maple| maple.get("url"))
+    .and_then(|url| url.as_str())
+    .is_some()
+}
+
+/// VS Code's user `settings.json`, which holds its MCP servers under `mcp.servers`.
+fn vscode_settings_path(home: &Path) -> PathBuf {
+  #[cfg(target_os = "windows")]
+  let user_dir = std::env::var_os("APPDATA")
+    .map(PathBuf::from)
+    .unwrap_or_else(|| home.join("AppData").join("Roaming"))
+    .join("Code")
+    .join("User");
+  #[cfg(target_os = "macos")]
+  let user_dir = home.join("Library").join("Application Support").join("Code").join("User");
+  #[cfg(all(not(target_os = "macos"), not(target_os = "windows")))]
+  let user_dir = std::env::var_os("XDG_CONFIG_HOME")
+    .map(PathBuf::from)
+    .filter(|dir| dir.is_absolute())
+    .unwrap_or_else(|| home.join(".config"))
+    .join("Code")
+    .join("User");
+  user_dir.join("settings.json")
+}
+
+fn is_vscode_detected_native(home: &Path) -> bool {
+  vscode_settings_path(home).parent().is_some_and(Path::is_dir) || detect_cli_native("code")
+}
+
+fn is_vscode_installed_native(home: &Path) -> bool {
+  let Ok(raw) = fs::read_to_string(vscode_settings_path(home)) else {
+    return false;
+  };
+  let Some(root) = parse_json_or_jsonc_value(&raw) else {
+    return false;
+  };
+  root
+    .get("mcp")
+    .and_then(|mcp| mcp.get("servers"))
+    .and_then(|servers| servers.get("maple"))
+    .and_then(|maple| maple.get("url"))
+    .and_then(|url| url.as_str())
+    .is_some()
+}
+
 fn strip_jsonc_comments(input: &str) -> String {
   let chars: Vec<char> = input.chars().collect();
   let mut out = String::with_capacity(chars.len());
@@ -605,6 +856,193 @@ fn parse_json_or_jsonc_value(input: &str) -> Option<serde_json::Value> {
   serde_json::from_str::<serde_json::Value>(stripped.trim()).ok()
 }
 
+/// Skips whitespace and `//` / `/* */` comments starting at byte `i`.
+fn skip_jsonc_trivia(bytes: &[u8], mut i: usize) -> usize {
+  while i < bytes.len() {
+    match (bytes[i], bytes.get(i + 1)) {
+      (b' ' | b'\t' | b'\r' | b'\n', _) => i += 1,
+      (b'/', Some(b'/')) => {
+        while i < bytes.len() && bytes[i] != b'\n' {
+          i += 1;
+        }
+      }
+      (b'/', Some(b'*')) => {
+        i += 2;
+        while i < bytes.len() && !(bytes[i] == b'*' && bytes.get(i + 1) == Some(&b'/')) {
+          i += 1;
+        }
+        i = (i + 2).min(bytes.len());
+      }
+      _ => break,
+    }
+  }
+  i
+}
+
+/// Returns the byte just past the string literal opening at `i`.
+fn skip_jsonc_string(bytes: &[u8], mut i: usize) -> Option<usize> {
+  i += 1;
+  while i < bytes.len() {
+    match bytes[i] {
+      b'\\' => i += 2,
+      b'"' => return Some(i + 1),
+      _ => i += 1,
+    }
+  }
+  None
+}
+
+/// Returns the byte just past the JSONC value starting at `i`.
+fn skip_jsonc_value(bytes: &[u8], i: usize) -> Option<usize> {
+  match *bytes.get(i)? {
+    b'"' => skip_jsonc_string(bytes, i),
+    b'{' | b'[' => {
+      let mut depth = 0usize;
+      let mut j = i;
+      while j < bytes.len() {
+        j = skip_jsonc_trivia(bytes, j);
+        match *bytes.get(j)? {
+          b'"' => {
+            j = skip_jsonc_string(bytes, j)?;
+            continue;
+          }
+          b'{' | b'[' => depth += 1,
+          b'}' | b']' => {
+            depth -= 1;
+            if depth == 0 {
+              return Some(j + 1);
+            }
+          }
+          _ => {}
+        }
+        j += 1;
+      }
+      None
+    }
+    _ => {
+      let mut j = i;
+      while j < bytes.len() && !matches!(bytes[j], b',' | b'}' | b']' | b'/' | b' ' | b'\t' | b'\r' | b'\n') {
+        j += 1;
+      }
+      (j > i).then_some(j)
+    }
+  }
+}
+
+struct JsoncMember {
+  key: String,
+  key_start: usize,
+  value: std::ops::Range<usize>,
+}
+
+/// The members of the JSONC object opening at `open`, with their byte positions.
+fn jsonc_object_members(text: &str, open: usize) -> Option<Vec<JsoncMember>> {
+  let bytes = text.as_bytes();
+  let mut members = Vec::new();
+  let mut i = skip_jsonc_trivia(bytes, open + 1);
+  while *bytes.get(i)? != b'}' {
+    let key_start = i;
+    let key_end = skip_jsonc_string(bytes, key_start)?;
+    let key = serde_json::from_str::<String>(&text[key_start..key_end]).ok()?;
+    i = skip_jsonc_trivia(bytes, key_end);
+    if *bytes.get(i)? != b':' {
+      return None;
+    }
+    let value_start = skip_jsonc_trivia(bytes, i + 1);
+    let value_end = skip_jsonc_value(bytes, value_start)?;
+    members.push(JsoncMember { key, key_start, value: value_start..value_end });
+    i = skip_jsonc_trivia(bytes, value_end);
+    if *bytes.get(i)? == b',' {
+      i = skip_jsonc_trivia(bytes, i + 1);
+    }
+  }
+  Some(members)
+}
+
+/// The indent unit of a JSONC document, taken from its first indented line.
+fn jsonc_indent_unit(text: &str) -> String {
+  text
+    .lines()
+    .map(|line| &line[..line.len() - line.trim_start().len()])
+    .find(|indent| !indent.is_empty())
+    .unwrap_or("  ")
+    .to_string()
+}
+
+fn render_jsonc_value(value: &serde_json::Value, unit: &str, depth: usize) -> String {
+  let mut buf = Vec::new();
+  let formatter = serde_json::ser::PrettyFormatter::with_indent(unit.as_bytes());
+  let mut serializer = serde_json::Serializer::with_formatter(&mut buf, formatter);
+  let _ = value.serialize(&mut serializer);
+  String::from_utf8_lossy(&buf).replace('\n', &format!("\n{}", unit.repeat(depth)))
+}
+
+/// Sets the value at `path` in a JSONC object document by editing only that span, so
+/// comments and formatting elsewhere in the file survive. `None` when the text is not
+/// an object we can walk.
+fn splice_jsonc_value(text: &str, path: &[&str], value: &serde_json::Value) -> Option<String> {
+  let unit = jsonc_indent_unit(text);
+  let mut open = skip_jsonc_trivia(text.as_bytes(), 0);
+  if text.as_bytes().get(open) != Some(&b'{') {
+    return None;
+  }
+
+  for (depth, key) in path.iter().enumerate() {
+    let members = jsonc_object_members(text, open)?;
+    let rest = &path[depth + 1..];
+    let nested = rest.iter().rev().fold(value.clone(), |inner, key| {
+      serde_json::Value::Object(std::iter::once((key.to_string(), inner)).collect())
+    });
+    // Duplicate keys resolve to the last one, as in serde_json.
+    match members.iter().rev().find(|member| member.key == *key).map(|member| &member.value) {
+      Some(range) if !rest.is_empty() && text.as_bytes()[range.start] == b'{' => open = range.start,
+      Some(range) => {
+        let rendered = render_jsonc_value(&nested, &unit, depth + 1);
+        return Some(format!("{}{rendered}{}", &text[..range.start], &text[range.end..]));
+      }
+      None => {
+        let rendered = render_jsonc_value(&nested, &unit, depth + 1);
+        let key_json = serde_json::to_string(key).ok()?;
+        let tail = if members.is_empty() { format!("\n{}", unit.repeat(depth)) } else { ",".to_string() };
+        let member = format!("\n{}{key_json}: {rendered}{tail}", unit.repeat(depth + 1));
+        return Some(format!("{}{member}{}", &text[..=open], &text[open + 1..]));
+      }
+    }
+  }
+  None
+}
+
+/// Removes the member at `path` from a JSONC object document, editing only that span.
+/// `None` when there is no such member.
+fn remove_jsonc_member(text: &str, path: &[&str]) -> Option<String> {
+  let (last, parents) = path.split_last()?;
+  let mut open = skip_jsonc_trivia(text.as_bytes(), 0);
+  for key in parents {
+    let members = jsonc_object_members(text, open)?;
+    let range = &members.iter().rev().find(|member| member.key == *key)?.value;
+    open = range.start;
+  }
+  if text.as_bytes().get(open) != Some(&b'{') {
+    return None;
+  }
+
+  let members = jsonc_object_members(text, open)?;
+  let index = members.iter().rposition(|member| member.key == *last)?;
+  let member = &members[index];
+  let span = if let Some(next) = members.get(index + 1) {
+    member.key_start..next.key_start
+  } else if let Some(prev) = index.checked_sub(1).map(|i| &members[i]) {
+    prev.value.end..member.value.end
+  } else {
+    let bytes = text.as_bytes();
+    let after = skip_jsonc_trivia(bytes, member.value.end);
+    let end = if bytes.get(after) == Some(&b',') { after + 1 } else { member.value.end };
+    let start = if text[open + 1..member.key_start].trim().is_empty() { open + 1 } else { member.key_start };
+    start..end
+  };
+  Some(format!("{}{}", &text[..span.start], &text[span.end..]))
+}
+
 fn opencode_config_has_maple_server(root: &serde_json::Value) -> bool {
   let Some(mcp) = root.get("mcp").and_then(|v| v.as_object()) else {
     return false;
@@ -655,11 +1093,11 @@ fn is_opencode_installed_native(home: &Path) -> bool {
   false
 }
 
-fn is_opencode_installed_wsl() -> bool {
+fn is_opencode_installed_wsl(distro: Option<&str>) -> bool {
   #[cfg(target_os = "windows")]
   {
     for rel in [".config/opencode/opencode.json", ".config/opencode/opencode.jsonc"] {
-      let Ok(Some(raw)) = wsl_read_home_file(rel) else {
+      let Ok(Some(raw)) = wsl_read_home_file(rel, distro) else {
         continue;
       };
       if let Some(root) = parse_json_or_jsonc_value(&raw) {
@@ -673,57 +1111,65 @@ fn is_opencode_installed_wsl() -> bool {
 
   #[cfg(not(target_os = "windows"))]
   {
+    let _ = distro;
     false
   }
 }
 
-fn is_codex_installed_wsl() -> bool {
+fn is_codex_installed_wsl(distro: Option<&str>) -> bool {
   #[cfg(target_os = "windows")]
   {
-    wsl_home_file_exists(".codex/skills/maple/SKILL.md").unwrap_or(false)
+    wsl_home_file_exists(".codex/skills/maple/SKILL.md", distro).unwrap_or(false)
   }
 
   #[cfg(not(target_os = "windows"))]
   {
+    let _ = distro;
     false
   }
 }
 
-fn is_claude_installed_wsl() -> bool {
+fn is_claude_installed_wsl(distro: Option<&str>) -> bool {
   #[cfg(target_os = "windows")]
   {
-    wsl_home_file_exists(".claude/commands/maple.md").unwrap_or(false)
+    wsl_home_file_exists(".claude/commands/maple.md", distro).unwrap_or(false)
   }
 
   #[cfg(not(target_os = "windows"))]
   {
+    let _ = distro;
     false
   }
 }
 
-fn is_iflow_installed_wsl() -> bool {
+fn is_iflow_installed_wsl(distro: Option<&str>) -> bool {
   #[cfg(target_os = "windows")]
   {
-    wsl_home_file_exists(".iflow/workflows/maple.md").unwrap_or(false)
-      && wsl_home_file_exists(".iflow/skills/maple/SKILL.md").unwrap_or(false)
-      && wsl_home_file_exists(".iflow/commands/maple.toml").unwrap_or(false)
+    wsl_home_file_exists(".iflow/workflows/maple.md", distro).unwrap_or(false)
+      && wsl_home_file_exists(".iflow/skills/maple/SKILL.md", distro).unwrap_or(false)
+      && wsl_home_file_exists(".iflow/commands/maple.toml", distro).unwrap_or(false)
   }
 
   #[cfg(not(target_os = "windows"))]
   {
+    let _ = distro;
     false
   }
 }
 
-fn is_gemini_installed_wsl() -> bool {
+fn is_gemini_installed_wsl(distro: Option<&str>) -> bool {
   #[cfg(target_os = "windows")]
   {
-    wsl_home_file_exists(".gemini/commands/maple.toml").unwrap_or(false)
-      && wsl_home_file_exists(".gemini/settings.json").unwrap_or(false)
+    wsl_home_file_exists(".gemini/commands/maple.toml", distro).unwrap_or(false)
+      && wsl_read_home_file(".gemini/settings.json", distro)
+        .ok()
+        .flatten()
+        .is_some_and(|raw| gemini_settings_text_has_maple_server(&raw))
   }
 
   #[cfg(not(target_os = "windows"))]
   {
+    let _ = distro;
     false
   }
 }
@@ -751,10 +1197,13 @@ fn write_install_probe_cache(cache: &HashMap<String, InstallTargetProbe>) {
   let _ = fs::write(path, payload);
 }
 
-pub fn probe_install_targets() -> Result<Vec<InstallTargetProbe>, String> {
-  fn cache_ok(probe: &InstallTargetProbe) -> bool {
-    probe.installed && probe.cli_found
-  }
+pub fn probe_install_targets(wsl_distro: Option<String>) -> Result<Vec<InstallTargetProbe>, String> {
+  let wsl_distro = normalize_wsl_distro(wsl_distro.as_deref());
+  let distro = wsl_distro.as_deref();
+  let cache_ok = |probe: &InstallTargetProbe| {
+    let expected_distro = if probe.runtime == "wsl" { distro } else { None };
+    probe.installed && probe.cli_found && probe.distro.as_deref() == expected_distro
+  };
 
   let mut cache = read_install_probe_cache();
   let home = maple_fs::user_home_dir()?;
@@ -763,19 +1212,23 @@ pub fn probe_install_targets() -> Result<Vec<InstallTargetProbe>, String> {
   let mut npm_native: Option<bool> = None;
   let mut npm_wsl: Option<bool> = None;
 
-  const NATIVE_ORDER: [(&str, &str); 5] = [
+  const NATIVE_ORDER: [(&str, &str); 7] = [
     ("codex", "native"),
     ("claude", "native"),
     ("iflow", "native"),
     ("gemini", "native"),
     ("opencode", "native"),
+    ("cursor", "native"),
+    ("vscode", "native"),
   ];
-  const FULL_ORDER: [(&str, &str); 10] = [
+  const FULL_ORDER: [(&str, &str); 12] = [
     ("codex", "native"),
     ("claude", "native"),
     ("iflow", "native"),
     ("gemini", "native"),
     ("opencode", "native"),
+    ("cursor", "native"),
+    ("vscode", "native"),
     ("wsl:codex", "wsl"),
     ("wsl:claude", "wsl"),
     ("wsl:iflow", "wsl"),
@@ -799,7 +1252,7 @@ pub fn probe_install_targets() -> Result<Vec<InstallTargetProbe>, String> {
     }
 
     let npm_found = if runtime == "wsl" {
-      *npm_wsl.get_or_insert_with(|| detect_cli_wsl("npm"))
+      *npm_wsl.get_or_insert_with(|| detect_cli_wsl("npm", distro))
     } else {
       *npm_native.get_or_insert_with(|| detect_cli_native("npm"))
     };
@@ -810,11 +1263,13 @@ pub fn probe_install_targets() -> Result<Vec<InstallTargetProbe>, String> {
       "iflow" => (detect_cli_native("iflow"), is_iflow_installed_native(&home)),
       "gemini" => (detect_cli_native("gemini"), is_gemini_installed_native(&home)),
       "opencode" => (detect_cli_native("opencode"), is_opencode_installed_native(&home)),
-      "wsl:codex" => (detect_cli_wsl("codex"), is_codex_installed_wsl()),
-      "wsl:claude" => (detect_cli_wsl("claude"), is_claude_installed_wsl()),
-      "wsl:iflow" => (detect_cli_wsl("iflow"), is_iflow_installed_wsl()),
-      "wsl:gemini" => (detect_cli_wsl("gemini"), is_gemini_installed_wsl()),
-      "wsl:opencode" => (detect_cli_wsl("opencode"), is_opencode_installed_wsl()),
+      "cursor" => (is_cursor_detected_native(&home), is_cursor_installed_native(&home)),
+      "vscode" => (is_vscode_detected_native(&home), is_vscode_installed_native(&home)),
+      "wsl:codex" => (detect_cli_wsl("codex", distro), is_codex_installed_wsl(distro)),
+      "wsl:claude" => (detect_cli_wsl("claude", distro), is_claude_installed_wsl(distro)),
+      "wsl:iflow" => (detect_cli_wsl("iflow", distro), is_iflow_installed_wsl(distro)),
+      "wsl:gemini" => (detect_cli_wsl("gemini", distro), is_gemini_installed_wsl(distro)),
+      "wsl:opencode" => (detect_cli_wsl("opencode", distro), is_opencode_installed_wsl(distro)),
       _ => (false, false),
     };
 
@@ -824,6 +1279,7 @@ pub fn probe_install_targets() -> Result<Vec<InstallTargetProbe>, String> {
       cli_found,
       installed,
       npm_found,
+      distro: if runtime == "wsl" { wsl_distro.clone() } else { None },
     };
 
     probes.push(probe.clone());
@@ -838,6 +1294,18 @@ pub fn probe_install_targets() -> Result<Vec<InstallTargetProbe>, String> {
   Ok(probes)
 }
 
+fn normalize_wsl_distro(distro: Option<&str>) -> Option<String> {
+  distro.map(str::trim).filter(|value| !value.is_empty()).map(str::to_string)
+}
+
+/// Prefixes `wsl` arguments with `-d <distro>` when a specific distribution is targeted.
+fn wsl_args(distro: Option<&str>, args: Vec<String>) -> Vec<String> {
+  match distro {
+    Some(distro) => [vec!["-d".to_string(), distro.to_string()], args].concat(),
+    None => args,
+  }
+}
+
 fn normalize_home_relative_path(path: &str) -> Result<String, String> {
   let trimmed = path.trim();
   if trimmed.is_empty() {
@@ -849,10 +1317,10 @@ fn normalize_home_relative_path(path: &str) -> Result<String, String> {
     .to_string())
 }
 
-fn wsl_read_home_file(path: &str) -> Result<Option<String>, String> {
+fn wsl_read_home_file(path: &str, distro: Option<&str>) -> Result<Option<String>, String> {
   let rel = normalize_home_relative_path(path)?;
   let script = format!("set -e; if [ -f \"$HOME/{rel}\" ]; then cat \"$HOME/{rel}\"; fi");
-  let args = vec!["-e".to_string(), "sh".to_string(), "-lc".to_string(), script];
+  let args = wsl_args(distro, vec!["-e".to_string(), "sh".to_string(), "-lc".to_string(), script]);
   match run_cli("wsl", &args, None) {
     Ok(out) => {
       if !out.success {
@@ -894,15 +1362,16 @@ fn wsl_write_home_file(
     )
   };
 
-  let args = vec![
-    "-e".to_string(),
-    "sh".to_string(),
-    "-lc".to_string(),
-    script,
-  ];
+  let args = wsl_args(
+    emitter.wsl_distro.as_deref(),
+    vec!["-e".to_string(), "sh".to_string(), "-lc".to_string(), script],
+  );
 
   let pretty = format!("wsl:~/{rel}");
   emitter.log(Some(target_id), "info", format!("写入 {pretty}\n"));
+  if emitter.dry_run {
+    return Ok(pretty);
+  }
   match run_cli("wsl", &args, None) {
     Ok(out) => {
       if out.success {
@@ -920,10 +1389,11 @@ fn wsl_write_home_file(
   }
 }
 
-fn wsl_home_file_exists(path: &str) -> Result<bool, String> {
+#[cfg(target_os = "windows")]
+fn wsl_home_file_exists(path: &str, distro: Option<&str>) -> Result<bool, String> {
   let rel = normalize_home_relative_path(path)?;
   let script = format!("test -f \"$HOME/{rel}\"");
-  let args = vec!["-e".to_string(), "sh".to_string(), "-lc".to_string(), script];
+  let args = wsl_args(distro, vec!["-e".to_string(), "sh".to_string(), "-lc".to_string(), script]);
   Ok(run_cli("wsl", &args, None).map(|out| out.success).unwrap_or(false))
 }
 
@@ -1096,9 +1566,23 @@ fn run_registration_commands(
 ) -> (Option<bool>, bool, String, String, Option<String>) {
   let mut stdout = String::new();
   let mut stderr = String::new();
+  let (remove_args, add_args) = if executable == "wsl" {
+    let distro = emitter.wsl_distro.as_deref();
+    (wsl_args(distro, remove_args), wsl_args(distro, add_args))
+  } else {
+    (remove_args, add_args)
+  };
+
+  if emitter.dry_run {
+    emitter.log_command(target_id, executable, &remove_args);
+    emitter.log_command(target_id, executable, &add_args);
+    return (Some(true), true, stdout, stderr, None);
+  }
+
+  let forward = |stream: &str, chunk: &str| emitter.log(Some(target_id), stream, chunk);
 
   emitter.log_command(target_id, executable, &remove_args);
-  let remove_out = run_cli(executable, &remove_args, None);
+  let remove_out = run_cli_streaming(executable, &remove_args, None, &forward);
   match remove_out {
     Ok(out) => {
       if is_windows_cli_not_found(&out) {
@@ -1106,12 +1590,10 @@ fn run_registration_commands(
         return (Some(false), false, out.stdout, out.stderr, None);
       }
       if !out.stdout.is_empty() {
-        emitter.log(Some(target_id), "stdout", format!("{}\n", out.stdout.trim_end()));
         stdout.push_str(&out.stdout);
         stdout.push('\n');
       }
       if !out.stderr.is_empty() {
-        emitter.log(Some(target_id), "stderr", format!("{}\n", out.stderr.trim_end()));
         stderr.push_str(&out.stderr);
         stderr.push('\n');
       }
@@ -1133,7 +1615,7 @@ fn run_registration_commands(
   }
 
   emitter.log_command(target_id, executable, &add_args);
-  let add_out = run_cli(executable, &add_args, None);
+  let add_out = run_cli_streaming(executable, &add_args, None, &forward);
   match add_out {
     Ok(out) => {
       if is_windows_cli_not_found(&out) {
@@ -1141,12 +1623,10 @@ fn run_registration_commands(
         return (Some(false), false, out.stdout, out.stderr, None);
       }
       if !out.stdout.is_empty() {
-        emitter.log(Some(target_id), "stdout", format!("{}\n", out.stdout.trim_end()));
         stdout.push_str(&out.stdout);
         stdout.push('\n');
       }
       if !out.stderr.is_empty() {
-        emitter.log(Some(target_id), "stderr", format!("{}\n", out.stderr.trim_end()));
         stderr.push_str(&out.stderr);
         stderr.push('\n');
       }
@@ -1200,6 +1680,143 @@ fn run_registration_commands(
   }
 }
 
+/// With `skip_if_installed`, returns a skipped result when the skill files are in place,
+/// the recorded skills version is current and `<cli> mcp get maple` already points at
+/// our URL. Anything uncertain falls through to a full install.
+fn skip_if_already_installed(
+  home: &Path,
+  emitter: &InstallEventEmitter,
+  runtime: InstallRuntime,
+  target_id: &str,
+  cli: &str,
+) -> Option<InstallTargetResult> {
+  if !emitter.skip_if_installed {
+    return None;
+  }
+  let distro = emitter.wsl_distro.as_deref();
+  let files_present = match (cli, runtime) {
+    ("codex", InstallRuntime::Native) => is_codex_installed_native(home),
+    ("codex", InstallRuntime::Wsl) => is_codex_installed_wsl(distro),
+    ("claude", InstallRuntime::Native) => is_claude_installed_native(home),
+    ("claude", InstallRuntime::Wsl) => is_claude_installed_wsl(distro),
+    ("iflow", InstallRuntime::Native) => is_iflow_installed_native(home),
+    ("iflow", InstallRuntime::Wsl) => is_iflow_installed_wsl(distro),
+    _ => false,
+  };
+  if !files_present || read_install_meta().skills_version != Some(SKILLS_VERSION) {
+    return None;
+  }
+
+  let (executable, args): (&str, Vec<String>) = match runtime {
+    InstallRuntime::Native => (cli, vec!["mcp".into(), "get".into(), "maple".into()]),
+    InstallRuntime::Wsl => (
+      "wsl",
+      wsl_args(distro, vec!["-e".into(), "bash".into(), "-lc".into(), format!("{cli} mcp get maple")]),
+    ),
+  };
+  emitter.log_command(target_id, executable, &args);
+  let out = run_cli(executable, &args, None).ok()?;
+  if !out.success || !(out.stdout.contains(&emitter.mcp_url) || out.stderr.contains(&emitter.mcp_url)) {
+    return None;
+  }
+
+  let reason = format!("{cli} 已安装 Maple skills 且 MCP 已指向 {}，已跳过。\n", emitter.mcp_url);
+  emitter.log(Some(target_id), "info", reason.clone());
+  emitter.target_state(target_id, "success");
+  Some(InstallTargetResult {
+    id: target_id.to_string(),
+    runtime: Some(runtime.as_str().to_string()),
+    success: true,
+    skipped: true,
+    cli_found: Some(true),
+    written_files: Vec::new(),
+    stdout: reason,
+    stderr: String::new(),
+    error: None,
+    warning: None,
+    duration_ms: None,
+  })
+}
+
+/// Runs `<cli> --version` and logs a warning when it is older than the version
+/// `worker_probe` knows to support HTTP MCP. The install proceeds either way; the
+/// warning is there to explain a later `mcp add` failure.
+fn warn_if_outdated_cli(
+  emitter: &InstallEventEmitter,
+  target_id: &str,
+  runtime: InstallRuntime,
+  cli: &str,
+) -> Option<String> {
+  let minimum = worker_probe::minimum_version(cli)?;
+  let out = match runtime {
+    InstallRuntime::Native => run_cli(cli, &["--version".to_string()], None),
+    InstallRuntime::Wsl => run_cli(
+      "wsl",
+      &wsl_args(
+        emitter.wsl_distro.as_deref(),
+        vec!["-e".into(), "bash".into(), "-lic".into(), format!("{cli} --version")],
+      ),
+      None,
+    ),
+  }
+  .ok()?;
+  let version = worker_probe::parse_version(&out.stdout).or_else(|| worker_probe::parse_version(&out.stderr))?;
+  if version >= minimum {
+    return None;
+  }
+  let warning = format!(
+    "{cli} 版本 {} 低于 Maple 所需的最低版本 {}，可能不支持 HTTP MCP，注册失败时请先升级。",
+    worker_probe::format_version(version),
+    worker_probe::format_version(minimum)
+  );
+  emitter.log(Some(target_id), "warning", format!("{warning}\n"));
+  Some(warning)
+}
+
+/// Some CLIs exit 0 from `mcp add` without persisting the server, so confirm that
+/// `mcp list` shows `maple` pointing at our URL.
+fn verify_mcp_registration(
+  emitter: &InstallEventEmitter,
+  target_id: &str,
+  runtime: InstallRuntime,
+  cli: &str,
+  registered: bool,
+  reg_error: Option<String>,
+) -> Option<String> {
+  if !registered || reg_error.is_some() || emitter.dry_run {
+    return reg_error;
+  }
+  let (executable, args): (&str, Vec<String>) = match runtime {
+    InstallRuntime::Native => (cli, vec!["mcp".into(), "list".into()]),
+    InstallRuntime::Wsl => (
+      "wsl",
+      wsl_args(
+        emitter.wsl_distro.as_deref(),
+        vec!["-e".into(), "bash".into(), "-lc".into(), format!("{cli} mcp list")],
+      ),
+    ),
+  };
+  emitter.log_command(target_id, executable, &args);
+  let url = emitter.mcp_url.clone();
+  let error = match run_cli(executable, &args, None) {
+    Ok(out) => {
+      let listed = out
+        .stdout
+        .lines()
+        .chain(out.stderr.lines())
+        .any(|line| line.contains("maple") && line.contains(&url));
+      if listed {
+        emitter.log(Some(target_id), "info", "已确认 MCP 注册生效。\n".to_string());
+        return None;
+      }
+      format!("`{cli} mcp add` 返回成功，但 `{cli} mcp list` 中未找到指向 {url} 的 maple，注册可能未生效。")
+    }
+    Err(error) => format!("MCP 注册验证失败：{error}"),
+  };
+  emitter.log(Some(target_id), "stderr", format!("{error}\n"));
+  Some(error)
+}
+
 fn install_codex(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRuntime, target_id: &str) -> InstallTargetResult {
   let mut written_files = Vec::new();
   let mut stdout = String::new();
@@ -1208,7 +1825,7 @@ fn install_codex(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRun
   emitter.target_state(target_id, "running");
   let cli_detected = match runtime {
     InstallRuntime::Native => detect_cli_native("codex"),
-    InstallRuntime::Wsl => detect_cli_wsl("codex"),
+    InstallRuntime::Wsl => detect_cli_wsl("codex", emitter.wsl_distro.as_deref()),
   };
   if !cli_detected {
     let scope = if runtime == InstallRuntime::Native { "本机" } else { "WSL" };
@@ -1224,13 +1841,21 @@ fn install_codex(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRun
       stdout,
       stderr,
       error: None,
+      warning: None,
+      duration_ms: None,
     };
   }
 
+  if let Some(result) = skip_if_already_installed(home, emitter, runtime, target_id, "codex") {
+    return result;
+  }
+
+  let version_warning = warn_if_outdated_cli(emitter, target_id, runtime, "codex");
+
   if runtime == InstallRuntime::Native {
     let skill_path = home.join(".codex").join("skills").join("maple").join("SKILL.md");
     emitter.log(Some(target_id), "info", format!("写入 {}\n", pretty_path(&skill_path)));
-    if let Err(error) = write_text_file(&skill_path, codex_skill_md()) {
+    if let Err(error) = emitter.write_text_file(&skill_path, codex_skill_md()) {
       emitter.target_state(target_id, "error");
       emitter.log(Some(target_id), "stderr", format!("{error}\n"));
       return InstallTargetResult {
@@ -1243,6 +1868,8 @@ fn install_codex(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRun
         stdout,
         stderr,
         error: Some(error),
+        warning: version_warning.clone(),
+        duration_ms: None,
       };
     }
     written_files.push(pretty_path(&skill_path));
@@ -1252,15 +1879,19 @@ fn install_codex(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRun
       target_id,
       "codex",
       vec!["mcp".into(), "remove".into(), "maple".into(), "--scope".into(), "user".into()],
-      vec![
-        "mcp".into(),
-        "add".into(),
-        "maple".into(),
-        "--url".into(),
-        MAPLE_MCP_URL.into(),
-        "--scope".into(),
-        "user".into(),
-      ],
+      [
+        vec![
+          "mcp".into(),
+          "add".into(),
+          "maple".into(),
+          "--url".into(),
+          emitter.mcp_url.clone(),
+          "--scope".into(),
+          "user".into(),
+        ],
+        maple_mcp_codex_token_args(),
+      ]
+      .concat(),
     );
 
     if !registered {
@@ -1279,13 +1910,11 @@ fn install_codex(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRun
           target_id,
           "codex",
           vec!["mcp".into(), "remove".into(), "maple".into()],
-          vec![
-            "mcp".into(),
-            "add".into(),
-            "maple".into(),
-            "--url".into(),
-            MAPLE_MCP_URL.into(),
-          ],
+          [
+            vec!["mcp".into(), "add".into(), "maple".into(), "--url".into(), emitter.mcp_url.clone()],
+            maple_mcp_codex_token_args(),
+          ]
+          .concat(),
         );
       }
     }
@@ -1305,9 +1934,12 @@ fn install_codex(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRun
         stdout,
         stderr,
         error: Some("未检测到 CLI：codex（本机）".to_string()),
+        warning: version_warning.clone(),
+        duration_ms: None,
       };
     }
 
+    let reg_error = verify_mcp_registration(emitter, target_id, runtime, "codex", registered, reg_error);
     let result = InstallTargetResult {
       id: target_id.to_string(),
       runtime: Some(runtime.as_str().to_string()),
@@ -1318,6 +1950,8 @@ fn install_codex(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRun
       stdout,
       stderr,
       error: reg_error,
+      warning: version_warning.clone(),
+      duration_ms: None,
     };
     emitter.target_state(target_id, if result.success && result.error.is_none() { "success" } else { "error" });
     return result;
@@ -1338,6 +1972,8 @@ fn install_codex(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRun
         stdout,
         stderr,
         error: Some(error),
+        warning: version_warning.clone(),
+        duration_ms: None,
       };
     }
   }
@@ -1351,7 +1987,14 @@ fn install_codex(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRun
       "-e".into(),
       "bash".into(),
       "-lc".into(),
-      format!("codex mcp add maple --url {}", MAPLE_MCP_URL),
+      format!(
+        "codex mcp add maple --url {}{}",
+        sh_quote(&emitter.mcp_url),
+        maple_mcp_codex_token_args()
+          .iter()
+          .map(|arg| format!(" {arg}"))
+          .collect::<String>()
+      ),
     ],
   );
   stdout = out;
@@ -1369,9 +2012,12 @@ fn install_codex(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRun
       stdout,
       stderr,
       error: Some("未检测到 CLI：codex（WSL）".to_string()),
+      warning: version_warning.clone(),
+      duration_ms: None,
     };
   }
 
+  let reg_error = verify_mcp_registration(emitter, target_id, runtime, "codex", registered, reg_error);
   let result = InstallTargetResult {
     id: target_id.to_string(),
     runtime: Some(runtime.as_str().to_string()),
@@ -1382,6 +2028,8 @@ fn install_codex(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRun
     stdout,
     stderr,
     error: reg_error,
+    warning: version_warning.clone(),
+    duration_ms: None,
   };
   emitter.target_state(target_id, if result.success && result.error.is_none() { "success" } else { "error" });
   result
@@ -1395,7 +2043,7 @@ fn install_claude(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRu
   emitter.target_state(target_id, "running");
   let cli_detected = match runtime {
     InstallRuntime::Native => detect_cli_native("claude"),
-    InstallRuntime::Wsl => detect_cli_wsl("claude"),
+    InstallRuntime::Wsl => detect_cli_wsl("claude", emitter.wsl_distro.as_deref()),
   };
   if !cli_detected {
     let scope = if runtime == InstallRuntime::Native { "本机" } else { "WSL" };
@@ -1411,13 +2059,21 @@ fn install_claude(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRu
       stdout,
       stderr,
       error: None,
+      warning: None,
+      duration_ms: None,
     };
   }
 
+  if let Some(result) = skip_if_already_installed(home, emitter, runtime, target_id, "claude") {
+    return result;
+  }
+
+  let version_warning = warn_if_outdated_cli(emitter, target_id, runtime, "claude");
+
   if runtime == InstallRuntime::Native {
     let command_path = home.join(".claude").join("commands").join("maple.md");
     emitter.log(Some(target_id), "info", format!("写入 {}\n", pretty_path(&command_path)));
-    if let Err(error) = write_text_file(&command_path, claude_command_md()) {
+    if let Err(error) = emitter.write_text_file(&command_path, claude_command_md()) {
       emitter.target_state(target_id, "error");
       emitter.log(Some(target_id), "stderr", format!("{error}\n"));
       return InstallTargetResult {
@@ -1430,6 +2086,8 @@ fn install_claude(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRu
         stdout,
         stderr,
         error: Some(error),
+        warning: version_warning.clone(),
+        duration_ms: None,
       };
     }
     written_files.push(pretty_path(&command_path));
@@ -1439,16 +2097,19 @@ fn install_claude(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRu
       target_id,
       "claude",
       vec!["mcp".into(), "remove".into(), "maple".into(), "--scope".into(), "user".into()],
-      vec![
-        "mcp".into(),
-        "add".into(),
-        "--scope".into(),
-        "user".into(),
-        "--transport".into(),
-        "http".into(),
-        "maple".into(),
-        MAPLE_MCP_URL.into(),
-      ],
+      [
+        vec![
+          "mcp".into(),
+          "add".into(),
+          "--scope".into(),
+          "user".into(),
+          "--transport".into(),
+          "http".into(),
+        ],
+        maple_mcp_header_args(),
+        vec!["maple".into(), emitter.mcp_url.clone()],
+      ]
+      .concat(),
     );
     stdout = out;
     stderr = err;
@@ -1465,9 +2126,12 @@ fn install_claude(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRu
         stdout,
         stderr,
         error: Some("未检测到 CLI：claude（本机）".to_string()),
+        warning: version_warning.clone(),
+        duration_ms: None,
       };
     }
 
+    let reg_error = verify_mcp_registration(emitter, target_id, runtime, "claude", registered, reg_error);
     let result = InstallTargetResult {
       id: target_id.to_string(),
       runtime: Some(runtime.as_str().to_string()),
@@ -1478,6 +2142,8 @@ fn install_claude(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRu
       stdout,
       stderr,
       error: reg_error,
+      warning: version_warning.clone(),
+      duration_ms: None,
     };
     emitter.target_state(target_id, if result.success && result.error.is_none() { "success" } else { "error" });
     return result;
@@ -1498,6 +2164,8 @@ fn install_claude(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRu
         stdout,
         stderr,
         error: Some(error),
+        warning: version_warning.clone(),
+        duration_ms: None,
       };
     }
   }
@@ -1516,7 +2184,11 @@ fn install_claude(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRu
       "-e".into(),
       "bash".into(),
       "-lc".into(),
-      format!("claude mcp add --scope user --transport http maple {}", MAPLE_MCP_URL),
+      format!(
+        "claude mcp add --scope user --transport http{} maple {}",
+        maple_mcp_header_shell_args(),
+        sh_quote(&emitter.mcp_url)
+      ),
     ],
   );
   stdout = out;
@@ -1534,9 +2206,12 @@ fn install_claude(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRu
       stdout,
       stderr,
       error: Some("未检测到 CLI：claude（WSL）".to_string()),
+      warning: version_warning.clone(),
+      duration_ms: None,
     };
   }
 
+  let reg_error = verify_mcp_registration(emitter, target_id, runtime, "claude", registered, reg_error);
   let result = InstallTargetResult {
     id: target_id.to_string(),
     runtime: Some(runtime.as_str().to_string()),
@@ -1547,11 +2222,41 @@ fn install_claude(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRu
     stdout,
     stderr,
     error: reg_error,
+    warning: version_warning.clone(),
+    duration_ms: None,
   };
   emitter.target_state(target_id, if result.success && result.error.is_none() { "success" } else { "error" });
   result
 }
 
+/// Install targets run in parallel; serializes the read-modify-write of the shared
+/// `.iflow/skills/SKILL.md` index.
+static SKILL_INDEX_LOCK: Mutex<()> = Mutex::new(());
+
+const IFLOW_SKILL_INDEX_MD: &str = r#"---
+name: maple
+description: "Project-local maple skill index."
+---
+
+# maple
+
+Use `~/.iflow/skills/maple/SKILL.md` for the full maple execution skill.
+"#;
+
+const IFLOW_SKILL_INDEX_SECTION: &str = "# maple\n\nUse `~/.iflow/skills/maple/SKILL.md` for the full maple execution skill.\n";
+
+/// Returns the index content to write, or `None` when `existing` already has a
+/// `# maple` section. Another tool's index keeps its content; Maple's section is appended.
+fn merge_iflow_skill_index(existing: Option<&str>) -> Option<String> {
+  let Some(existing) = existing.filter(|text| !text.trim().is_empty()) else {
+    return Some(IFLOW_SKILL_INDEX_MD.to_string());
+  };
+  if existing.lines().any(|line| line.trim().eq_ignore_ascii_case("# maple")) {
+    return None;
+  }
+  Some(format!("{}\n\n{IFLOW_SKILL_INDEX_SECTION}", existing.trim_end()))
+}
+
 fn install_iflow(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRuntime, target_id: &str) -> InstallTargetResult {
   let mut written_files = Vec::new();
   let mut stdout = String::new();
@@ -1560,7 +2265,7 @@ fn install_iflow(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRun
   emitter.target_state(target_id, "running");
   let cli_detected = match runtime {
     InstallRuntime::Native => detect_cli_native("iflow"),
-    InstallRuntime::Wsl => detect_cli_wsl("iflow"),
+    InstallRuntime::Wsl => detect_cli_wsl("iflow", emitter.wsl_distro.as_deref()),
   };
   if !cli_detected {
     let scope = if runtime == InstallRuntime::Native { "本机" } else { "WSL" };
@@ -1576,23 +2281,19 @@ fn install_iflow(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRun
       stdout,
       stderr,
       error: None,
+      warning: None,
+      duration_ms: None,
     };
   }
 
-  let index_md = r#"---
-name: maple
-description: "Project-local maple skill index."
----
-
-# maple
-
-Use `~/.iflow/skills/maple/SKILL.md` for the full maple execution skill.
-"#;
+  if let Some(result) = skip_if_already_installed(home, emitter, runtime, target_id, "iflow") {
+    return result;
+  }
 
   if runtime == InstallRuntime::Native {
     let workflow_path = home.join(".iflow").join("workflows").join("maple.md");
     emitter.log(Some(target_id), "info", format!("写入 {}\n", pretty_path(&workflow_path)));
-    if let Err(error) = write_text_file(&workflow_path, iflow_workflow_md()) {
+    if let Err(error) = emitter.write_text_file(&workflow_path, iflow_workflow_md()) {
       emitter.target_state(target_id, "error");
       emitter.log(Some(target_id), "stderr", format!("{error}\n"));
       return InstallTargetResult {
@@ -1605,13 +2306,15 @@ Use `~/.iflow/skills/maple/SKILL.md` for the full maple execution skill.
         stdout,
         stderr,
         error: Some(error),
+        warning: None,
+        duration_ms: None,
       };
     }
     written_files.push(pretty_path(&workflow_path));
 
     let command_path = home.join(".iflow").join("commands").join("maple.toml");
     emitter.log(Some(target_id), "info", format!("写入 {}\n", pretty_path(&command_path)));
-    if let Err(error) = write_text_file(&command_path, iflow_command_toml()) {
+    if let Err(error) = emitter.write_text_file(&command_path, iflow_command_toml()) {
       emitter.target_state(target_id, "error");
       emitter.log(Some(target_id), "stderr", format!("{error}\n"));
       return InstallTargetResult {
@@ -1624,13 +2327,15 @@ Use `~/.iflow/skills/maple/SKILL.md` for the full maple execution skill.
         stdout,
         stderr,
         error: Some(error),
+        warning: None,
+        duration_ms: None,
       };
     }
     written_files.push(pretty_path(&command_path));
 
     let skill_path = home.join(".iflow").join("skills").join("maple").join("SKILL.md");
     emitter.log(Some(target_id), "info", format!("写入 {}\n", pretty_path(&skill_path)));
-    if let Err(error) = write_text_file(&skill_path, iflow_skill_md()) {
+    if let Err(error) = emitter.write_text_file(&skill_path, iflow_skill_md()) {
       emitter.target_state(target_id, "error");
       emitter.log(Some(target_id), "stderr", format!("{error}\n"));
       return InstallTargetResult {
@@ -1643,15 +2348,22 @@ Use `~/.iflow/skills/maple/SKILL.md` for the full maple execution skill.
         stdout,
         stderr,
         error: Some(error),
+        warning: None,
+        duration_ms: None,
       };
     }
     written_files.push(pretty_path(&skill_path));
 
-    // Only create the skills index if it doesn't exist to avoid overwriting user content.
     let skill_index_path = home.join(".iflow").join("skills").join("SKILL.md");
-    if !skill_index_path.exists() {
+    let index_guard = SKILL_INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let existing_index = fs::read_to_string(&skill_index_path).ok();
+    let merged_index = merge_iflow_skill_index(existing_index.as_deref());
+    if merged_index.is_none() {
+      emitter.log(Some(target_id), "info", format!("{} 已包含 maple 条目，跳过。\n", pretty_path(&skill_index_path)));
+    }
+    if let Some(index_md) = merged_index {
       emitter.log(Some(target_id), "info", format!("写入 {}\n", pretty_path(&skill_index_path)));
-      if let Err(error) = write_text_file(&skill_index_path, index_md) {
+      if let Err(error) = emitter.write_text_file(&skill_index_path, &index_md) {
         emitter.target_state(target_id, "error");
         emitter.log(Some(target_id), "stderr", format!("{error}\n"));
         return InstallTargetResult {
@@ -1664,26 +2376,32 @@ Use `~/.iflow/skills/maple/SKILL.md` for the full maple execution skill.
           stdout,
           stderr,
           error: Some(error),
+          warning: None,
+          duration_ms: None,
         };
       }
       written_files.push(pretty_path(&skill_index_path));
     }
+    drop(index_guard);
 
     let (cli_found, registered, out, err, reg_error) = run_registration_commands(
       emitter,
       target_id,
       "iflow",
       vec!["mcp".into(), "remove".into(), "maple".into()],
-      vec![
-        "mcp".into(),
-        "add".into(),
-        "--scope".into(),
-        "user".into(),
-        "--transport".into(),
-        "http".into(),
-        "maple".into(),
-        MAPLE_MCP_URL.into(),
-      ],
+      [
+        vec![
+          "mcp".into(),
+          "add".into(),
+          "--scope".into(),
+          "user".into(),
+          "--transport".into(),
+          "http".into(),
+        ],
+        maple_mcp_header_args(),
+        vec!["maple".into(), emitter.mcp_url.clone()],
+      ]
+      .concat(),
     );
     stdout = out;
     stderr = err;
@@ -1700,9 +2418,12 @@ Use `~/.iflow/skills/maple/SKILL.md` for the full maple execution skill.
         stdout,
         stderr,
         error: Some("未检测到 CLI：iflow（本机）".to_string()),
+        warning: None,
+        duration_ms: None,
       };
     }
 
+    let reg_error = verify_mcp_registration(emitter, target_id, runtime, "iflow", registered, reg_error);
     let result = InstallTargetResult {
       id: target_id.to_string(),
       runtime: Some(runtime.as_str().to_string()),
@@ -1713,6 +2434,8 @@ Use `~/.iflow/skills/maple/SKILL.md` for the full maple execution skill.
       stdout,
       stderr,
       error: reg_error,
+      warning: None,
+      duration_ms: None,
     };
     emitter.target_state(target_id, if result.success && result.error.is_none() { "success" } else { "error" });
     return result;
@@ -1733,6 +2456,8 @@ Use `~/.iflow/skills/maple/SKILL.md` for the full maple execution skill.
         stdout,
         stderr,
         error: Some(error),
+        warning: None,
+        duration_ms: None,
       };
     }
   }
@@ -1752,6 +2477,8 @@ Use `~/.iflow/skills/maple/SKILL.md` for the full maple execution skill.
         stdout,
         stderr,
         error: Some(error),
+        warning: None,
+        duration_ms: None,
       };
     }
   }
@@ -1771,14 +2498,23 @@ Use `~/.iflow/skills/maple/SKILL.md` for the full maple execution skill.
         stdout,
         stderr,
         error: Some(error),
+        warning: None,
+        duration_ms: None,
       };
     }
   }
 
-  // Only create the skills index if it doesn't exist to avoid overwriting user content.
-  let index_exists = wsl_home_file_exists(".iflow/skills/SKILL.md").unwrap_or(true);
-  if !index_exists {
-    match wsl_write_home_file(emitter, target_id, ".iflow/skills/SKILL.md", index_md) {
+  let index_guard = SKILL_INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+  let merged_index = match wsl_read_home_file(".iflow/skills/SKILL.md", emitter.wsl_distro.as_deref()) {
+    Ok(existing) => merge_iflow_skill_index(existing.as_deref()),
+    Err(error) => {
+      // Without the current content an append could clobber it, so leave the index alone.
+      emitter.log(Some(target_id), "stderr", format!("读取 wsl:~/.iflow/skills/SKILL.md 失败，跳过索引更新：{error}\n"));
+      None
+    }
+  };
+  if let Some(index_md) = merged_index {
+    match wsl_write_home_file(emitter, target_id, ".iflow/skills/SKILL.md", &index_md) {
       Ok(path) => written_files.push(path),
       Err(error) => {
         emitter.target_state(target_id, "error");
@@ -1793,10 +2529,13 @@ Use `~/.iflow/skills/maple/SKILL.md` for the full maple execution skill.
           stdout,
           stderr,
           error: Some(error),
+          warning: None,
+          duration_ms: None,
         };
       }
     }
   }
+  drop(index_guard);
 
   let (cli_found, registered, out, err, reg_error) = run_registration_commands(
     emitter,
@@ -1807,7 +2546,11 @@ Use `~/.iflow/skills/maple/SKILL.md` for the full maple execution skill.
       "-e".into(),
       "bash".into(),
       "-lc".into(),
-      format!("iflow mcp add --scope user --transport http maple {}", MAPLE_MCP_URL),
+      format!(
+        "iflow mcp add --scope user --transport http{} maple {}",
+        maple_mcp_header_shell_args(),
+        sh_quote(&emitter.mcp_url)
+      ),
     ],
   );
   stdout = out;
@@ -1825,9 +2568,12 @@ Use `~/.iflow/skills/maple/SKILL.md` for the full maple execution skill.
       stdout,
       stderr,
       error: Some("未检测到 CLI：iflow（WSL）".to_string()),
+      warning: None,
+      duration_ms: None,
     };
   }
 
+  let reg_error = verify_mcp_registration(emitter, target_id, runtime, "iflow", registered, reg_error);
   let result = InstallTargetResult {
     id: target_id.to_string(),
     runtime: Some(runtime.as_str().to_string()),
@@ -1838,6 +2584,8 @@ Use `~/.iflow/skills/maple/SKILL.md` for the full maple execution skill.
     stdout,
     stderr,
     error: reg_error,
+    warning: None,
+    duration_ms: None,
   };
   emitter.target_state(target_id, if result.success && result.error.is_none() { "success" } else { "error" });
   result
@@ -1851,7 +2599,7 @@ fn install_gemini(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRu
   emitter.target_state(target_id, "running");
   let cli_detected = match runtime {
     InstallRuntime::Native => detect_cli_native("gemini"),
-    InstallRuntime::Wsl => detect_cli_wsl("gemini"),
+    InstallRuntime::Wsl => detect_cli_wsl("gemini", emitter.wsl_distro.as_deref()),
   };
   if !cli_detected {
     let scope = if runtime == InstallRuntime::Native { "本机" } else { "WSL" };
@@ -1867,13 +2615,15 @@ fn install_gemini(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRu
       stdout,
       stderr,
       error: None,
+      warning: None,
+      duration_ms: None,
     };
   }
 
   if runtime == InstallRuntime::Native {
     let command_path = home.join(".gemini").join("commands").join("maple.toml");
     emitter.log(Some(target_id), "info", format!("写入 {}\n", pretty_path(&command_path)));
-    if let Err(error) = write_text_file(&command_path, gemini_command_toml()) {
+    if let Err(error) = emitter.write_text_file(&command_path, gemini_command_toml()) {
       emitter.target_state(target_id, "error");
       emitter.log(Some(target_id), "stderr", format!("{error}\n"));
       return InstallTargetResult {
@@ -1886,6 +2636,8 @@ fn install_gemini(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRu
         stdout,
         stderr,
         error: Some(error),
+        warning: None,
+        duration_ms: None,
       };
     }
     written_files.push(pretty_path(&command_path));
@@ -1901,16 +2653,19 @@ fn install_gemini(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRu
         "user".into(),
         "maple".into(),
       ],
-      vec![
-        "mcp".into(),
-        "add".into(),
-        "--transport".into(),
-        "http".into(),
-        "--scope".into(),
-        "user".into(),
-        "maple".into(),
-        MAPLE_MCP_URL.into(),
-      ],
+      [
+        vec![
+          "mcp".into(),
+          "add".into(),
+          "--transport".into(),
+          "http".into(),
+          "--scope".into(),
+          "user".into(),
+        ],
+        maple_mcp_header_args(),
+        vec!["maple".into(), emitter.mcp_url.clone()],
+      ]
+      .concat(),
     );
 
     if !registered {
@@ -1929,14 +2684,12 @@ fn install_gemini(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRu
           target_id,
           "gemini",
           vec!["mcp".into(), "remove".into(), "maple".into()],
-          vec![
-            "mcp".into(),
-            "add".into(),
-            "--transport".into(),
-            "http".into(),
-            "maple".into(),
-            MAPLE_MCP_URL.into(),
-          ],
+          [
+            vec!["mcp".into(), "add".into(), "--transport".into(), "http".into()],
+            maple_mcp_header_args(),
+            vec!["maple".into(), emitter.mcp_url.clone()],
+          ]
+          .concat(),
         );
       }
     }
@@ -1956,6 +2709,8 @@ fn install_gemini(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRu
         stdout,
         stderr,
         error: Some("未检测到 CLI：gemini（本机）".to_string()),
+        warning: None,
+        duration_ms: None,
       };
     }
 
@@ -1969,6 +2724,8 @@ fn install_gemini(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRu
       stdout,
       stderr,
       error: reg_error,
+      warning: None,
+      duration_ms: None,
     };
     emitter.target_state(target_id, if result.success && result.error.is_none() { "success" } else { "error" });
     return result;
@@ -1989,6 +2746,8 @@ fn install_gemini(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRu
         stdout,
         stderr,
         error: Some(error),
+        warning: None,
+        duration_ms: None,
       };
     }
   }
@@ -2008,8 +2767,9 @@ fn install_gemini(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRu
       "bash".into(),
       "-lc".into(),
       format!(
-        "gemini mcp add --transport http --scope user maple {}",
-        MAPLE_MCP_URL
+        "gemini mcp add --transport http --scope user{} maple {}",
+        maple_mcp_header_shell_args(),
+        sh_quote(&emitter.mcp_url)
       ),
     ],
   );
@@ -2028,6 +2788,8 @@ fn install_gemini(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRu
       stdout,
       stderr,
       error: Some("未检测到 CLI：gemini（WSL）".to_string()),
+      warning: None,
+      duration_ms: None,
     };
   }
 
@@ -2041,6 +2803,8 @@ fn install_gemini(home: &Path, emitter: &InstallEventEmitter, runtime: InstallRu
     stdout,
     stderr,
     error: reg_error,
+    warning: None,
+    duration_ms: None,
   };
   emitter.target_state(target_id, if result.success && result.error.is_none() { "success" } else { "error" });
   result
@@ -2067,7 +2831,7 @@ fn opencode_maple_command_template() -> String {
   .join("\n")
 }
 
-fn apply_opencode_maple_config(root: &mut serde_json::Value) {
+fn apply_opencode_maple_config(root: &mut serde_json::Value, mcp_url: &str) {
   if !root.is_object() {
     *root = json!({});
   }
@@ -2077,14 +2841,15 @@ fn apply_opencode_maple_config(root: &mut serde_json::Value) {
   if !mcp.is_object() {
     *mcp = json!({});
   }
-  mcp.as_object_mut().unwrap().insert(
-    "maple".to_string(),
-    json!({
-      "type": "remote",
-      "url": MAPLE_MCP_URL,
-      "enabled": true
-    }),
-  );
+  let mut maple = json!({
+    "type": "remote",
+    "url": mcp_url,
+    "enabled": true
+  });
+  if let Some(headers) = maple_mcp_headers_json() {
+    maple["headers"] = headers;
+  }
+  mcp.as_object_mut().unwrap().insert("maple".to_string(), maple);
 
   let command = obj.entry("command").or_insert_with(|| json!({}));
   if !command.is_object() {
@@ -2110,7 +2875,7 @@ fn install_opencode(home: &Path, emitter: &InstallEventEmitter, runtime: Install
   emitter.target_state(target_id, "running");
   let cli_detected = match runtime {
     InstallRuntime::Native => detect_cli_native("opencode"),
-    InstallRuntime::Wsl => detect_cli_wsl("opencode"),
+    InstallRuntime::Wsl => detect_cli_wsl("opencode", emitter.wsl_distro.as_deref()),
   };
   if !cli_detected {
     let scope = if runtime == InstallRuntime::Native { "本机" } else { "WSL" };
@@ -2126,6 +2891,8 @@ fn install_opencode(home: &Path, emitter: &InstallEventEmitter, runtime: Install
       stdout,
       stderr,
       error: None,
+      warning: None,
+      duration_ms: None,
     };
   }
 
@@ -2144,11 +2911,11 @@ fn install_opencode(home: &Path, emitter: &InstallEventEmitter, runtime: Install
       }
     }
 
-    apply_opencode_maple_config(&mut root);
+    apply_opencode_maple_config(&mut root, &emitter.mcp_url);
 
     let json_text = serde_json::to_string_pretty(&root).unwrap_or_else(|_| "{\n}\n".to_string());
     emitter.log(Some(target_id), "info", format!("写入 {}\n", pretty_path(&config_path)));
-    if let Err(error) = write_text_file(&config_path, &(json_text + "\n")) {
+    if let Err(error) = emitter.write_text_file(&config_path, &(json_text + "\n")) {
       emitter.target_state(target_id, "error");
       emitter.log(Some(target_id), "stderr", format!("{error}\n"));
       return InstallTargetResult {
@@ -2161,6 +2928,8 @@ fn install_opencode(home: &Path, emitter: &InstallEventEmitter, runtime: Install
         stdout,
         stderr,
         error: Some(error),
+        warning: None,
+        duration_ms: None,
       };
     }
     written_files.push(pretty_path(&config_path));
@@ -2175,6 +2944,8 @@ fn install_opencode(home: &Path, emitter: &InstallEventEmitter, runtime: Install
       stdout,
       stderr,
       error: None,
+      warning: None,
+      duration_ms: None,
     };
     emitter.target_state(target_id, "success");
     return result;
@@ -2184,16 +2955,16 @@ fn install_opencode(home: &Path, emitter: &InstallEventEmitter, runtime: Install
   {
     let json_rel = ".config/opencode/opencode.json";
     let jsonc_rel = ".config/opencode/opencode.jsonc";
-    let use_jsonc = wsl_home_file_exists(jsonc_rel).unwrap_or(false);
+    let use_jsonc = wsl_home_file_exists(jsonc_rel, emitter.wsl_distro.as_deref()).unwrap_or(false);
     let config_rel = if use_jsonc { jsonc_rel } else { json_rel };
 
     let mut root = serde_json::Value::Object(Default::default());
-    if let Ok(Some(raw)) = wsl_read_home_file(config_rel) {
+    if let Ok(Some(raw)) = wsl_read_home_file(config_rel, emitter.wsl_distro.as_deref()) {
       if let Some(parsed) = parse_json_or_jsonc_value(&raw) {
         root = parsed;
       }
     }
-    apply_opencode_maple_config(&mut root);
+    apply_opencode_maple_config(&mut root, &emitter.mcp_url);
 
     let json_text = serde_json::to_string_pretty(&root).unwrap_or_else(|_| "{\n}\n".to_string()) + "\n";
     match wsl_write_home_file(emitter, target_id, config_rel, &json_text) {
@@ -2211,6 +2982,8 @@ fn install_opencode(home: &Path, emitter: &InstallEventEmitter, runtime: Install
           stdout,
           stderr,
           error: Some(error),
+          warning: None,
+          duration_ms: None,
         };
       }
     }
@@ -2225,6 +2998,8 @@ fn install_opencode(home: &Path, emitter: &InstallEventEmitter, runtime: Install
       stdout,
       stderr,
       error: None,
+      warning: None,
+      duration_ms: None,
     };
     emitter.target_state(target_id, "success");
     return result;
@@ -2243,6 +3018,8 @@ fn install_opencode(home: &Path, emitter: &InstallEventEmitter, runtime: Install
       stdout,
       stderr,
       error: Some("WSL install is only supported on Windows.".to_string()),
+      warning: None,
+      duration_ms: None,
     };
     emitter.target_state(target_id, "error");
     result
@@ -2278,14 +3055,15 @@ fn install_windsurf(home: &Path, emitter: &InstallEventEmitter) -> InstallTarget
   if !servers.is_object() {
     *servers = json!({});
   }
-  servers
-    .as_object_mut()
-    .unwrap()
-    .insert("maple".to_string(), json!({ "url": MAPLE_MCP_URL }));
+  let mut maple = json!({ "url": emitter.mcp_url });
+  if let Some(headers) = maple_mcp_headers_json() {
+    maple["headers"] = headers;
+  }
+  servers.as_object_mut().unwrap().insert("maple".to_string(), maple);
 
   let json_text = serde_json::to_string_pretty(&root).unwrap_or_else(|_| "{\n}\n".to_string());
   emitter.log(Some("windsurf"), "info", format!("写入 {}\n", pretty_path(&config_path)));
-  if let Err(error) = write_text_file(&config_path, &(json_text + "\n")) {
+  if let Err(error) = emitter.write_text_file(&config_path, &(json_text + "\n")) {
     emitter.target_state("windsurf", "error");
     emitter.log(Some("windsurf"), "stderr", format!("{error}\n"));
     return InstallTargetResult {
@@ -2298,6 +3076,8 @@ fn install_windsurf(home: &Path, emitter: &InstallEventEmitter) -> InstallTarget
       stdout: String::new(),
       stderr: String::new(),
       error: Some(error),
+      warning: None,
+      duration_ms: None,
     };
   }
   written_files.push(pretty_path(&config_path));
@@ -2312,11 +3092,439 @@ fn install_windsurf(home: &Path, emitter: &InstallEventEmitter) -> InstallTarget
     stdout: String::new(),
     stderr: String::new(),
     error: None,
+    warning: None,
+    duration_ms: None,
   };
   emitter.target_state("windsurf", "success");
   result
 }
 
+fn install_cursor(home: &Path, emitter: &InstallEventEmitter) -> InstallTargetResult {
+  let mut written_files = Vec::new();
+
+  emitter.target_state("cursor", "running");
+  let config_path = cursor_mcp_config_path(home);
+  let fail = |written_files: Vec<String>, error: String| {
+    emitter.target_state("cursor", "error");
+    emitter.log(Some("cursor"), "stderr", format!("{error}\n"));
+    InstallTargetResult {
+      id: "cursor".to_string(),
+      runtime: Some("native".to_string()),
+      success: false,
+      skipped: false,
+      cli_found: None,
+      written_files,
+      stdout: String::new(),
+      stderr: String::new(),
+      error: Some(error),
+      warning: None,
+      duration_ms: None,
+    }
+  };
+
+  let existing = fs::read_to_string(&config_path).ok();
+  let mut root = serde_json::Value::Object(Default::default());
+  if let Some(raw) = existing.as_deref().filter(|raw| !raw.trim().is_empty()) {
+    match parse_json_or_jsonc_value(raw) {
+      Some(parsed) if parsed.is_object() => root = parsed,
+      // Refuse to replace a config we cannot merge into.
+      _ => return fail(written_files, format!("无法解析 {}，已跳过写入。", pretty_path(&config_path))),
+    }
+  }
+
+  if let Some(raw) = existing.as_deref() {
+    let backup_path = config_path.with_file_name("mcp.json.bak");
+    emitter.log(Some("cursor"), "info", format!("备份 {}\n", pretty_path(&backup_path)));
+    if let Err(error) = emitter.write_text_file(&backup_path, raw) {
+      return fail(written_files, error);
+    }
+    written_files.push(pretty_path(&backup_path));
+  }
+
+  let obj = root.as_object_mut().unwrap();
+  let servers = obj.entry("mcpServers").or_insert_with(|| json!({}));
+  if !servers.is_object() {
+    *servers = json!({});
+  }
+  let mut maple = json!({ "url": emitter.mcp_url });
+  if let Some(headers) = maple_mcp_headers_json() {
+    maple["headers"] = headers;
+  }
+  servers.as_object_mut().unwrap().insert("maple".to_string(), maple);
+
+  let json_text = serde_json::to_string_pretty(&root).unwrap_or_else(|_| "{\n}\n".to_string());
+  emitter.log(Some("cursor"), "info", format!("写入 {}\n", pretty_path(&config_path)));
+  if let Err(error) = emitter.write_text_file(&config_path, &(json_text + "\n")) {
+    return fail(written_files, error);
+  }
+  written_files.push(pretty_path(&config_path));
+
+  let result = InstallTargetResult {
+    id: "cursor".to_string(),
+    runtime: Some("native".to_string()),
+    success: true,
+    skipped: false,
+    cli_found: None,
+    written_files,
+    stdout: String::new(),
+    stderr: String::new(),
+    error: None,
+    warning: None,
+    duration_ms: None,
+  };
+  emitter.target_state("cursor", "success");
+  result
+}
+
+fn install_vscode(home: &Path, emitter: &InstallEventEmitter) -> InstallTargetResult {
+  let mut written_files = Vec::new();
+
+  emitter.target_state("vscode", "running");
+  let settings_path = vscode_settings_path(home);
+  let fail = |written_files: Vec<String>, error: String| {
+    emitter.target_state("vscode", "error");
+    emitter.log(Some("vscode"), "stderr", format!("{error}\n"));
+    InstallTargetResult {
+      id: "vscode".to_string(),
+      runtime: Some("native".to_string()),
+      success: false,
+      skipped: false,
+      cli_found: None,
+      written_files,
+      stdout: String::new(),
+      stderr: String::new(),
+      error: Some(error),
+      warning: None,
+      duration_ms: None,
+    }
+  };
+
+  let mut maple = json!({ "type": "http", "url": emitter.mcp_url });
+  if let Some(headers) = maple_mcp_headers_json() {
+    maple["headers"] = headers;
+  }
+
+  // settings.json holds every VS Code preference, so only the `mcp.servers.maple` span is
+  // spliced in; comments and the rest of the file are left as the user wrote them.
+  let existing = fs::read_to_string(&settings_path).ok();
+  let json_text = match existing.as_deref().filter(|raw| !raw.trim().is_empty()) {
+    Some(raw) => {
+      let spliced = parse_json_or_jsonc_value(raw)
+        .filter(serde_json::Value::is_object)
+        .and_then(|_| splice_jsonc_value(raw, &["mcp", "servers", "maple"], &maple));
+      let Some(spliced) = spliced else {
+        // Never replace a settings file we cannot merge into.
+        return fail(written_files, format!("无法解析 {}，已跳过写入。", pretty_path(&settings_path)));
+      };
+      let backup_path = settings_path.with_file_name("settings.json.maple.bak");
+      emitter.log(Some("vscode"), "info", format!("备份 {}\n", pretty_path(&backup_path)));
+      if let Err(error) = emitter.write_text_file(&backup_path, raw) {
+        return fail(written_files, error);
+      }
+      written_files.push(pretty_path(&backup_path));
+      spliced
+    }
+    None => {
+      let root = json!({ "mcp": { "servers": { "maple": maple } } });
+      serde_json::to_string_pretty(&root).unwrap_or_else(|_| "{\n}".to_string()) + "\n"
+    }
+  };
+
+  emitter.log(Some("vscode"), "info", format!("写入 {}\n", pretty_path(&settings_path)));
+  if let Err(error) = emitter.write_text_file(&settings_path, &json_text) {
+    return fail(written_files, error);
+  }
+  written_files.push(pretty_path(&settings_path));
+
+  let result = InstallTargetResult {
+    id: "vscode".to_string(),
+    runtime: Some("native".to_string()),
+    success: true,
+    skipped: false,
+    cli_found: None,
+    written_files,
+    stdout: String::new(),
+    stderr: String::new(),
+    error: None,
+    warning: None,
+    duration_ms: None,
+  };
+  emitter.target_state("vscode", "success");
+  result
+}
+
+// ── Uninstall ──
+
+/// Removes the `maple` entry from each of the given objects in a JSON/JSONC config; a
+/// dotted section such as `mcp.servers` names a nested object. Returns the rewritten
+/// text, or `Ok(None)` when there was nothing to remove.
+fn remove_maple_from_json_text(raw: &str, sections: &[&str]) -> Result<Option<String>, String> {
+  if raw.trim().is_empty() {
+    return Ok(None);
+  }
+  if parse_json_or_jsonc_value(raw).is_none() {
+    return Err("配置文件不是有效的 JSON，已跳过。".to_string());
+  }
+  // Edit only the removed spans so comments elsewhere in the file survive.
+  let mut text = raw.to_string();
+  let mut changed = false;
+  for section in sections {
+    let path: Vec<&str> = section.split('.').chain(["maple"]).collect();
+    if let Some(next) = remove_jsonc_member(&text, &path) {
+      text = next;
+      changed = true;
+    }
+  }
+  Ok(changed.then_some(text))
+}
+
+fn uninstall_result(target_id: &str, runtime: InstallRuntime, cli_found: Option<bool>) -> InstallTargetResult {
+  InstallTargetResult {
+    id: target_id.to_string(),
+    runtime: Some(runtime.as_str().to_string()),
+    success: true,
+    skipped: false,
+    cli_found,
+    written_files: Vec::new(),
+    stdout: String::new(),
+    stderr: String::new(),
+    error: None,
+    warning: None,
+    duration_ms: None,
+  }
+}
+
+fn finish_uninstall(emitter: &InstallEventEmitter, mut result: InstallTargetResult, error: Option<String>) -> InstallTargetResult {
+  if let Some(error) = error {
+    emitter.log(Some(&result.id), "stderr", format!("{error}\n"));
+    result.success = false;
+    result.error = Some(error);
+  }
+  emitter.target_state(&result.id, if result.success { "success" } else { "error" });
+  result
+}
+
+/// Runs `<cli> mcp remove maple` and deletes the skill/command files the installer wrote.
+/// `written_files` lists the removed paths.
+fn uninstall_cli_target(
+  home: &Path,
+  emitter: &InstallEventEmitter,
+  runtime: InstallRuntime,
+  target_id: &str,
+  cli: &str,
+  remove_args: &[&str],
+  paths: &[&str],
+) -> InstallTargetResult {
+  emitter.target_state(target_id, "running");
+  let cli_found = match runtime {
+    InstallRuntime::Native => detect_cli_native(cli),
+    InstallRuntime::Wsl => detect_cli_wsl(cli, emitter.wsl_distro.as_deref()),
+  };
+  let mut result = uninstall_result(target_id, runtime, Some(cli_found));
+
+  if cli_found {
+    let (executable, args): (&str, Vec<String>) = match runtime {
+      InstallRuntime::Native => (cli, remove_args.iter().map(|arg| arg.to_string()).collect()),
+      InstallRuntime::Wsl => (
+        "wsl",
+        wsl_args(
+          emitter.wsl_distro.as_deref(),
+          vec![
+            "-e".into(),
+            "bash".into(),
+            "-lc".into(),
+            format!("{cli} {}", remove_args.join(" ")),
+          ],
+        ),
+      ),
+    };
+    emitter.log_command(target_id, executable, &args);
+    let forward = |stream: &str, chunk: &str| emitter.log(Some(target_id), stream, chunk);
+    match run_cli_streaming(executable, &args, None, &forward) {
+      Ok(out) => {
+        if !out.success {
+          // Usually means maple was never registered; the file cleanup below still applies.
+          emitter.log(Some(target_id), "info", "MCP 注销未成功（可能本就未注册），继续清理文件。\n".to_string());
+        }
+        result.stdout = out.stdout;
+        result.stderr = out.stderr;
+      }
+      Err(error) => return finish_uninstall(emitter, result, Some(error)),
+    }
+  }
+
+  for rel in paths {
+    match runtime {
+      InstallRuntime::Native => {
+        let path = home.join(rel);
+        let removed = if path.is_dir() {
+          fs::remove_dir_all(&path)
+        } else if path.exists() {
+          fs::remove_file(&path)
+        } else {
+          continue;
+        };
+        if let Err(error) = removed {
+          let message = format!("删除 {} 失败: {error}", pretty_path(&path));
+          return finish_uninstall(emitter, result, Some(message));
+        }
+        emitter.log(Some(target_id), "info", format!("已删除 {}\n", pretty_path(&path)));
+        result.written_files.push(pretty_path(&path));
+      }
+      InstallRuntime::Wsl => {
+        let rel = match normalize_home_relative_path(rel) {
+          Ok(rel) => rel,
+          Err(error) => return finish_uninstall(emitter, result, Some(error)),
+        };
+        let script = format!("if [ -e \"$HOME/{rel}\" ]; then rm -rf \"$HOME/{rel}\" && echo removed; fi");
+        let args = wsl_args(
+          emitter.wsl_distro.as_deref(),
+          vec!["-e".to_string(), "sh".to_string(), "-lc".to_string(), script],
+        );
+        match run_cli("wsl", &args, None) {
+          Ok(out) if out.success => {
+            if out.stdout.contains("removed") {
+              emitter.log(Some(target_id), "info", format!("已删除 ~/{rel}\n"));
+              result.written_files.push(format!("~/{rel}"));
+            }
+          }
+          Ok(out) => {
+            let message = format!("删除 ~/{rel} 失败: {}", out.stderr);
+            return finish_uninstall(emitter, result, Some(message));
+          }
+          Err(error) => return finish_uninstall(emitter, result, Some(error)),
+        }
+      }
+    }
+  }
+
+  finish_uninstall(emitter, result, None)
+}
+
+/// Removes the `maple` keys from a native JSON config file (Windsurf / Cursor / VS Code / OpenCode).
+fn uninstall_json_target(
+  emitter: &InstallEventEmitter,
+  target_id: &str,
+  config_paths: &[PathBuf],
+  sections: &[&str],
+) -> InstallTargetResult {
+  emitter.target_state(target_id, "running");
+  let mut result = uninstall_result(target_id, InstallRuntime::Native, None);
+  for config_path in config_paths {
+    let Ok(raw) = fs::read_to_string(config_path) else {
+      continue;
+    };
+    match remove_maple_from_json_text(&raw, sections) {
+      Ok(Some(json_text)) => {
+        emitter.log(Some(target_id), "info", format!("写入 {}\n", pretty_path(config_path)));
+        if let Err(error) = write_text_file(config_path, &json_text) {
+          return finish_uninstall(emitter, result, Some(error));
+        }
+        result.written_files.push(pretty_path(config_path));
+      }
+      Ok(None) => {}
+      Err(error) => {
+        let message = format!("{}：{error}", pretty_path(config_path));
+        return finish_uninstall(emitter, result, Some(message));
+      }
+    }
+  }
+  finish_uninstall(emitter, result, None)
+}
+
+fn uninstall_opencode_wsl(emitter: &InstallEventEmitter, target_id: &str) -> InstallTargetResult {
+  emitter.target_state(target_id, "running");
+  let mut result = uninstall_result(target_id, InstallRuntime::Wsl, None);
+  for rel in [".config/opencode/opencode.json", ".config/opencode/opencode.jsonc"] {
+    let raw = match wsl_read_home_file(rel, emitter.wsl_distro.as_deref()) {
+      Ok(Some(raw)) => raw,
+      Ok(None) => continue,
+      Err(error) => return finish_uninstall(emitter, result, Some(error)),
+    };
+    match remove_maple_from_json_text(&raw, &["mcp", "command"]) {
+      Ok(Some(json_text)) => match wsl_write_home_file(emitter, target_id, rel, &json_text) {
+        Ok(path) => result.written_files.push(path),
+        Err(error) => return finish_uninstall(emitter, result, Some(error)),
+      },
+      Ok(None) => {}
+      Err(error) => return finish_uninstall(emitter, result, Some(format!("~/{rel}：{error}"))),
+    }
+  }
+  finish_uninstall(emitter, result, None)
+}
+
+pub fn uninstall_mcp_and_skills(options: InstallMcpSkillsOptions) -> Result<Vec<InstallTargetResult>, String> {
+  let home = maple_fs::user_home_dir()?;
+  let emitter = InstallEventEmitter {
+    install_id: options.install_id.clone().unwrap_or_default(),
+    emit: None,
+    mcp_url: resolve_mcp_url(options.mcp_url.as_deref())?,
+    dry_run: false,
+    skip_if_installed: false,
+    wsl_distro: normalize_wsl_distro(options.wsl_distro.as_deref()),
+  };
+  let wsl = should_enable_wsl_integration();
+  let mut targets = Vec::new();
+
+  const CODEX_PATHS: &[&str] = &[".codex/skills/maple"];
+  const CLAUDE_PATHS: &[&str] = &[".claude/commands/maple.md"];
+  // The shared `.iflow/skills/SKILL.md` index may hold user content, so it stays.
+  const IFLOW_PATHS: &[&str] = &[".iflow/workflows/maple.md", ".iflow/commands/maple.toml", ".iflow/skills/maple"];
+  const GEMINI_PATHS: &[&str] = &[".gemini/commands/maple.toml"];
+  let mut uninstall_cli = |native: bool, wsl_selected: bool, cli: &str, remove_args: &[&str], paths: &[&str]| {
+    if native {
+      targets.push(uninstall_cli_target(&home, &emitter, InstallRuntime::Native, cli, cli, remove_args, paths));
+    }
+    if wsl && wsl_selected {
+      let target_id = format!("wsl:{cli}");
+      targets.push(uninstall_cli_target(&home, &emitter, InstallRuntime::Wsl, &target_id, cli, remove_args, paths));
+    }
+  };
+  uninstall_cli(options.codex, options.wsl_codex, "codex", &["mcp", "remove", "maple"], CODEX_PATHS);
+  uninstall_cli(options.claude, options.wsl_claude, "claude", &["mcp", "remove", "maple", "--scope", "user"], CLAUDE_PATHS);
+  uninstall_cli(options.iflow, options.wsl_iflow, "iflow", &["mcp", "remove", "maple"], IFLOW_PATHS);
+  uninstall_cli(options.gemini, options.wsl_gemini, "gemini", &["mcp", "remove", "--scope", "user", "maple"], GEMINI_PATHS);
+
+  if options.opencode {
+    let config_dir = home.join(".config").join("opencode");
+    targets.push(uninstall_json_target(
+      &emitter,
+      "opencode",
+      &[config_dir.join("opencode.json"), config_dir.join("opencode.jsonc")],
+      &["mcp", "command"],
+    ));
+  }
+  if wsl && options.wsl_opencode {
+    targets.push(uninstall_opencode_wsl(&emitter, "wsl:opencode"));
+  }
+  if options.windsurf {
+    let config_path = home.join(".codeium").join("windsurf").join("mcp_config.json");
+    targets.push(uninstall_json_target(&emitter, "windsurf", &[config_path], &["mcpServers"]));
+  }
+  if options.cursor {
+    targets.push(uninstall_json_target(&emitter, "cursor", &[cursor_mcp_config_path(&home)], &["mcpServers"]));
+  }
+  if options.vscode {
+    targets.push(uninstall_json_target(&emitter, "vscode", &[vscode_settings_path(&home)], &["mcp.servers"]));
+  }
+
+  // Cached probes would otherwise keep reporting these targets as installed.
+  let mut cache = read_install_probe_cache();
+  let before = cache.len();
+  cache.retain(|id, _| !targets.iter().any(|target| &target.id == id));
+  if cache.len() != before {
+    write_install_probe_cache(&cache);
+  }
+
+  Ok(targets)
+}
+
+type InstallJob<'a> = Box<dyn FnOnce() -> InstallTargetResult + Send + 'a>;
+
+fn elapsed_ms(started: Instant) -> u64 {
+  u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX)
+}
+
 #[allow(dead_code)]
 pub fn install_mcp_and_skills(options: InstallMcpSkillsOptions) -> Result<InstallMcpSkillsReport, String> {
   install_mcp_and_skills_with_events(options, None)
@@ -2327,7 +3535,7 @@ pub fn install_mcp_and_skills_with_events(
   emit: Option<Arc<dyn Fn(InstallTaskEvent) + Send + Sync>>,
 ) -> Result<InstallMcpSkillsReport, String> {
   let home = maple_fs::user_home_dir()?;
-  let mut targets = Vec::new();
+  let mcp_url = resolve_mcp_url(options.mcp_url.as_deref())?;
   let install_id = options
     .install_id
     .as_deref()
@@ -2346,70 +3554,110 @@ pub fn install_mcp_and_skills_with_events(
   let emitter = InstallEventEmitter {
     install_id: resolved_install_id.clone(),
     emit,
+    mcp_url,
+    dry_run: options.dry_run,
+    skip_if_installed: options.skip_if_installed,
+    wsl_distro: normalize_wsl_distro(options.wsl_distro.as_deref()),
   };
+  if emitter.dry_run {
+    emitter.log(None, "info", "预览模式：仅列出将写入的文件与将执行的命令，不做任何修改。\n");
+  }
 
+  let wsl = should_enable_wsl_integration();
+  let (home, emitter) = (&home, &emitter);
+  let mut jobs: Vec<(&str, InstallJob)> = Vec::new();
   if options.codex {
-    let result = install_codex(&home, &emitter, InstallRuntime::Native, "codex");
-    emitter.target_result(result.clone());
-    targets.push(result);
+    jobs.push(("codex", Box::new(move || install_codex(home, emitter, InstallRuntime::Native, "codex"))));
   }
-  if should_enable_wsl_integration() && options.wsl_codex {
-    let result = install_codex(&home, &emitter, InstallRuntime::Wsl, "wsl:codex");
-    emitter.target_result(result.clone());
-    targets.push(result);
+  if wsl && options.wsl_codex {
+    jobs.push(("wsl:codex", Box::new(move || install_codex(home, emitter, InstallRuntime::Wsl, "wsl:codex"))));
   }
   if options.claude {
-    let result = install_claude(&home, &emitter, InstallRuntime::Native, "claude");
-    emitter.target_result(result.clone());
-    targets.push(result);
+    jobs.push(("claude", Box::new(move || install_claude(home, emitter, InstallRuntime::Native, "claude"))));
   }
-  if should_enable_wsl_integration() && options.wsl_claude {
-    let result = install_claude(&home, &emitter, InstallRuntime::Wsl, "wsl:claude");
-    emitter.target_result(result.clone());
-    targets.push(result);
+  if wsl && options.wsl_claude {
+    jobs.push(("wsl:claude", Box::new(move || install_claude(home, emitter, InstallRuntime::Wsl, "wsl:claude"))));
   }
   if options.iflow {
-    let result = install_iflow(&home, &emitter, InstallRuntime::Native, "iflow");
-    emitter.target_result(result.clone());
-    targets.push(result);
+    jobs.push(("iflow", Box::new(move || install_iflow(home, emitter, InstallRuntime::Native, "iflow"))));
   }
-  if should_enable_wsl_integration() && options.wsl_iflow {
-    let result = install_iflow(&home, &emitter, InstallRuntime::Wsl, "wsl:iflow");
-    emitter.target_result(result.clone());
-    targets.push(result);
+  if wsl && options.wsl_iflow {
+    jobs.push(("wsl:iflow", Box::new(move || install_iflow(home, emitter, InstallRuntime::Wsl, "wsl:iflow"))));
   }
   if options.gemini {
-    let result = install_gemini(&home, &emitter, InstallRuntime::Native, "gemini");
-    emitter.target_result(result.clone());
-    targets.push(result);
+    jobs.push(("gemini", Box::new(move || install_gemini(home, emitter, InstallRuntime::Native, "gemini"))));
   }
-  if should_enable_wsl_integration() && options.wsl_gemini {
-    let result = install_gemini(&home, &emitter, InstallRuntime::Wsl, "wsl:gemini");
-    emitter.target_result(result.clone());
-    targets.push(result);
+  if wsl && options.wsl_gemini {
+    jobs.push(("wsl:gemini", Box::new(move || install_gemini(home, emitter, InstallRuntime::Wsl, "wsl:gemini"))));
   }
   if options.opencode {
-    let result = install_opencode(&home, &emitter, InstallRuntime::Native, "opencode");
-    emitter.target_result(result.clone());
-    targets.push(result);
+    jobs.push(("opencode", Box::new(move || install_opencode(home, emitter, InstallRuntime::Native, "opencode"))));
   }
-  if should_enable_wsl_integration() && options.wsl_opencode {
-    let result = install_opencode(&home, &emitter, InstallRuntime::Wsl, "wsl:opencode");
-    emitter.target_result(result.clone());
-    targets.push(result);
+  if wsl && options.wsl_opencode {
+    jobs.push(("wsl:opencode", Box::new(move || install_opencode(home, emitter, InstallRuntime::Wsl, "wsl:opencode"))));
   }
   if options.windsurf {
-    let result = install_windsurf(&home, &emitter);
-    emitter.target_result(result.clone());
-    targets.push(result);
+    jobs.push(("windsurf", Box::new(move || install_windsurf(home, emitter))));
+  }
+  if options.cursor {
+    jobs.push(("cursor", Box::new(move || install_cursor(home, emitter))));
+  }
+  if options.vscode {
+    jobs.push(("vscode", Box::new(move || install_vscode(home, emitter))));
   }
 
+  // Targets are independent (each `wsl` call boots the distro, so running them serially
+  // is slow); run them concurrently and keep the results in selection order.
+  let targets: Vec<InstallTargetResult> = std::thread::scope(|scope| {
+    let handles: Vec<_> = jobs
+      .into_iter()
+      .map(|(target_id, job)| {
+        let started = Instant::now();
+        let handle = scope.spawn(move || {
+          let mut result = job();
+          result.duration_ms = Some(elapsed_ms(started));
+          emitter.target_result(result.clone());
+          result
+        });
+        (target_id, started, handle)
+      })
+      .collect();
+    handles
+      .into_iter()
+      .map(|(target_id, started, handle)| {
+        handle.join().unwrap_or_else(|_| {
+          let error = "安装线程异常退出".to_string();
+          emitter.target_state(target_id, "error");
+          let result = InstallTargetResult {
+            id: target_id.to_string(),
+            runtime: Some(if target_id.starts_with("wsl:") { "wsl" } else { "native" }.to_string()),
+            success: false,
+            skipped: false,
+            cli_found: None,
+            written_files: Vec::new(),
+            stdout: String::new(),
+            stderr: String::new(),
+            error: Some(error),
+            warning: None,
+            duration_ms: Some(elapsed_ms(started)),
+          };
+          emitter.target_result(result.clone());
+          result
+        })
+      })
+      .collect()
+  });
+
   let report = InstallMcpSkillsReport {
-    mcp_url: MAPLE_MCP_URL.to_string(),
+    mcp_url: emitter.mcp_url.clone(),
     targets,
     skills_version: SKILLS_VERSION,
   };
 
+  if options.dry_run {
+    return Ok(report);
+  }
+
   // Write install meta to ~/.maple/install-meta.json
   if let Ok(maple_home) = maple_fs::maple_home_dir() {
     let _ = fs::create_dir_all(&maple_home);
@@ -2423,3 +3671,91 @@ pub fn install_mcp_and_skills_with_events(
 
   Ok(report)
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SETTINGS: &str = "{\n    // keep me\n    \"editor.fontSize\": 14, /* and me */\n    \"files.exclude\": { \"**/.git\": true },\n}\n";
+
+  fn maple() -> serde_json::Value {
+    json!({ "type": "http", "url": "http://localhost:45819/mcp" })
+  }
+
+  fn parsed(text: &str) -> serde_json::Value {
+    parse_json_or_jsonc_value(text).expect("still valid JSONC")
+  }
+
+  #[test]
+  fn splicing_a_new_server_keeps_comments_and_other_settings() {
+    let text = splice_jsonc_value(SETTINGS, &["mcp", "servers", "maple"], &maple()).unwrap();
+    assert!(text.contains("// keep me") && text.contains("/* and me */"));
+    assert!(text.contains("\n    \"mcp\": {\n        \"servers\": {"), "uses the file's indent:\n{text}");
+    let root = parsed(&text);
+    assert_eq!(root["mcp"]["servers"]["maple"], maple());
+    assert_eq!(root["editor.fontSize"], 14);
+    assert_eq!(root["files.exclude"]["**/.git"], true);
+  }
+
+  #[test]
+  fn splicing_replaces_an_existing_server_and_leaves_its_siblings() {
+    let raw = "{\n  \"mcp\": {\n    // servers\n    \"servers\": {\n      \"other\": { \"url\": \"x\" },\n      \"maple\": { \"url\": \"old\" }\n    }\n  }\n}\n";
+    let text = splice_jsonc_value(raw, &["mcp", "servers", "maple"], &maple()).unwrap();
+    assert!(text.contains("// servers"));
+    let root = parsed(&text);
+    assert_eq!(root["mcp"]["servers"]["maple"], maple());
+    assert_eq!(root["mcp"]["servers"]["other"]["url"], "x");
+  }
+
+  #[test]
+  fn splicing_into_empty_or_non_object_sections() {
+    let root = parsed(&splice_jsonc_value("{}", &["mcp", "servers", "maple"], &maple()).unwrap());
+    assert_eq!(root["mcp"]["servers"]["maple"], maple());
+
+    let raw = "{ \"mcp\": { \"servers\": [] } }";
+    let root = parsed(&splice_jsonc_value(raw, &["mcp", "servers", "maple"], &maple()).unwrap());
+    assert_eq!(root["mcp"]["servers"]["maple"], maple());
+
+    assert_eq!(splice_jsonc_value("[1, 2]", &["mcp"], &maple()), None);
+  }
+
+  #[test]
+  fn keys_inside_strings_and_comments_are_not_matched() {
+    let raw = "{\n  // \"mcp\": {}\n  \"note\": \"\\\"mcp\\\": { }\",\n  \"mcp\": { \"servers\": {} }\n}";
+    let text = splice_jsonc_value(raw, &["mcp", "servers", "maple"], &maple()).unwrap();
+    assert!(text.contains("// \"mcp\": {}"));
+    let root = parsed(&text);
+    assert_eq!(root["note"], "\"mcp\": { }");
+    assert_eq!(root["mcp"]["servers"]["maple"], maple());
+  }
+
+  #[test]
+  fn removing_the_server_keeps_comments_and_siblings() {
+    let installed = splice_jsonc_value(SETTINGS, &["mcp", "servers", "maple"], &maple()).unwrap();
+    let text = remove_maple_from_json_text(&installed, &["mcp.servers"]).unwrap().unwrap();
+    assert!(text.contains("// keep me") && text.contains("/* and me */"));
+    let root = parsed(&text);
+    assert_eq!(root["mcp"]["servers"], json!({}));
+    assert_eq!(root["editor.fontSize"], 14);
+
+    let raw = "{ \"mcpServers\": { \"a\": 1, \"maple\": {}, \"b\": 2 } }";
+    let root = parsed(&remove_maple_from_json_text(raw, &["mcpServers"]).unwrap().unwrap());
+    assert_eq!(root["mcpServers"], json!({ "a": 1, "b": 2 }));
+
+    let raw = "{ \"mcpServers\": { \"a\": 1, \"maple\": {} } }";
+    let root = parsed(&remove_maple_from_json_text(raw, &["mcpServers"]).unwrap().unwrap());
+    assert_eq!(root["mcpServers"], json!({ "a": 1 }));
+
+    assert_eq!(remove_maple_from_json_text(raw, &["mcp.servers"]), Ok(None));
+  }
+
+  #[test]
+  fn logged_commands_hide_the_bearer_token() {
+    let line = "$ claude mcp add --scope user --transport http --header Authorization: Bearer s3cret maple http://localhost\n";
+    let redacted = redact_token(line, Some("s3cret"));
+    assert!(redacted.contains("Authorization: Bearer ***"));
+    assert!(!redacted.contains("s3cret"));
+    assert_eq!(redact_token(line, None), line);
+    assert_eq!(redact_token(line, Some("")), line);
+  }
+}
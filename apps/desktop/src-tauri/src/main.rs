@@ -1,25 +1,40 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 mod mcp_http;
+mod mcp_tools;
 mod maple_fs;
 mod installer;
 mod codex_usage;
 mod maple_protocol;
 mod tray_status;
 mod process_utils;
+mod worker_log;
+mod worker_probe;
+mod worker_queue;
+mod state_import;
+mod state_patch;
+mod state_replace;
+mod state_watcher;
+mod asset_thumbnail;
+mod worker_pty;
 
 use base64::Engine;
 use encoding_rs::{GBK, WINDOWS_1252};
 use serde::Serialize;
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::io::{Read, Write};
-use std::path::PathBuf;
-use std::process::{Child, ChildStdin, Command, Stdio};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
 use std::sync::Mutex;
 use tauri::{AppHandle, Emitter, Manager};
 use tauri::State;
 
 const ENABLE_WSL_CONSTITUTION_SYNC: bool = false;
+/// How long a stopped worker gets to exit on its own before it is force-killed.
+const DEFAULT_WORKER_STOP_GRACE_MS: u64 = 3000;
+/// Shorter grace used when the app quits, so closing the window doesn't hang.
+const EXIT_WORKER_STOP_GRACE_MS: u64 = 1500;
 
 #[derive(Serialize)]
 struct WorkerCommandResult {
@@ -27,6 +42,9 @@ struct WorkerCommandResult {
   code: Option<i32>,
   stdout: String,
   stderr: String,
+  /// stdout and stderr in the order they were read; only streamed runs fill this.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  combined: Option<String>,
 }
 
 #[derive(Serialize, Clone)]
@@ -36,6 +54,9 @@ struct WorkerLogEvent {
   task_title: String,
   stream: String,
   line: String,
+  /// Per-stream line number; only set when the stream is line-buffered.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  line_index: Option<u64>,
 }
 
 #[derive(Serialize, Clone)]
@@ -44,6 +65,17 @@ struct WorkerDoneEvent {
   worker_id: String,
   success: bool,
   code: Option<i32>,
+  /// Set when the worker was killed through `stop_worker_session`.
+  cancelled: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AppVersions {
+  app_version: String,
+  mcp_protocol_version: String,
+  mcp_tool_schema_version: u32,
+  skills_version: u32,
 }
 
 #[derive(Serialize)]
@@ -59,7 +91,12 @@ struct ManagedMcpServer {
 }
 
 struct ManagedWorkerSession {
-  stdin: Option<ChildStdin>,
+  pid: u32,
+  stdin: Option<Box<dyn Write + Send>>,
+  /// What "press Enter" means for this session's input.
+  newline: &'static [u8],
+  /// The PTY master, kept so `resize_worker_pty` can set the window size.
+  pty: Option<worker_pty::PtyResizer>,
 }
 
 #[derive(Default)]
@@ -67,6 +104,45 @@ struct AppState {
   mcp_server: Mutex<Option<ManagedMcpServer>>,
   worker_sessions: Mutex<HashMap<String, ManagedWorkerSession>>,
   running_workers: Mutex<HashMap<String, u32>>,
+  worker_queue: worker_queue::WorkerQueue,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct WorkerQueuedEvent {
+  worker_id: String,
+  position: usize,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct WorkerStartedEvent {
+  worker_id: String,
+}
+
+/// Waits for a free worker slot, announcing `worker-queued` if the launch is
+/// deferred and `worker-started` once it may proceed.
+fn acquire_worker_slot<'a, E: Emitter<tauri::Wry>>(
+  queue: &'a worker_queue::WorkerQueue,
+  emitter: &E,
+  worker_id: &str,
+) -> worker_queue::WorkerSlot<'a> {
+  let slot = queue.acquire(|position| {
+    let _ = emitter.emit(
+      "maple://worker-queued",
+      WorkerQueuedEvent {
+        worker_id: worker_id.to_string(),
+        position,
+      },
+    );
+  });
+  let _ = emitter.emit(
+    "maple://worker-started",
+    WorkerStartedEvent {
+      worker_id: worker_id.to_string(),
+    },
+  );
+  slot
 }
 
 #[tauri::command]
@@ -81,8 +157,18 @@ async fn probe_worker(
 }
 
 #[tauri::command]
-async fn probe_install_targets() -> Result<Vec<installer::InstallTargetProbe>, String> {
-  tauri::async_runtime::spawn_blocking(installer::probe_install_targets)
+async fn probe_worker_version(
+  kind: String,
+  executable: Option<String>,
+) -> Result<worker_probe::WorkerProbeResult, String> {
+  tauri::async_runtime::spawn_blocking(move || worker_probe::probe(&kind, executable.as_deref()))
+    .await
+    .map_err(|_| "Worker 探测线程异常退出".to_string())?
+}
+
+#[tauri::command]
+async fn probe_install_targets(wsl_distro: Option<String>) -> Result<Vec<installer::InstallTargetProbe>, String> {
+  tauri::async_runtime::spawn_blocking(move || installer::probe_install_targets(wsl_distro))
     .await
     .map_err(|_| "环境检测线程异常退出".to_string())?
 }
@@ -92,6 +178,16 @@ fn get_install_meta() -> installer::InstallMeta {
   installer::read_install_meta()
 }
 
+#[tauri::command]
+fn versions() -> AppVersions {
+  AppVersions {
+    app_version: env!("CARGO_PKG_VERSION").to_string(),
+    mcp_protocol_version: mcp_http::MCP_PROTOCOL_VERSION.to_string(),
+    mcp_tool_schema_version: mcp_http::MCP_TOOL_SCHEMA_VERSION,
+    skills_version: installer::SKILLS_VERSION,
+  }
+}
+
 #[tauri::command]
 async fn install_mcp_skills(
   window: tauri::Window,
@@ -107,17 +203,31 @@ async fn install_mcp_skills(
     .map_err(|_| "安装线程异常退出".to_string())?
 }
 
+#[tauri::command]
+async fn uninstall_mcp_skills(
+  options: installer::InstallMcpSkillsOptions,
+) -> Result<Vec<installer::InstallTargetResult>, String> {
+  tauri::async_runtime::spawn_blocking(move || installer::uninstall_mcp_and_skills(options))
+    .await
+    .map_err(|_| "卸载线程异常退出".to_string())?
+}
+
 #[tauri::command]
 async fn query_codex_usage(
   base_url: String,
   api_key: String,
+  timeout_secs: Option<u64>,
+  proxy: Option<String>,
 ) -> Result<codex_usage::CodexUsageHttpResult, String> {
-  tauri::async_runtime::spawn_blocking(move || codex_usage::query_codex_usage(base_url, api_key))
+  tauri::async_runtime::spawn_blocking(move || {
+    codex_usage::query_codex_usage(base_url, api_key, timeout_secs, proxy)
+  })
     .await
     .map_err(|_| "余额查询线程异常退出".to_string())?
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn run_worker(
   window: tauri::Window,
   worker_id: String,
@@ -126,6 +236,7 @@ async fn run_worker(
   args: Vec<String>,
   prompt: String,
   cwd: Option<String>,
+  line_buffered: Option<bool>,
 ) -> Result<WorkerCommandResult, String> {
   tauri::async_runtime::spawn_blocking(move || {
     run_command_stream(
@@ -136,6 +247,7 @@ async fn run_worker(
       args,
       Some(prompt),
       cwd,
+      line_buffered.unwrap_or(false),
     )
   })
   .await
@@ -261,6 +373,55 @@ fn mcp_server_status(state: State<'_, AppState>) -> Result<McpServerStatus, Stri
   })
 }
 
+#[tauri::command]
+fn pause_mcp_server(
+  all_tools: Option<bool>,
+  app_handle: AppHandle,
+  state: State<'_, std::sync::Arc<mcp_http::McpHttpState>>,
+) -> mcp_http::McpPauseStatus {
+  let status = mcp_http::set_paused(state.inner(), true, all_tools.unwrap_or(false));
+  let _ = tray_status::set_mcp_paused(&app_handle, true);
+  status
+}
+
+#[tauri::command]
+fn resume_mcp_server(
+  app_handle: AppHandle,
+  state: State<'_, std::sync::Arc<mcp_http::McpHttpState>>,
+) -> mcp_http::McpPauseStatus {
+  let status = mcp_http::set_paused(state.inner(), false, false);
+  let _ = tray_status::set_mcp_paused(&app_handle, false);
+  status
+}
+
+#[tauri::command]
+fn mcp_latency_stats(
+  state: State<'_, std::sync::Arc<mcp_http::McpHttpState>>,
+) -> Vec<mcp_http::McpLatencyStats> {
+  mcp_http::latency_stats(state.inner())
+}
+
+#[tauri::command]
+async fn await_mcp_ready(
+  timeout_ms: Option<u64>,
+  state: State<'_, std::sync::Arc<mcp_http::McpHttpState>>,
+) -> Result<mcp_http::McpReadyStatus, String> {
+  let mcp_state = state.inner().clone();
+  tauri::async_runtime::spawn_blocking(move || {
+    mcp_http::await_ready(&mcp_state, timeout_ms.unwrap_or(3000))
+  })
+  .await
+  .map_err(|_| "MCP 就绪检测线程异常退出".to_string())
+}
+
+#[tauri::command]
+fn suggest_mcp_port(
+  preferred: Option<u16>,
+  state: State<'_, std::sync::Arc<mcp_http::McpHttpState>>,
+) -> mcp_http::McpPortSuggestion {
+  mcp_http::suggest_port(state.inner(), preferred)
+}
+
 #[tauri::command]
 async fn start_interactive_worker(
   app_handle: AppHandle,
@@ -280,62 +441,69 @@ async fn start_interactive_worker(
   let ttitle = task_title.clone();
 
   tauri::async_runtime::spawn_blocking(move || {
-    let mut child = spawn_worker_process(&executable_trimmed, &args, cwd, "启动 Worker")?;
+    let queue_state = app_handle.state::<AppState>();
+    let _slot = acquire_worker_slot(&queue_state.worker_queue, &app_handle, &wid);
+    let mut worker = spawn_worker_process(&executable_trimmed, &args, cwd, "启动 Worker")?;
 
     let worker_key = wid.clone();
-    let pid = child.id();
+    let pid = worker.pid;
+    worker_log::reset(&wid);
     {
       let state = app_handle.state::<AppState>();
       let mut running = state.running_workers.lock().unwrap_or_else(|e| e.into_inner());
       running.insert(worker_key.clone(), pid);
     }
 
-    if let Some(mut stdin_handle) = child.stdin.take() {
+    if let Some(stdin_handle) = worker.stdin.as_mut() {
       if let Some(value) = prompt.as_ref() {
         let trimmed = value.trim();
         if !trimmed.is_empty() {
           let _ = stdin_handle.write_all(trimmed.as_bytes());
-          let _ = stdin_handle.write_all(b"\n");
+          let _ = stdin_handle.write_all(worker.newline);
           let _ = stdin_handle.flush();
         }
       }
-      child.stdin = Some(stdin_handle);
     }
 
-    let stdin_handle = child.stdin.take();
-    let stdout = child.stdout.take().ok_or_else(|| "无法捕获 stdout".to_string())?;
-    let stderr = child.stderr.take().ok_or_else(|| "无法捕获 stderr".to_string())?;
-
     {
       let state = app_handle.state::<AppState>();
       let mut sessions = state.worker_sessions.lock().map_err(|_| "会话锁不可用".to_string())?;
-      sessions.insert(wid.clone(), ManagedWorkerSession { stdin: stdin_handle });
+      sessions.insert(
+        wid.clone(),
+        ManagedWorkerSession {
+          pid,
+          stdin: worker.stdin.take(),
+          newline: worker.newline,
+          pty: worker.pty.clone(),
+        },
+      );
     }
 
-    let stdout_app = app_handle.clone();
-    let stdout_wid = wid.clone();
-    let stdout_ttitle = ttitle.clone();
-    let stdout_handle = std::thread::spawn(move || {
-      stream_chunks_app(stdout_app, stdout_wid, stdout_ttitle, "stdout", stdout)
-    });
-
-    let stderr_app = app_handle.clone();
-    let stderr_wid = wid.clone();
-    let stderr_ttitle = ttitle.clone();
-    let stderr_handle = std::thread::spawn(move || {
-      stream_chunks_app(stderr_app, stderr_wid, stderr_ttitle, "stderr", stderr)
-    });
+    let stream_handles: Vec<_> = worker
+      .outputs
+      .drain(..)
+      .map(|(stream, reader)| {
+        let stream_app = app_handle.clone();
+        let stream_wid = wid.clone();
+        let stream_ttitle = ttitle.clone();
+        std::thread::spawn(move || {
+          stream_chunks_app(stream_app, stream_wid, stream_ttitle, stream, reader)
+        })
+      })
+      .collect();
 
-    let status = child.wait().map_err(|error| format!("等待 Worker 退出失败: {error}"))?;
+    let (success, code) = worker.waiter.wait()?;
 
-    let _ = stdout_handle.join();
-    let _ = stderr_handle.join();
+    for handle in stream_handles {
+      let _ = handle.join();
+    }
 
-    {
+    // A missing session means `stop_worker_session` took it over and reports worker-done itself.
+    let cancelled = {
       let state = app_handle.state::<AppState>();
       let mut sessions = state.worker_sessions.lock().unwrap_or_else(|e| e.into_inner());
-      sessions.remove(&wid);
-    }
+      sessions.remove(&wid).is_none()
+    };
 
     {
       let state = app_handle.state::<AppState>();
@@ -343,14 +511,17 @@ async fn start_interactive_worker(
       running.remove(&worker_key);
     }
 
-    let _ = app_handle.emit(
-      "maple://worker-done",
-      WorkerDoneEvent {
-        worker_id: wid,
-        success: status.success(),
-        code: status.code(),
-      },
-    );
+    if !cancelled {
+      let _ = app_handle.emit(
+        "maple://worker-done",
+        WorkerDoneEvent {
+          worker_id: wid,
+          success,
+          code,
+          cancelled: false,
+        },
+      );
+    }
 
     Ok(true)
   })
@@ -374,6 +545,7 @@ fn send_worker_input(
     .get_mut(&worker_id)
     .ok_or_else(|| format!("Worker 会话不存在: {worker_id}"))?;
 
+  let newline = session.newline;
   let stdin = session
     .stdin
     .as_mut()
@@ -384,7 +556,7 @@ fn send_worker_input(
     .map_err(|error| format!("写入 stdin 失败: {error}"))?;
   if append_newline.unwrap_or(true) {
     stdin
-      .write_all(b"\n")
+      .write_all(newline)
       .map_err(|error| format!("写入换行失败: {error}"))?;
   }
   stdin
@@ -395,20 +567,132 @@ fn send_worker_input(
 }
 
 #[tauri::command]
-fn stop_worker_session(
+fn resize_worker_pty(
   worker_id: String,
+  cols: u16,
+  rows: u16,
   state: State<'_, AppState>,
 ) -> Result<bool, String> {
-  let mut sessions = state
+  let sessions = state
     .worker_sessions
     .lock()
     .map_err(|_| "会话锁不可用".to_string())?;
+  let session = sessions
+    .get(&worker_id)
+    .ok_or_else(|| format!("Worker 会话不存在: {worker_id}"))?;
 
-  if sessions.remove(&worker_id).is_some() {
-    Ok(true)
-  } else {
-    Ok(false)
+  // Workers on the pipe fallback have no terminal to resize.
+  match session.pty.as_ref() {
+    Some(pty) => pty.resize(cols, rows),
+    None => Ok(false),
+  }
+}
+
+/// Closes the session's stdin, except that a Windows ConPTY writer is handed back:
+/// console CLIs only see Ctrl+C through it.
+fn take_interrupt_input(session: &mut ManagedWorkerSession) -> Option<Box<dyn Write + Send>> {
+  let stdin = session.stdin.take();
+  #[cfg(target_os = "windows")]
+  {
+    if session.pty.is_some() {
+      return stdin;
+    }
   }
+  drop(stdin);
+  None
+}
+
+#[tauri::command]
+fn stop_worker_session(
+  app_handle: AppHandle,
+  worker_id: String,
+  grace_ms: Option<u64>,
+  state: State<'_, AppState>,
+) -> Result<bool, String> {
+  let pid = {
+    let sessions = state
+      .worker_sessions
+      .lock()
+      .map_err(|_| "会话锁不可用".to_string())?;
+    match sessions.get(&worker_id) {
+      Some(session) => session.pid,
+      None => return Ok(false),
+    }
+  };
+  // A process that already exited is left to its worker thread, which still
+  // owns the session and reports the real exit status.
+  if !process_utils::is_process_alive(pid) {
+    return Ok(false);
+  }
+
+  let session = {
+    let mut sessions = state
+      .worker_sessions
+      .lock()
+      .map_err(|_| "会话锁不可用".to_string())?;
+    sessions.remove(&worker_id)
+  };
+  let Some(mut session) = session else {
+    return Ok(false);
+  };
+  // Close stdin first so the worker is not left blocked on a read while we kill it.
+  let pty_input = take_interrupt_input(&mut session);
+
+  {
+    let mut running = state
+      .running_workers
+      .lock()
+      .map_err(|_| "Worker 进程锁不可用".to_string())?;
+    running.remove(&worker_id);
+  }
+  // Workers spawn helpers of their own (and run under `cmd` on Windows), so the
+  // whole tree has to go. The grace period would block the UI thread, so the
+  // shutdown runs in the background and worker-done is only reported once the
+  // tree is gone.
+  let grace = std::time::Duration::from_millis(grace_ms.unwrap_or(DEFAULT_WORKER_STOP_GRACE_MS));
+  std::thread::spawn(move || {
+    process_utils::terminate_process_tree(pid, grace, pty_input);
+    let _ = app_handle.emit(
+      "maple://worker-done",
+      WorkerDoneEvent {
+        worker_id,
+        success: false,
+        code: None,
+        cancelled: true,
+      },
+    );
+  });
+  Ok(true)
+}
+
+#[tauri::command]
+fn export_worker_log_html(worker_id: String, dest_path: String) -> Result<String, String> {
+  worker_log::export_html(&worker_id, &dest_path)
+}
+
+#[tauri::command]
+fn read_worker_log(worker_id: String) -> Result<String, String> {
+  worker_log::read(&worker_id)
+}
+
+#[tauri::command]
+fn get_worker_log_persistence() -> bool {
+  worker_log::is_persistence_enabled()
+}
+
+#[tauri::command]
+fn set_worker_log_persistence(enabled: bool) -> Result<bool, String> {
+  worker_log::set_persistence_enabled(enabled)
+}
+
+#[tauri::command]
+fn worker_stats(state: State<'_, AppState>) -> worker_queue::WorkerStats {
+  state.worker_queue.stats()
+}
+
+#[tauri::command]
+fn set_worker_concurrency(limit: usize, state: State<'_, AppState>) -> worker_queue::WorkerStats {
+  state.worker_queue.set_limit(limit)
 }
 
 #[tauri::command]
@@ -544,9 +828,37 @@ fn run_command(
     code: output.status.code(),
     stdout: decode_command_output(&output.stdout).trim().to_string(),
     stderr: decode_command_output(&output.stderr).trim().to_string(),
+    combined: None,
   })
 }
 
+/// Carries a UTF-8 sequence cut by a read boundary over to the next read, so
+/// streamed output never decodes half a character into U+FFFD.
+#[derive(Default)]
+struct StreamDecoder {
+  pending: Vec<u8>,
+}
+
+impl StreamDecoder {
+  fn push(&mut self, bytes: &[u8]) -> String {
+    self.pending.extend_from_slice(bytes);
+    let complete = match std::str::from_utf8(&self.pending) {
+      Ok(_) => self.pending.len(),
+      // `error_len() == None` means the bytes are valid so far but end mid-sequence.
+      Err(error) if error.error_len().is_none() => error.valid_up_to(),
+      // Not UTF-8 at all (e.g. GBK); hand everything to the legacy decoder.
+      Err(_) => self.pending.len(),
+    };
+    let chunk: Vec<u8> = self.pending.drain(..complete).collect();
+    decode_command_output(&chunk)
+  }
+
+  fn finish(&mut self) -> String {
+    let rest = std::mem::take(&mut self.pending);
+    decode_command_output(&rest)
+  }
+}
+
 fn decode_command_output(bytes: &[u8]) -> String {
   if bytes.is_empty() {
     return String::new();
@@ -638,40 +950,85 @@ fn apply_worker_process_env(command: &mut Command, cwd: Option<String>) {
   }
 }
 
+/// A spawned worker: a pseudo-terminal session, or a piped child as the fallback.
+struct WorkerProcess {
+  pid: u32,
+  stdin: Option<Box<dyn Write + Send>>,
+  /// `(stream, reader)` pairs; a pseudo terminal merges stderr into stdout.
+  outputs: Vec<(&'static str, Box<dyn Read + Send>)>,
+  newline: &'static [u8],
+  pty: Option<worker_pty::PtyResizer>,
+  waiter: WorkerWaiter,
+}
+
+enum WorkerWaiter {
+  Child(Child),
+  Pty(worker_pty::PtyProcess),
+}
+
+impl WorkerWaiter {
+  fn wait(self) -> Result<(bool, Option<i32>), String> {
+    match self {
+      WorkerWaiter::Child(mut child) => {
+        let status = child
+          .wait()
+          .map_err(|error| format!("等待 Worker 退出失败: {error}"))?;
+        Ok((status.success(), status.code()))
+      }
+      WorkerWaiter::Pty(process) => process.wait(),
+    }
+  }
+}
+
+impl WorkerProcess {
+  fn from_child(mut child: Child) -> Result<Self, String> {
+    let stdout = child.stdout.take().ok_or_else(|| "无法捕获 stdout".to_string())?;
+    let stderr = child.stderr.take().ok_or_else(|| "无法捕获 stderr".to_string())?;
+    Ok(Self {
+      pid: child.id(),
+      stdin: child
+        .stdin
+        .take()
+        .map(|stdin| Box::new(stdin) as Box<dyn Write + Send>),
+      outputs: vec![("stdout", Box::new(stdout)), ("stderr", Box::new(stderr))],
+      newline: b"\n",
+      pty: None,
+      waiter: WorkerWaiter::Child(child),
+    })
+  }
+}
+
 fn spawn_worker_process(
   executable: &str,
   args: &[String],
   cwd: Option<String>,
   action_label: &str,
-) -> Result<Child, String> {
-  #[cfg(target_os = "windows")]
-  {
-    let mut command = process_utils::build_cli_command(executable, args);
-    apply_worker_process_env(&mut command, cwd);
-    return command
-      .spawn()
-      .map_err(|error| format!("{action_label}失败: {error}"));
-  }
-
-  #[cfg(not(target_os = "windows"))]
-  {
-    let mut pty_command = Command::new("script");
-    pty_command.arg("-q").arg("/dev/null").arg(executable).args(args);
-    apply_worker_process_env(&mut pty_command, cwd.clone());
-
-    match pty_command.spawn() {
-      Ok(child) => Ok(child),
-      Err(pty_error) => {
-        let mut fallback = process_utils::build_cli_command(executable, args);
-        apply_worker_process_env(&mut fallback, cwd);
-        fallback.spawn().map_err(|fallback_error| {
-          format!("{action_label}失败（PTY+回退均失败）: PTY={pty_error}; fallback={fallback_error}")
-        })
-      }
+) -> Result<WorkerProcess, String> {
+  let mut command = process_utils::build_cli_command(executable, args);
+  apply_worker_process_env(&mut command, cwd);
+  // A pseudo terminal gives interactive CLIs colors, line editing and a window
+  // size; plain pipes are the fallback.
+  let pty_error = match worker_pty::spawn(&command) {
+    Ok((process, reader, writer)) => {
+      return Ok(WorkerProcess {
+        pid: process.pid(),
+        stdin: Some(writer),
+        outputs: vec![("stdout", reader)],
+        newline: b"\r",
+        pty: Some(process.resizer()),
+        waiter: WorkerWaiter::Pty(process),
+      });
     }
-  }
+    Err(error) => error,
+  };
+  eprintln!("PTY 不可用，改用管道: {pty_error}");
+  let child = command.spawn().map_err(|fallback_error| {
+    format!("{action_label}失败（PTY+回退均失败）: PTY={pty_error}; fallback={fallback_error}")
+  })?;
+  WorkerProcess::from_child(child)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn run_command_stream(
   window: tauri::Window,
   worker_id: String,
@@ -680,55 +1037,94 @@ fn run_command_stream(
   args: Vec<String>,
   prompt: Option<String>,
   cwd: Option<String>,
+  line_buffered: bool,
 ) -> Result<WorkerCommandResult, String> {
   let executable = executable.trim().to_string();
   if executable.is_empty() {
     return Err("worker executable 不能为空".to_string());
   }
 
-  let mut child = spawn_worker_process(&executable, &args, cwd, "执行命令")?;
+  let queue_state = window.state::<AppState>();
+  let _slot = acquire_worker_slot(&queue_state.worker_queue, &window, &worker_id);
+  let mut worker = spawn_worker_process(&executable, &args, cwd, "执行命令")?;
 
   let worker_key = worker_id.clone();
-  let pid = child.id();
+  let pid = worker.pid;
+  worker_log::reset(&worker_id);
   {
     let state = window.state::<AppState>();
     let mut running = state.running_workers.lock().unwrap_or_else(|e| e.into_inner());
     running.insert(worker_key.clone(), pid);
   }
 
-  if let Some(mut stdin_handle) = child.stdin.take() {
+  if let Some(mut stdin_handle) = worker.stdin.take() {
     if let Some(value) = prompt.as_ref() {
       if !value.trim().is_empty() {
         let _ = stdin_handle.write_all(value.as_bytes());
-        let _ = stdin_handle.write_all(b"\n");
+        let _ = stdin_handle.write_all(worker.newline);
         let _ = stdin_handle.flush();
       }
     }
   }
 
-  let stdout = child.stdout.take().ok_or_else(|| "无法捕获 stdout".to_string())?;
-  let stderr = child.stderr.take().ok_or_else(|| "无法捕获 stderr".to_string())?;
-
-  let stdout_window = window.clone();
-  let stdout_worker_id = worker_id.clone();
-  let stdout_task_title = task_title.clone();
-  let stdout_handle = std::thread::spawn(move || {
-    stream_chunks(stdout_window, stdout_worker_id, stdout_task_title, "stdout", stdout)
-  });
-
-  let stderr_window = window.clone();
-  let stderr_worker_id = worker_id.clone();
-  let stderr_task_title = task_title.clone();
-  let stderr_handle = std::thread::spawn(move || {
-    stream_chunks(stderr_window, stderr_worker_id, stderr_task_title, "stderr", stderr)
-  });
+  // No stdin here (the prompt is the whole input), but registering lets `stop_worker_session` cancel it.
+  {
+    let state = window.state::<AppState>();
+    let mut sessions = state.worker_sessions.lock().unwrap_or_else(|e| e.into_inner());
+    sessions.insert(
+      worker_id.clone(),
+      ManagedWorkerSession {
+        pid,
+        stdin: None,
+        newline: worker.newline,
+        pty: worker.pty.clone(),
+      },
+    );
+  }
 
-  let status = child
-    .wait()
-    .map_err(|error| format!("等待 Worker 退出失败: {error}"))?;
+  let transcript = std::sync::Arc::new(Mutex::new(String::new()));
+  let stream_handles: Vec<_> = worker
+    .outputs
+    .drain(..)
+    .map(|(stream, reader)| {
+      let stream_window = window.clone();
+      let stream_worker_id = worker_id.clone();
+      let stream_task_title = task_title.clone();
+      let stream_transcript = transcript.clone();
+      let handle = std::thread::spawn(move || {
+        stream_chunks(
+          stream_window,
+          stream_worker_id,
+          stream_task_title,
+          stream,
+          reader,
+          line_buffered,
+          &stream_transcript,
+        )
+      });
+      (stream, handle)
+    })
+    .collect();
+
+  let (success, code) = worker.waiter.wait()?;
+
+  let mut stdout_text = String::new();
+  let mut stderr_text = String::new();
+  for (stream, handle) in stream_handles {
+    let text = handle.join().unwrap_or_default();
+    if stream == "stderr" {
+      stderr_text.push_str(&text);
+    } else {
+      stdout_text.push_str(&text);
+    }
+  }
 
-  let stdout_text = stdout_handle.join().unwrap_or_default();
-  let stderr_text = stderr_handle.join().unwrap_or_default();
+  // As in the interactive path, a missing session means `stop_worker_session` reports it.
+  let cancelled = {
+    let state = window.state::<AppState>();
+    let mut sessions = state.worker_sessions.lock().unwrap_or_else(|e| e.into_inner());
+    sessions.remove(&worker_key).is_none()
+  };
 
   {
     let state = window.state::<AppState>();
@@ -736,11 +1132,29 @@ fn run_command_stream(
     running.remove(&worker_key);
   }
 
+  if !cancelled {
+    let _ = window.emit(
+      "maple://worker-done",
+      WorkerDoneEvent {
+        worker_id: worker_id.clone(),
+        success,
+        code,
+        cancelled: false,
+      },
+    );
+  }
+
   Ok(WorkerCommandResult {
-    success: status.success(),
-    code: status.code(),
+    success,
+    code,
     stdout: stdout_text.trim().to_string(),
     stderr: stderr_text.trim().to_string(),
+    combined: Some(
+      transcript
+        .lock()
+        .map(|text| text.trim().to_string())
+        .unwrap_or_default(),
+    ),
   })
 }
 
@@ -757,37 +1171,109 @@ fn is_conpty_noise(text: &str) -> bool {
   CONPTY_NOISE.iter().any(|pat| text.contains(pat))
 }
 
+/// Splits streamed text into complete lines (newline kept) and numbers them.
+#[derive(Default)]
+struct LineBuffer {
+  partial: String,
+  next_index: u64,
+}
+
+impl LineBuffer {
+  fn push(&mut self, chunk: &str) -> Vec<(Option<u64>, String)> {
+    self.partial.push_str(chunk);
+    let mut lines = Vec::new();
+    while let Some(pos) = self.partial.find('\n') {
+      let line: String = self.partial.drain(..=pos).collect();
+      lines.push((Some(self.next_index), line));
+      self.next_index += 1;
+    }
+    lines
+  }
+
+  fn finish(&mut self) -> Option<(Option<u64>, String)> {
+    if self.partial.is_empty() {
+      return None;
+    }
+    let line = std::mem::take(&mut self.partial);
+    self.next_index += 1;
+    Some((Some(self.next_index - 1), line))
+  }
+}
+
+/// Reads `reader` to the end, decoding across read boundaries. Each chunk is appended
+/// to the shared `transcript` as soon as its read completes, so the stdout and stderr
+/// threads together record the order in which output was produced. Returns this
+/// stream's own text.
+fn capture_stream<R: Read>(
+  mut reader: R,
+  transcript: &Mutex<String>,
+  mut on_chunk: impl FnMut(&str),
+) -> String {
+  let mut out = String::new();
+  let mut buffer = [0u8; 4096];
+  let mut decoder = StreamDecoder::default();
+  let mut eof = false;
+
+  while !eof {
+    let chunk = match reader.read(&mut buffer) {
+      Ok(0) | Err(_) => {
+        eof = true;
+        decoder.finish()
+      }
+      Ok(size) => decoder.push(&buffer[..size]),
+    };
+    if chunk.is_empty() || is_conpty_noise(&chunk) {
+      continue;
+    }
+    out.push_str(&chunk);
+    transcript
+      .lock()
+      .unwrap_or_else(|e| e.into_inner())
+      .push_str(&chunk);
+    on_chunk(&chunk);
+  }
+
+  out
+}
+
+/// With `line_buffered`, emits one `worker-log` event per complete line instead of
+/// one per read; interactive PTY rendering needs the raw chunks, so it is opt-in.
 fn stream_chunks<R: Read>(
   window: tauri::Window,
   worker_id: String,
   task_title: String,
   stream: &str,
-  mut reader: R,
+  reader: R,
+  line_buffered: bool,
+  transcript: &Mutex<String>,
 ) -> String {
-  let mut out = String::new();
-  let mut buffer = [0u8; 4096];
-
-  loop {
-    match reader.read(&mut buffer) {
-      Ok(0) => break,
-      Ok(size) => {
-        let chunk = decode_command_output(&buffer[..size]);
-        if is_conpty_noise(&chunk) {
-          continue;
+  let emit = |line_index: Option<u64>, line: String| {
+    let _ = window.emit(
+      "maple://worker-log",
+      WorkerLogEvent {
+        worker_id: worker_id.clone(),
+        task_title: task_title.clone(),
+        stream: stream.to_string(),
+        line,
+        line_index,
+      },
+    );
+  };
+  let mut lines = line_buffered.then(LineBuffer::default);
+
+  let out = capture_stream(reader, transcript, |chunk| {
+    worker_log::append(&worker_id, chunk);
+    match lines.as_mut() {
+      Some(lines) => {
+        for (line_index, line) in lines.push(chunk) {
+          emit(line_index, line);
         }
-        out.push_str(&chunk);
-        let _ = window.emit(
-          "maple://worker-log",
-          WorkerLogEvent {
-            worker_id: worker_id.clone(),
-            task_title: task_title.clone(),
-            stream: stream.to_string(),
-            line: chunk,
-          },
-        );
       }
-      Err(_) => break,
+      None => emit(None, chunk.to_string()),
     }
+  });
+  if let Some((line_index, line)) = lines.as_mut().and_then(LineBuffer::finish) {
+    emit(line_index, line);
   }
 
   out
@@ -802,28 +1288,32 @@ fn stream_chunks_app<R: Read>(
 ) -> String {
   let mut out = String::new();
   let mut buffer = [0u8; 4096];
-
-  loop {
-    match reader.read(&mut buffer) {
-      Ok(0) => break,
-      Ok(size) => {
-        let chunk = decode_command_output(&buffer[..size]);
-        if is_conpty_noise(&chunk) {
-          continue;
-        }
-        out.push_str(&chunk);
-        let _ = app_handle.emit(
-          "maple://worker-log",
-          WorkerLogEvent {
-            worker_id: worker_id.clone(),
-            task_title: task_title.clone(),
-            stream: stream.to_string(),
-            line: chunk,
-          },
-        );
+  let mut decoder = StreamDecoder::default();
+  let mut eof = false;
+
+  while !eof {
+    let chunk = match reader.read(&mut buffer) {
+      Ok(0) | Err(_) => {
+        eof = true;
+        decoder.finish()
       }
-      Err(_) => break,
+      Ok(size) => decoder.push(&buffer[..size]),
+    };
+    if chunk.is_empty() || is_conpty_noise(&chunk) {
+      continue;
     }
+    out.push_str(&chunk);
+    worker_log::append(&worker_id, &chunk);
+    let _ = app_handle.emit(
+      "maple://worker-log",
+      WorkerLogEvent {
+        worker_id: worker_id.clone(),
+        task_title: task_title.clone(),
+        stream: stream.to_string(),
+        line: chunk,
+        line_index: None,
+      },
+    );
   }
 
   out
@@ -863,11 +1353,37 @@ fn is_valid_asset_file_name(value: &str) -> bool {
 }
 
 #[tauri::command]
-fn write_state_file(json: String) -> Result<(), String> {
+fn write_state_file(app_handle: AppHandle, json: String) -> Result<(), String> {
   let dir = maple_home_dir()?;
   std::fs::create_dir_all(&dir).map_err(|e| format!("创建 .maple 目录失败: {e}"))?;
   let path = dir.join("state.json");
-  std::fs::write(&path, json.as_bytes()).map_err(|e| format!("写入状态文件失败: {e}"))?;
+  let _state_guard = mcp_http::lock_state();
+
+  // Keep reports an MCP tool wrote after the UI took its snapshot.
+  let mut json = json;
+  let mut reload_project_count = None;
+  if let (Ok(mut incoming), Some(on_disk)) = (
+    serde_json::from_str::<serde_json::Value>(&json),
+    std::fs::read_to_string(&path)
+      .ok()
+      .and_then(|raw| serde_json::from_str::<serde_json::Value>(&raw).ok()),
+  ) {
+    if mcp_http::merge_disk_only_reports(&mut incoming, &on_disk) > 0 {
+      json = serde_json::to_string(&incoming).map_err(|e| format!("序列化状态失败: {e}"))?;
+      reload_project_count = incoming.as_array().map(Vec::len);
+    }
+  }
+
+  if let Err(e) = mcp_http::backup_state_before_write(json.as_bytes()) {
+    eprintln!("[maple-state] {e}");
+  }
+  state_watcher::record_self_write(json.as_bytes());
+  maple_fs::write_file_atomic(&path, json.as_bytes()).map_err(|e| format!("写入状态文件失败: {e}"))?;
+  mcp_http::invalidate_state_cache();
+  // The UI is missing the kept reports, so have it re-read the file.
+  if let Some(project_count) = reload_project_count {
+    mcp_http::emit_state_reloaded(&app_handle, "merge", project_count);
+  }
   Ok(())
 }
 
@@ -880,6 +1396,34 @@ fn read_state_file() -> Result<String, String> {
   std::fs::read_to_string(&path).map_err(|e| format!("读取状态文件失败: {e}"))
 }
 
+#[tauri::command]
+fn replace_state(app_handle: AppHandle, json: String) -> Result<usize, String> {
+  state_replace::replace_state(&app_handle, &json)
+}
+
+#[tauri::command]
+fn restore_state_backup(app_handle: AppHandle, index: usize) -> Result<usize, String> {
+  state_replace::restore_state_backup(&app_handle, index)
+}
+
+#[tauri::command]
+fn apply_state_patch(patch_json: String) -> Result<Vec<String>, String> {
+  state_patch::apply_state_patch(&patch_json)
+}
+
+#[tauri::command]
+async fn import_github_issues(
+  app_handle: AppHandle,
+  project: String,
+  issues_json: String,
+) -> Result<state_import::GithubImportReport, String> {
+  tauri::async_runtime::spawn_blocking(move || {
+    state_import::import_github_issues(&app_handle, &project, &issues_json)
+  })
+  .await
+  .map_err(|_| "导入线程异常退出".to_string())?
+}
+
 #[tauri::command]
 fn read_constitution_file() -> Result<String, String> {
   let path = constitution_path()?;
@@ -945,17 +1489,95 @@ fn save_asset_file(file_name: String, bytes_base64: String) -> Result<bool, Stri
   let bytes = base64::engine::general_purpose::STANDARD
     .decode(bytes_base64.trim().as_bytes())
     .map_err(|e| format!("解码图片数据失败: {e}"))?;
-
   let dir = asset_dir()?;
   let path = dir.join(trimmed_name);
   if path.exists() {
     return Ok(true);
   }
 
-  std::fs::write(&path, &bytes).map_err(|e| format!("写入图片文件失败: {e}"))?;
+  // Only new writes are checked; assets already on disk stay readable as they are.
+  let ext = trimmed_name.split('.').nth(1).unwrap_or_default();
+  maple_fs::check_content_matches_extension(&bytes, ext)?;
+
+  write_asset_bytes(&path, &bytes)?;
   Ok(true)
 }
 
+fn write_asset_bytes(path: &Path, bytes: &[u8]) -> Result<(), String> {
+  std::fs::write(path, bytes).map_err(|e| format!("写入图片文件失败: {e}"))
+}
+
+/// Decodes one bundled asset and checks it the way a fresh upload would be
+/// checked, plus that the bytes hash to the `<sha256>` in its name, so a bundle
+/// can't plant other content under a hash another project references.
+fn decode_bundle_asset(file_name: &str, data: &serde_json::Value) -> Result<Vec<u8>, String> {
+  if !is_valid_asset_file_name(file_name) {
+    return Err(format!("项目包包含无效的 asset 文件名: {file_name}"));
+  }
+  let encoded = data
+    .as_str()
+    .ok_or_else(|| format!("asset {file_name} 的数据不是 base64 字符串。"))?;
+  if encoded.len() / 4 * 3 > MAX_IMPORT_ASSET_BYTES {
+    return Err(format!("asset {file_name} 过大（上限 {MAX_IMPORT_ASSET_BYTES} bytes）。"));
+  }
+  let bytes = base64::engine::general_purpose::STANDARD
+    .decode(encoded.trim().as_bytes())
+    .map_err(|e| format!("asset {file_name} 解码失败: {e}"))?;
+  let (hash, ext) = file_name.split_once('.').unwrap_or((file_name, ""));
+  let digest: String = Sha256::digest(&bytes).iter().map(|b| format!("{b:02x}")).collect();
+  if digest != hash {
+    return Err(format!("asset {file_name} 的内容与文件名中的 sha256 不符。"));
+  }
+  maple_fs::check_content_matches_extension(&bytes, ext).map_err(|e| format!("asset {file_name}: {e}"))?;
+  Ok(bytes)
+}
+
+/// Per-asset cap for project bundles; larger files are rejected rather than imported.
+const MAX_IMPORT_ASSET_BYTES: usize = 25 * 1024 * 1024;
+
+#[tauri::command]
+fn export_project(name: String) -> Result<String, String> {
+  state_import::export_project(&name)
+}
+
+#[tauri::command]
+fn import_project(
+  app_handle: AppHandle,
+  bundle: String,
+  rename_on_conflict: Option<bool>,
+) -> Result<String, String> {
+  let doc: serde_json::Value =
+    serde_json::from_str(&bundle).map_err(|e| format!("项目包不是合法 JSON: {e}"))?;
+  if doc.get("format").and_then(|v| v.as_str()) != Some(state_import::PROJECT_BUNDLE_FORMAT) {
+    return Err("不是 Maple 项目包。".to_string());
+  }
+  let version = doc.get("version").and_then(|v| v.as_u64()).unwrap_or(0);
+  if version == 0 || version > state_import::PROJECT_BUNDLE_VERSION {
+    return Err(format!("不支持的项目包版本: {version}"));
+  }
+  let project = doc
+    .get("project")
+    .cloned()
+    .ok_or_else(|| "项目包缺少 project。".to_string())?;
+
+  // Decode and verify every asset and parse the project before writing
+  // anything, so a bad bundle leaves nothing behind.
+  state_import::validate_project(&project)?;
+  let mut assets: Vec<(String, Vec<u8>)> = Vec::new();
+  for (file_name, data) in doc.get("assets").and_then(|v| v.as_object()).into_iter().flatten() {
+    assets.push((file_name.clone(), decode_bundle_asset(file_name, data)?));
+  }
+  let dir = asset_dir()?;
+  for (file_name, bytes) in assets {
+    let path = dir.join(&file_name);
+    if !path.exists() {
+      write_asset_bytes(&path, &bytes)?;
+    }
+  }
+
+  state_import::import_project(&app_handle, project, rename_on_conflict.unwrap_or(true))
+}
+
 #[tauri::command]
 fn get_asset_file_path(file_name: String) -> Result<String, String> {
   let trimmed_name = file_name.trim();
@@ -986,6 +1608,20 @@ fn read_asset_file_base64(file_name: String) -> Result<String, String> {
   Ok(base64::engine::general_purpose::STANDARD.encode(bytes))
 }
 
+#[tauri::command]
+async fn benchmark_asset_io(sample_size: Option<usize>) -> Result<maple_fs::AssetIoBenchmark, String> {
+  tauri::async_runtime::spawn_blocking(move || maple_fs::benchmark_asset_io(sample_size.unwrap_or(50)))
+    .await
+    .map_err(|_| "asset 基准测试线程异常退出".to_string())?
+}
+
+#[tauri::command]
+async fn asset_storage_stats(largest: Option<usize>) -> Result<maple_fs::AssetStorageStats, String> {
+  tauri::async_runtime::spawn_blocking(move || maple_fs::asset_storage_stats(largest.unwrap_or(10).min(100)))
+    .await
+    .map_err(|_| "asset 统计线程异常退出".to_string())?
+}
+
 #[tauri::command]
 fn sync_tray_task_badge(
   snapshot: tray_status::TrayTaskSnapshot,
@@ -994,37 +1630,61 @@ fn sync_tray_task_badge(
   tray_status::sync(&app_handle, &snapshot).map_err(|error| format!("同步托盘状态失败: {error}"))
 }
 
+#[tauri::command]
+fn reset_tray(app_handle: AppHandle) -> Result<(), String> {
+  tray_status::reset(&app_handle).map_err(|error| format!("重置托盘状态失败: {error}"))
+}
+
+/// Runs on window close and again on `RunEvent::Exit`, so quitting from the tray
+/// doesn't leave the MCP server holding its port or workers running detached.
 fn cleanup_background_processes(app_handle: &AppHandle) {
   let state = app_handle.state::<AppState>();
 
   {
     let mut guard = state.mcp_server.lock().unwrap_or_else(|e| e.into_inner());
     if let Some(mut server) = guard.take() {
+      // The server may be a wrapper (npx, node) whose own children hold the port.
+      process_utils::kill_process_tree(server.child.id());
       let _ = server.child.kill();
       let _ = server.child.wait();
     }
   }
 
-  let pids = {
-    let mut running = state.running_workers.lock().unwrap_or_else(|e| e.into_inner());
-    let pids: Vec<u32> = running.values().copied().collect();
-    running.clear();
-    pids
+  let mut workers: Vec<(u32, Option<Box<dyn Write + Send>>)> = {
+    let mut sessions = state.worker_sessions.lock().unwrap_or_else(|e| e.into_inner());
+    sessions
+      .drain()
+      .map(|(_, mut session)| (session.pid, take_interrupt_input(&mut session)))
+      .collect()
   };
 
-  for pid in pids {
-    process_utils::kill_process_tree(pid);
+  {
+    let mut running = state.running_workers.lock().unwrap_or_else(|e| e.into_inner());
+    for (_, pid) in running.drain() {
+      if !workers.iter().any(|(known, _)| *known == pid) {
+        workers.push((pid, None));
+      }
+    }
   }
 
-  {
-    let mut sessions = state.worker_sessions.lock().unwrap_or_else(|e| e.into_inner());
-    sessions.clear();
+  // Workers get the same interrupt-then-kill shutdown as a manual stop, in
+  // parallel, so interactive CLIs can still flush their session files.
+  let grace = std::time::Duration::from_millis(EXIT_WORKER_STOP_GRACE_MS);
+  let handles: Vec<_> = workers
+    .into_iter()
+    .map(|(pid, pty_input)| {
+      std::thread::spawn(move || process_utils::terminate_process_tree(pid, grace, pty_input))
+    })
+    .collect();
+  for handle in handles {
+    let _ = handle.join();
   }
 }
 
 #[tauri::command]
 fn stop_worker_process(
   worker_id: String,
+  grace_ms: Option<u64>,
   state: State<'_, AppState>,
 ) -> Result<bool, String> {
   let pid = {
@@ -1035,16 +1695,19 @@ fn stop_worker_process(
     running.remove(&worker_id)
   };
 
-  {
+  // Only close stdin: the worker thread still owns the session and reports
+  // worker-done once the process is gone.
+  let pty_input = {
     let mut sessions = state
       .worker_sessions
       .lock()
       .map_err(|_| "会话锁不可用".to_string())?;
-    sessions.remove(&worker_id);
-  }
+    sessions.get_mut(&worker_id).and_then(take_interrupt_input)
+  };
 
   if let Some(pid) = pid {
-    process_utils::kill_process_tree(pid);
+    let grace = std::time::Duration::from_millis(grace_ms.unwrap_or(DEFAULT_WORKER_STOP_GRACE_MS));
+    std::thread::spawn(move || process_utils::terminate_process_tree(pid, grace, pty_input));
     Ok(true)
   } else {
     Ok(false)
@@ -1064,6 +1727,9 @@ fn main() {
     })
     .setup(|app| {
       mcp_http::start(app.handle().clone());
+      if let Err(error) = state_watcher::start(app.handle().clone()) {
+        eprintln!("{error}");
+      }
       if let Err(error) = tray_status::init(app.handle()) {
         eprintln!("failed to initialize tray status: {error}");
       }
@@ -1071,29 +1737,194 @@ fn main() {
     })
     .invoke_handler(tauri::generate_handler![
       probe_worker,
+      probe_worker_version,
       probe_install_targets,
       get_install_meta,
+      versions,
       install_mcp_skills,
+      uninstall_mcp_skills,
       run_worker,
       start_interactive_worker,
       send_worker_input,
+      resize_worker_pty,
       stop_worker_session,
       stop_worker_process,
+      export_worker_log_html,
+      read_worker_log,
+      get_worker_log_persistence,
+      set_worker_log_persistence,
+      worker_stats,
+      set_worker_concurrency,
       open_path,
       open_in_editor,
       start_mcp_server,
       stop_mcp_server,
       mcp_server_status,
+      pause_mcp_server,
+      resume_mcp_server,
+      mcp_latency_stats,
+      await_mcp_ready,
+      suggest_mcp_port,
       write_state_file,
       read_state_file,
+      replace_state,
+      restore_state_backup,
+      apply_state_patch,
+      import_github_issues,
       read_constitution_file,
       write_constitution_file,
       query_codex_usage,
       save_asset_file,
       get_asset_file_path,
       read_asset_file_base64,
-      sync_tray_task_badge
+      export_project,
+      import_project,
+      benchmark_asset_io,
+      asset_storage_stats,
+      sync_tray_task_badge,
+      reset_tray
     ])
-    .run(tauri::generate_context!())
-    .expect("error while running maple desktop");
+    .build(tauri::generate_context!())
+    .expect("error while building maple desktop")
+    .run(|app_handle, event| {
+      if matches!(event, tauri::RunEvent::Exit) {
+        cleanup_background_processes(app_handle);
+      }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn decode_split(bytes: &[u8], at: usize) -> String {
+    let mut decoder = StreamDecoder::default();
+    let mut text = decoder.push(&bytes[..at]);
+    text.push_str(&decoder.push(&bytes[at..]));
+    text.push_str(&decoder.finish());
+    text
+  }
+
+  #[test]
+  fn stream_decoder_rejoins_multibyte_sequences_split_at_any_offset() {
+    // 2-, 3- and 4-byte sequences, alone and next to each other.
+    for sample in ["aé b", "进度：完成", "ok 😀 done", "é中😀"] {
+      let bytes = sample.as_bytes();
+      for at in 0..=bytes.len() {
+        assert_eq!(decode_split(bytes, at), sample, "split at byte {at}");
+      }
+    }
+  }
+
+  #[test]
+  fn stream_decoder_handles_one_byte_reads() {
+    let sample = "日志 😀 — done";
+    let mut decoder = StreamDecoder::default();
+    let mut text = String::new();
+    for byte in sample.as_bytes() {
+      text.push_str(&decoder.push(std::slice::from_ref(byte)));
+    }
+    text.push_str(&decoder.finish());
+    assert_eq!(text, sample);
+  }
+
+  #[test]
+  fn stream_decoder_flushes_a_truncated_trailing_sequence_on_finish() {
+    let mut decoder = StreamDecoder::default();
+    // "中" is E4 B8 AD; the stream ends after its first two bytes.
+    assert_eq!(decoder.push(&[b'o', b'k', 0xE4, 0xB8]), "ok");
+    let rest = decoder.finish();
+    assert!(!rest.is_empty(), "the dangling bytes must not be dropped");
+    #[cfg(not(target_os = "windows"))]
+    assert_eq!(rest, "\u{FFFD}");
+    assert!(decoder.pending.is_empty());
+  }
+
+  /// Hands out whatever the test sends, one message per read; EOF once the sender is dropped.
+  struct ChannelReader(std::sync::mpsc::Receiver<Vec<u8>>);
+
+  impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+      let Ok(bytes) = self.0.recv() else {
+        return Ok(0);
+      };
+      buf[..bytes.len()].copy_from_slice(&bytes);
+      Ok(bytes.len())
+    }
+  }
+
+  #[test]
+  fn captured_streams_keep_their_interleaving_in_the_transcript() {
+    let transcript = std::sync::Arc::new(Mutex::new(String::new()));
+    let mut senders = Vec::new();
+    let mut handles = Vec::new();
+    for _ in ["stdout", "stderr"] {
+      let (sender, receiver) = std::sync::mpsc::channel::<Vec<u8>>();
+      let transcript = transcript.clone();
+      senders.push(sender);
+      handles.push(std::thread::spawn(move || {
+        capture_stream(ChannelReader(receiver), &transcript, |_| {})
+      }));
+    }
+
+    let writes = [
+      (0, "compiling\n"),
+      (1, "warning: unused\n"),
+      (0, "running 3 tests\n"),
+      (1, "error: test failed\n"),
+      (1, "note: see log\n"),
+      (0, "done\n"),
+    ];
+    let mut expected = String::new();
+    for (stream, text) in writes {
+      senders[stream].send(text.as_bytes().to_vec()).unwrap();
+      expected.push_str(text);
+      // Wait for the capture thread before producing the next write, as a real
+      // worker's output is spread over time.
+      let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+      while transcript.lock().unwrap().len() < expected.len() {
+        assert!(std::time::Instant::now() < deadline, "capture thread stalled");
+        std::thread::sleep(std::time::Duration::from_millis(1));
+      }
+    }
+    drop(senders);
+
+    let outputs: Vec<String> = handles.into_iter().map(|handle| handle.join().unwrap()).collect();
+    assert_eq!(*transcript.lock().unwrap(), expected);
+    assert_eq!(outputs[0], "compiling\nrunning 3 tests\ndone\n");
+    assert_eq!(outputs[1], "warning: unused\nerror: test failed\nnote: see log\n");
+  }
+
+  #[test]
+  fn stream_decoder_does_not_hold_back_invalid_bytes() {
+    let mut decoder = StreamDecoder::default();
+    let text = decoder.push(&[b'a', 0xFF, b'b']);
+    assert!(text.starts_with('a') && text.ends_with('b'), "{text:?}");
+    assert!(decoder.pending.is_empty());
+    assert_eq!(decoder.finish(), "");
+  }
+
+  fn bundle_asset(bytes: &[u8], ext: &str) -> (String, serde_json::Value) {
+    let hash: String = Sha256::digest(bytes).iter().map(|b| format!("{b:02x}")).collect();
+    let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
+    (format!("{hash}.{ext}"), serde_json::Value::String(encoded))
+  }
+
+  #[test]
+  fn bundle_assets_must_match_their_hash_and_extension() {
+    let png = b"\x89PNG\r\n\x1a\nrest-of-image";
+    let (name, data) = bundle_asset(png, "png");
+    assert_eq!(decode_bundle_asset(&name, &data).unwrap(), png);
+
+    let (_, other) = bundle_asset(b"\x89PNG\r\n\x1a\nsomething else", "png");
+    let err = decode_bundle_asset(&name, &other).unwrap_err();
+    assert!(err.contains("sha256"), "{err}");
+
+    let (gif_name, gif_data) = bundle_asset(b"GIF89a....", "png");
+    assert!(decode_bundle_asset(&gif_name, &gif_data).is_err());
+
+    let (_, data) = bundle_asset(png, "png");
+    assert!(decode_bundle_asset("../escape.png", &data).is_err());
+    assert!(decode_bundle_asset(&name, &serde_json::json!(42)).is_err());
+  }
 }
@@ -1,4 +1,7 @@
-use std::path::PathBuf;
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 
 fn read_env_non_empty(key: &str) -> Option<String> {
   let value = std::env::var(key).ok()?;
@@ -24,7 +27,16 @@ pub fn user_home_dir() -> Result<PathBuf, String> {
   Err("无法获取用户 Home 目录".to_string())
 }
 
+/// `~/.maple`, unless `MAPLE_HOME` points somewhere else (isolated test
+/// instances, data on another drive). The override must be an absolute path.
 pub fn maple_home_dir() -> Result<PathBuf, String> {
+  if let Some(custom) = read_env_non_empty("MAPLE_HOME") {
+    let path = PathBuf::from(custom);
+    if !path.is_absolute() {
+      return Err(format!("MAPLE_HOME 必须是绝对路径: {}", path.display()));
+    }
+    return Ok(path);
+  }
   Ok(user_home_dir()?.join(".maple"))
 }
 
@@ -34,6 +46,48 @@ pub fn asset_dir() -> Result<PathBuf, String> {
   Ok(dir)
 }
 
+static ATOMIC_WRITE_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// Writes to a unique temp file next to `path`, fsyncs it and renames it over
+/// `path`, so readers see either the old or the new content, never a partial file.
+pub fn write_file_atomic(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+  let file_name = path
+    .file_name()
+    .map(|name| name.to_string_lossy().to_string())
+    .unwrap_or_else(|| "file".to_string());
+  let sequence = ATOMIC_WRITE_SEQUENCE.fetch_add(1, Ordering::Relaxed);
+  let tmp = path.with_file_name(format!(".{file_name}.{}.{sequence}.tmp", std::process::id()));
+  let result = (|| {
+    let mut file = std::fs::File::create(&tmp)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    drop(file);
+    rename_with_retry(&tmp, path)
+  })();
+  if result.is_err() {
+    let _ = std::fs::remove_file(&tmp);
+  }
+  result
+}
+
+/// On Windows, replacing a file fails with access denied while another process
+/// (antivirus, indexer, a reader) briefly holds it open; retry a few times.
+fn rename_with_retry(from: &Path, to: &Path) -> std::io::Result<()> {
+  let attempts = if cfg!(windows) { 10 } else { 1 };
+  let mut last_error = None;
+  for attempt in 0..attempts {
+    match std::fs::rename(from, to) {
+      Ok(()) => return Ok(()),
+      Err(error) if error.kind() == std::io::ErrorKind::PermissionDenied && attempt + 1 < attempts => {
+        last_error = Some(error);
+        std::thread::sleep(std::time::Duration::from_millis(20 * (attempt as u64 + 1)));
+      }
+      Err(error) => return Err(error),
+    }
+  }
+  Err(last_error.unwrap_or_else(|| std::io::Error::other("rename failed")))
+}
+
 pub fn is_valid_asset_file_name(value: &str) -> bool {
   let trimmed = value.trim();
   if trimmed.len() < 66 || trimmed.len() > 73 {
@@ -57,3 +111,276 @@ pub fn is_valid_asset_file_name(value: &str) -> bool {
   true
 }
 
+/// Identifies PNG, JPEG, WebP and GIF data from its leading magic bytes.
+pub fn sniff_image_extension(bytes: &[u8]) -> Option<&'static str> {
+  if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+    return Some("png");
+  }
+  if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+    return Some("jpg");
+  }
+  if bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+    return Some("webp");
+  }
+  if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+    return Some("gif");
+  }
+  None
+}
+
+/// Checks `bytes` against a raster image extension. Extensions without a
+/// signature we recognise (svg, pdf, ...) are accepted as-is.
+pub fn check_content_matches_extension(bytes: &[u8], ext: &str) -> Result<(), String> {
+  let claimed = match ext.trim().to_ascii_lowercase().as_str() {
+    "png" => "png",
+    "jpg" | "jpeg" => "jpg",
+    "webp" => "webp",
+    "gif" => "gif",
+    _ => return Ok(()),
+  };
+  match sniff_image_extension(bytes) {
+    Some(actual) if actual == claimed => Ok(()),
+    Some(actual) => Err(format!("文件内容与扩展名不符：声明为 .{ext}，实际为 {actual}。")),
+    None => Err(format!("文件内容不是有效的 .{ext} 图片。")),
+  }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetIoPass {
+  pub total_ms: f64,
+  pub bytes_per_sec: f64,
+  pub p50_ms: f64,
+  pub p95_ms: f64,
+  pub max_ms: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetSlowFile {
+  pub file_name: String,
+  pub bytes: u64,
+  pub cold_ms: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetIoBenchmark {
+  pub total_assets: usize,
+  pub sampled: usize,
+  pub total_bytes: u64,
+  pub cold: AssetIoPass,
+  pub warm: AssetIoPass,
+  pub base64_ms: f64,
+  pub slow_files: Vec<AssetSlowFile>,
+}
+
+fn percentile_ms(sorted: &[f64], pct: f64) -> f64 {
+  if sorted.is_empty() {
+    return 0.0;
+  }
+  let rank = ((pct / 100.0) * sorted.len() as f64).ceil() as usize;
+  sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn summarize_pass(latencies: &[f64], total_bytes: u64) -> AssetIoPass {
+  let mut sorted = latencies.to_vec();
+  sorted.sort_by(|a, b| a.total_cmp(b));
+  let total_ms: f64 = latencies.iter().sum();
+  AssetIoPass {
+    total_ms,
+    bytes_per_sec: if total_ms > 0.0 { total_bytes as f64 / (total_ms / 1000.0) } else { 0.0 },
+    p50_ms: percentile_ms(&sorted, 50.0),
+    p95_ms: percentile_ms(&sorted, 95.0),
+    max_ms: sorted.last().copied().unwrap_or(0.0),
+  }
+}
+
+/// Read-only timing of a bounded sample of assets. The first pass is as cold as the OS
+/// cache allows; the second re-reads the same files warm.
+pub fn benchmark_asset_io(sample_size: usize) -> Result<AssetIoBenchmark, String> {
+  use base64::Engine;
+  use std::time::Instant;
+
+  let dir = asset_dir()?;
+  let mut files: Vec<PathBuf> = std::fs::read_dir(&dir)
+    .map_err(|e| format!("读取 assets 目录失败: {e}"))?
+    .filter_map(|entry| entry.ok().map(|e| e.path()))
+    .filter(|path| {
+      path.is_file()
+        && path
+          .file_name()
+          .and_then(|name| name.to_str())
+          .is_some_and(is_valid_asset_file_name)
+    })
+    .collect();
+  files.sort();
+  let total_assets = files.len();
+
+  // Spread the sample across the whole directory instead of taking the first N.
+  let sample_size = sample_size.clamp(1, 500);
+  let step = (total_assets / sample_size).max(1);
+  let sample: Vec<PathBuf> = files.into_iter().step_by(step).take(sample_size).collect();
+
+  let mut cold: Vec<f64> = Vec::with_capacity(sample.len());
+  let mut sizes: Vec<u64> = Vec::with_capacity(sample.len());
+  for path in &sample {
+    let started = Instant::now();
+    let bytes = std::fs::read(path).map_err(|e| format!("读取 asset 失败: {e}"))?;
+    cold.push(started.elapsed().as_secs_f64() * 1000.0);
+    sizes.push(bytes.len() as u64);
+  }
+
+  let mut warm: Vec<f64> = Vec::with_capacity(sample.len());
+  let mut base64_ms = 0.0;
+  for path in &sample {
+    let started = Instant::now();
+    let bytes = std::fs::read(path).map_err(|e| format!("读取 asset 失败: {e}"))?;
+    warm.push(started.elapsed().as_secs_f64() * 1000.0);
+    let encode_started = Instant::now();
+    let encoded = base64::engine::general_purpose::STANDARD.encode(&bytes);
+    base64_ms += encode_started.elapsed().as_secs_f64() * 1000.0;
+    drop(encoded);
+  }
+
+  let total_bytes: u64 = sizes.iter().sum();
+  let cold_pass = summarize_pass(&cold, total_bytes);
+  let warm_pass = summarize_pass(&warm, total_bytes);
+
+  let slow_threshold = (cold_pass.p50_ms * 4.0).max(20.0);
+  let slow_files = sample
+    .iter()
+    .zip(cold.iter().zip(sizes.iter()))
+    .filter(|(_, (ms, _))| **ms > slow_threshold)
+    .map(|(path, (ms, bytes))| AssetSlowFile {
+      file_name: path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+      bytes: *bytes,
+      cold_ms: *ms,
+    })
+    .collect();
+
+  Ok(AssetIoBenchmark {
+    total_assets,
+    sampled: sample.len(),
+    total_bytes,
+    cold: cold_pass,
+    warm: warm_pass,
+    base64_ms,
+    slow_files,
+  })
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetSize {
+  pub file_name: String,
+  pub bytes: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetStorageStats {
+  pub file_count: u64,
+  pub total_bytes: u64,
+  pub largest: Vec<AssetSize>,
+}
+
+/// Disk usage of the assets directory. Hidden entries (including the `.thumbs`
+/// cache) and subdirectories are not counted.
+pub fn asset_storage_stats(largest_count: usize) -> Result<AssetStorageStats, String> {
+  let dir = asset_dir()?;
+  let mut file_count = 0u64;
+  let mut total_bytes = 0u64;
+  let mut sizes: Vec<AssetSize> = Vec::new();
+
+  for entry in std::fs::read_dir(&dir).map_err(|e| format!("读取 assets 目录失败: {e}"))? {
+    let Ok(entry) = entry else { continue };
+    let file_name = entry.file_name().to_string_lossy().to_string();
+    if file_name.starts_with('.') {
+      continue;
+    }
+    let Ok(meta) = entry.metadata() else { continue };
+    if !meta.is_file() {
+      continue;
+    }
+    file_count += 1;
+    total_bytes += meta.len();
+    sizes.push(AssetSize { file_name, bytes: meta.len() });
+  }
+
+  sizes.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.file_name.cmp(&b.file_name)));
+  sizes.truncate(largest_count);
+
+  Ok(AssetStorageStats {
+    file_count,
+    total_bytes,
+    largest: sizes,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::atomic::AtomicBool;
+  use std::sync::Arc;
+
+  fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("maple-fs-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  #[test]
+  fn interleaved_atomic_writes_never_expose_a_torn_file() {
+    let dir = scratch_dir("atomic");
+    let path = dir.join("state.json");
+    write_file_atomic(&path, br#"{"writer":-1,"round":0,"items":[]}"#).unwrap();
+
+    let done = Arc::new(AtomicBool::new(false));
+    let reader = {
+      let (path, done) = (path.clone(), done.clone());
+      std::thread::spawn(move || {
+        let mut reads = 0usize;
+        while !done.load(Ordering::Relaxed) {
+          // Windows can briefly refuse the open while a rename lands; only
+          // content that was actually read has to be whole.
+          let Ok(bytes) = std::fs::read(&path) else { continue };
+          let parsed: serde_json::Value = serde_json::from_slice(&bytes)
+            .unwrap_or_else(|error| panic!("torn read ({} bytes): {error}", bytes.len()));
+          assert!(parsed.get("items").is_some_and(|items| items.is_array()));
+          reads += 1;
+        }
+        reads
+      })
+    };
+
+    let writers: Vec<_> = (0..4)
+      .map(|writer| {
+        let path = path.clone();
+        std::thread::spawn(move || {
+          for round in 0..50 {
+            // Sizes vary so a partial overwrite could not go unnoticed.
+            let count = (writer * 50 + round) * 20;
+            let items: Vec<String> = (0..count).map(|i| format!("item-{i}")).collect();
+            let json = serde_json::json!({ "writer": writer, "round": round, "items": items });
+            write_file_atomic(&path, json.to_string().as_bytes()).unwrap();
+          }
+        })
+      })
+      .collect();
+    for writer in writers {
+      writer.join().unwrap();
+    }
+    done.store(true, Ordering::Relaxed);
+    assert!(reader.join().unwrap() > 0);
+
+    let leftovers: Vec<_> = std::fs::read_dir(&dir)
+      .unwrap()
+      .filter_map(|entry| entry.ok())
+      .filter(|entry| entry.file_name().to_string_lossy().ends_with(".tmp"))
+      .collect();
+    assert!(leftovers.is_empty(), "temp files left behind: {leftovers:?}");
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+}
@@ -1,8 +1,9 @@
 use std::borrow::Cow;
+use std::io::{Read, Seek, SeekFrom};
 
 use tauri::http::{header, Response, StatusCode};
 
-use crate::maple_fs;
+use crate::{asset_thumbnail, maple_fs};
 
 fn mime_from_extension(ext: &str) -> &'static str {
   let normalized = ext.trim().to_lowercase();
@@ -82,12 +83,112 @@ fn extract_asset_file_name(uri: &tauri::http::Uri) -> Option<&str> {
   None
 }
 
+/// Extract the asset file name from a `maple://thumb/<name>` URI (same variants as assets).
+fn extract_thumb_file_name(uri: &tauri::http::Uri) -> Option<&str> {
+  let authority = uri.authority().map(|value| value.as_str()).unwrap_or("");
+  let path = uri.path();
+  let rest = if authority == "thumb" {
+    path.strip_prefix('/').unwrap_or(path)
+  } else {
+    path.strip_prefix("/thumb/")?
+  };
+  let rest = rest.strip_suffix('/').unwrap_or(rest);
+  if rest.is_empty() { None } else { Some(rest) }
+}
+
+fn handle_thumbnail(file_name: &str, uri: &tauri::http::Uri) -> Response<Cow<'static, [u8]>> {
+  let width = uri
+    .query()
+    .unwrap_or("")
+    .split('&')
+    .find_map(|pair| pair.strip_prefix("w="))
+    .and_then(|value| value.parse::<u32>().ok());
+  let width = asset_thumbnail::clamp_width(width);
+
+  match asset_thumbnail::load_or_create(file_name, width) {
+    Ok(bytes) => Response::builder()
+      .status(StatusCode::OK)
+      .header(header::CONTENT_TYPE, "image/png")
+      .header(header::CACHE_CONTROL, "public, max-age=31536000, immutable")
+      .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+      .body(Cow::Owned(bytes))
+      .unwrap_or_else(|_| text_response(StatusCode::INTERNAL_SERVER_ERROR, "响应构建失败。")),
+    Err(error) => {
+      let status = match error {
+        asset_thumbnail::ThumbnailError::InvalidName => StatusCode::BAD_REQUEST,
+        asset_thumbnail::ThumbnailError::NotFound => StatusCode::NOT_FOUND,
+        asset_thumbnail::ThumbnailError::Unsupported => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        asset_thumbnail::ThumbnailError::TooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+        asset_thumbnail::ThumbnailError::Failed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+      };
+      eprintln!("[maple-protocol] {} — thumbnail {file_name}: {}", status.as_u16(), error.message());
+      Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "text/plain; charset=utf-8")
+        .body(Cow::Owned(error.message().into_bytes()))
+        .unwrap_or_else(|_| text_response(StatusCode::INTERNAL_SERVER_ERROR, "响应构建失败。"))
+    }
+  }
+}
+
+/// Parses a single-range `Range` header against a file of `len` bytes.
+///
+/// Returns `None` when the header should be ignored (malformed, multi-range or
+/// not `bytes`), `Some(Err(()))` when the range cannot be satisfied, and
+/// otherwise the inclusive `(start, end)` byte offsets.
+fn parse_byte_range(value: &str, len: u64) -> Option<Result<(u64, u64), ()>> {
+  let spec = value.trim().strip_prefix("bytes=")?.trim();
+  if spec.contains(',') {
+    return None;
+  }
+  let (start, end) = spec.split_once('-')?;
+  let (start, end) = (start.trim(), end.trim());
+
+  if start.is_empty() {
+    // bytes=-N: the last N bytes.
+    let suffix: u64 = end.parse().ok()?;
+    if suffix == 0 || len == 0 {
+      return Some(Err(()));
+    }
+    return Some(Ok((len.saturating_sub(suffix), len - 1)));
+  }
+
+  let start: u64 = start.parse().ok()?;
+  let end: u64 = if end.is_empty() { u64::MAX } else { end.parse().ok()? };
+  if start > end {
+    return None;
+  }
+  if start >= len {
+    return Some(Err(()));
+  }
+  Some(Ok((start, end.min(len - 1))))
+}
+
+/// True when an `If-None-Match` value lists `etag` (weak validators compare equal) or is `*`.
+fn if_none_match_hits(value: &str, etag: &str) -> bool {
+  value.split(',').map(str::trim).any(|candidate| {
+    candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == etag
+  })
+}
+
+fn read_byte_range(path: &std::path::Path, start: u64, end: u64) -> std::io::Result<Vec<u8>> {
+  let mut file = std::fs::File::open(path)?;
+  file.seek(SeekFrom::Start(start))?;
+  let mut bytes = Vec::with_capacity((end - start + 1) as usize);
+  file.take(end - start + 1).read_to_end(&mut bytes)?;
+  Ok(bytes)
+}
+
 pub fn handle<R: tauri::Runtime>(
   _ctx: tauri::UriSchemeContext<'_, R>,
   request: tauri::http::Request<Vec<u8>>,
 ) -> Response<Cow<'static, [u8]>> {
   let uri = request.uri();
 
+  if let Some(file_name) = extract_thumb_file_name(uri) {
+    return handle_thumbnail(file_name.trim(), uri);
+  }
+
   let Some(file_name) = extract_asset_file_name(uri).map(|value| value.trim()) else {
     eprintln!("[maple-protocol] 404 — no asset file name in URI: {uri}");
     return text_response(StatusCode::NOT_FOUND, "Not Found");
@@ -112,12 +213,107 @@ pub fn handle<R: tauri::Runtime>(
   }
 
   let ext = file_name.split('.').nth(1).unwrap_or_default();
-  let mime = mime_from_extension(ext);
+  // Asset names are `<sha256>.<ext>`, so the hash is a strong validator for free.
+  let etag = format!("\"{}\"", file_name.split('.').next().unwrap_or_default());
+
+  let not_modified = request
+    .headers()
+    .get(header::IF_NONE_MATCH)
+    .and_then(|value| value.to_str().ok())
+    .is_some_and(|value| if_none_match_hits(value, &etag));
+  if not_modified {
+    return Response::builder()
+      .status(StatusCode::NOT_MODIFIED)
+      .header(header::ETAG, etag.as_str())
+      .header(header::CACHE_CONTROL, "public, max-age=31536000, immutable")
+      .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+      .body(Cow::Borrowed(&b""[..]))
+      .unwrap_or_else(|_| text_response(StatusCode::INTERNAL_SERVER_ERROR, "响应构建失败。"));
+  }
+
+  // Older assets took their extension from `file.type` or the file name, so some
+  // carry the wrong one. Label them by what the bytes actually are instead of
+  // refusing files that used to render; new saves are checked in `save_asset_file`.
+  let mut head = [0u8; 16];
+  let head_len = std::fs::File::open(&path)
+    .and_then(|mut file| file.read(&mut head))
+    .unwrap_or(0);
+  let mime = match maple_fs::check_content_matches_extension(&head[..head_len], ext) {
+    Ok(()) => mime_from_extension(ext),
+    Err(e) => {
+      let mime = maple_fs::sniff_image_extension(&head[..head_len])
+        .map(mime_from_extension)
+        .unwrap_or_else(|| mime_from_extension(ext));
+      eprintln!("[maple-protocol] content mismatch — {file_name}: {e} Serving as {mime}.");
+      mime
+    }
+  };
+
+  // HEAD only needs the headers; answer from metadata without reading the file.
+  if request.method() == tauri::http::Method::HEAD {
+    return match std::fs::metadata(&path) {
+      Ok(metadata) => Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, mime)
+        .header(header::CONTENT_LENGTH, metadata.len())
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::ETAG, etag.as_str())
+        .header(header::CACHE_CONTROL, "public, max-age=31536000, immutable")
+        .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+        .body(Cow::Borrowed(&b""[..]))
+        .unwrap_or_else(|_| text_response(StatusCode::INTERNAL_SERVER_ERROR, "响应构建失败。")),
+      Err(e) => {
+        eprintln!("[maple-protocol] 500 — failed to stat asset: {e}");
+        text_response(StatusCode::INTERNAL_SERVER_ERROR, "读取 asset 文件失败。")
+      }
+    };
+  }
+
+  let range = request
+    .headers()
+    .get(header::RANGE)
+    .and_then(|value| value.to_str().ok())
+    .and_then(|value| {
+      let len = std::fs::metadata(&path).ok()?.len();
+      Some((parse_byte_range(value, len)?, len))
+    });
+
+  if let Some((range, len)) = range {
+    let (start, end) = match range {
+      Ok(bounds) => bounds,
+      Err(()) => {
+        return Response::builder()
+          .status(StatusCode::RANGE_NOT_SATISFIABLE)
+          .header(header::CONTENT_RANGE, format!("bytes */{len}"))
+          .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+          .body(Cow::Borrowed(&b""[..]))
+          .unwrap_or_else(|_| text_response(StatusCode::INTERNAL_SERVER_ERROR, "响应构建失败。"));
+      }
+    };
+    return match read_byte_range(&path, start, end) {
+      Ok(bytes) => Response::builder()
+        .status(StatusCode::PARTIAL_CONTENT)
+        .header(header::CONTENT_TYPE, mime)
+        .header(header::CONTENT_RANGE, format!("bytes {start}-{end}/{len}"))
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::ETAG, etag.as_str())
+        .header(header::CACHE_CONTROL, "public, max-age=31536000, immutable")
+        .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+        .body(Cow::Owned(bytes))
+        .unwrap_or_else(|_| text_response(StatusCode::INTERNAL_SERVER_ERROR, "响应构建失败。")),
+      Err(e) => {
+        eprintln!("[maple-protocol] 500 — failed to read asset range: {e}");
+        text_response(StatusCode::INTERNAL_SERVER_ERROR, "读取 asset 文件失败。")
+      }
+    };
+  }
 
   match std::fs::read(&path) {
     Ok(bytes) => Response::builder()
       .status(StatusCode::OK)
       .header(header::CONTENT_TYPE, mime)
+      .header(header::ACCEPT_RANGES, "bytes")
+      .header(header::ETAG, etag.as_str())
       .header(header::CACHE_CONTROL, "public, max-age=31536000, immutable")
       .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
       .body(Cow::Owned(bytes))
@@ -128,3 +324,58 @@ pub fn handle<R: tauri::Runtime>(
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn open_ended_range_runs_to_the_last_byte() {
+    assert_eq!(parse_byte_range("bytes=0-", 1000), Some(Ok((0, 999))));
+    assert_eq!(parse_byte_range("bytes=400-", 1000), Some(Ok((400, 999))));
+    assert_eq!(parse_byte_range("bytes=999-", 1000), Some(Ok((999, 999))));
+  }
+
+  #[test]
+  fn bounded_range_is_inclusive_and_clamped_to_the_file() {
+    assert_eq!(parse_byte_range("bytes=0-0", 1000), Some(Ok((0, 0))));
+    assert_eq!(parse_byte_range("bytes=100-199", 1000), Some(Ok((100, 199))));
+    assert_eq!(parse_byte_range(" bytes= 100 - 199 ", 1000), Some(Ok((100, 199))));
+    assert_eq!(parse_byte_range("bytes=900-5000", 1000), Some(Ok((900, 999))));
+  }
+
+  #[test]
+  fn suffix_range_takes_the_last_bytes() {
+    assert_eq!(parse_byte_range("bytes=-100", 1000), Some(Ok((900, 999))));
+    assert_eq!(parse_byte_range("bytes=-1", 1000), Some(Ok((999, 999))));
+    // A suffix longer than the file means the whole file.
+    assert_eq!(parse_byte_range("bytes=-5000", 1000), Some(Ok((0, 999))));
+  }
+
+  #[test]
+  fn out_of_range_requests_are_unsatisfiable() {
+    assert_eq!(parse_byte_range("bytes=1000-", 1000), Some(Err(())));
+    assert_eq!(parse_byte_range("bytes=1000-1100", 1000), Some(Err(())));
+    assert_eq!(parse_byte_range("bytes=-0", 1000), Some(Err(())));
+    assert_eq!(parse_byte_range("bytes=0-", 0), Some(Err(())));
+    assert_eq!(parse_byte_range("bytes=-10", 0), Some(Err(())));
+  }
+
+  #[test]
+  fn malformed_or_unsupported_ranges_are_ignored() {
+    for value in [
+      "",
+      "bytes=",
+      "bytes=-",
+      "bytes=abc-",
+      "bytes=0-abc",
+      "bytes=200-100",
+      "bytes=0-1,5-9",
+      "items=0-10",
+      "bytes 0-10",
+      "bytes=--5",
+    ] {
+      assert_eq!(parse_byte_range(value, 1000), None, "{value:?}");
+    }
+  }
+}
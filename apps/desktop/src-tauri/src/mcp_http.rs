@@ -2,28 +2,32 @@ use axum::{
     extract::State as AxumState,
     http::{header, HeaderMap, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
-    routing::post,
+    routing::{get, post},
     Json, Router,
 };
 use base64::Engine;
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::collections::{HashSet, BTreeMap};
+use std::collections::{HashSet, BTreeMap, VecDeque};
 use std::fs;
 use std::path::PathBuf;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
-use std::time::{SystemTime, UNIX_EPOCH};
-use tauri::Emitter;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tauri::{Emitter, Manager};
 
+use crate::mcp_tools::{self, ToolError};
 use crate::maple_fs;
+use crate::state_watcher;
 
-const MCP_PORT: u16 = 45819;
-const MCP_IMAGE_MAX_BYTES: usize = 3 * 1024 * 1024;
-const MCP_PROTOCOL_VERSION: &str = "2025-03-26";
+const DEFAULT_MCP_PORT: u16 = 45819;
+pub(crate) const MCP_IMAGE_MAX_BYTES: usize = 3 * 1024 * 1024;
+const MCP_RECENT_REQUESTS_CAPACITY: usize = 500;
+pub const MCP_PROTOCOL_VERSION: &str = "2025-03-26";
+pub const MCP_TOOL_SCHEMA_VERSION: u32 = 1;
 
-fn mime_from_extension(ext: &str) -> &'static str {
+pub(crate) fn mime_from_extension(ext: &str) -> &'static str {
     let normalized = ext.trim().to_lowercase();
     if normalized == "png" {
         return "image/png";
@@ -43,7 +47,7 @@ fn mime_from_extension(ext: &str) -> &'static str {
     "application/octet-stream"
 }
 
-fn parse_maple_asset_file_name(url: &str) -> Option<&str> {
+pub(crate) fn parse_maple_asset_file_name(url: &str) -> Option<&str> {
     let trimmed = url.trim().trim_end_matches('/');
     if let Some(rest) = trimmed.strip_prefix("maple://asset/") {
         return Some(rest);
@@ -57,7 +61,7 @@ fn parse_maple_asset_file_name(url: &str) -> Option<&str> {
     None
 }
 
-fn rewrite_maple_asset_urls(text: &str) -> (String, Vec<String>) {
+pub(crate) fn rewrite_maple_asset_urls(text: &str) -> (String, Vec<String>) {
     let mut rewritten = String::with_capacity(text.len());
     let mut cursor = 0usize;
     let mut assets: Vec<String> = Vec::new();
@@ -99,37 +103,315 @@ fn rewrite_maple_asset_urls(text: &str) -> (String, Vec<String>) {
     (rewritten, assets)
 }
 
-fn read_asset_base64_image(file_name: &str) -> Result<(String, &'static str), String> {
-    let trimmed = file_name.trim();
-    if !maple_fs::is_valid_asset_file_name(trimmed) {
-        return Err("无效的 asset 文件名（必须为 64 位小写 hex + 扩展名）。".to_string());
-    }
+/// Port from `MAPLE_MCP_PORT`, falling back to 45819 when unset or unparseable.
+pub fn mcp_port() -> u16 {
+    static PORT: std::sync::OnceLock<u16> = std::sync::OnceLock::new();
+    *PORT.get_or_init(|| {
+        std::env::var("MAPLE_MCP_PORT")
+            .ok()
+            .and_then(|value| value.trim().parse::<u16>().ok())
+            .filter(|port| *port != 0)
+            .unwrap_or(DEFAULT_MCP_PORT)
+    })
+}
 
-    let dir = maple_fs::asset_dir()?;
-    let path = dir.join(trimmed);
-    if !path.exists() {
-        return Err("asset 文件不存在。".to_string());
-    }
+const DEFAULT_MCP_HOST: &str = "127.0.0.1";
+
+/// Bind address from `MAPLE_MCP_HOST`, e.g. `0.0.0.0` so workers in a container or
+/// on another machine can connect. Defaults to loopback.
+pub fn mcp_host() -> String {
+    static HOST: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+    HOST.get_or_init(|| {
+        std::env::var("MAPLE_MCP_HOST")
+            .ok()
+            .map(|value| value.trim().trim_matches(['[', ']']).to_string())
+            .filter(|value| !value.is_empty())
+            .unwrap_or_else(|| DEFAULT_MCP_HOST.to_string())
+    })
+    .clone()
+}
 
-    let bytes = fs::read(&path).map_err(|e| format!("读取 asset 文件失败: {e}"))?;
-    if bytes.len() > MCP_IMAGE_MAX_BYTES {
-        return Err(format!("图片过大（{} bytes），已跳过内联。", bytes.len()));
-    }
+fn is_loopback_host(host: &str) -> bool {
+    host.eq_ignore_ascii_case("localhost")
+        || host.parse::<std::net::IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
 
-    let ext = trimmed.split('.').nth(1).unwrap_or_default();
-    let mime = mime_from_extension(ext);
-    if mime == "application/octet-stream" {
-        return Err("不支持的图片类型。".to_string());
-    }
+pub const MCP_TOKEN_ENV: &str = "MAPLE_MCP_TOKEN";
+
+/// Optional bearer token from `MAPLE_MCP_TOKEN`; when set, `/mcp` requires it.
+pub fn mcp_token() -> Option<String> {
+    static TOKEN: std::sync::OnceLock<Option<String>> = std::sync::OnceLock::new();
+    TOKEN
+        .get_or_init(|| {
+            std::env::var(MCP_TOKEN_ENV)
+                .ok()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        })
+        .clone()
+}
+
+fn is_authorized(headers: &HeaderMap) -> bool {
+    let Some(expected) = mcp_token() else {
+        return true;
+    };
+    let Some(provided) = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().strip_prefix("Bearer "))
+        .map(str::trim)
+    else {
+        return false;
+    };
+    // Compare without short-circuiting so timing does not leak the token prefix.
+    provided.len() == expected.len()
+        && provided
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
 
-    let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
-    Ok((encoded, mime))
+fn unauthorized_response() -> Response {
+    let mut headers = mcp_response_headers(None);
+    headers.insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+    response_with_json(
+        StatusCode::UNAUTHORIZED,
+        headers,
+        json!({
+            "jsonrpc": "2.0",
+            "id": Value::Null,
+            "error": {
+                "code": -32000,
+                "message": "Unauthorized: missing or invalid bearer token"
+            }
+        }),
+    )
 }
 
 pub struct McpHttpState {
     pub app_handle: tauri::AppHandle,
     pub sessions: Mutex<HashSet<String>>,
     pub next_session_id: AtomicU64,
+    pub paused: AtomicBool,
+    pub pause_all_tools: AtomicBool,
+    pub recent_requests: Mutex<VecDeque<McpRequestRecord>>,
+    pub bound_address: Mutex<Option<String>>,
+    pub bind_error: Mutex<Option<String>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpReadyStatus {
+    pub ready: bool,
+    pub port: u16,
+    pub bound_address: Option<String>,
+    pub error: Option<String>,
+}
+
+/// A wildcard bind is reachable through loopback; Windows refuses to connect to `0.0.0.0`.
+fn connectable_address(mut addr: std::net::SocketAddr) -> std::net::SocketAddr {
+    if addr.ip().is_unspecified() {
+        let loopback: std::net::IpAddr = match addr {
+            std::net::SocketAddr::V4(_) => std::net::Ipv4Addr::LOCALHOST.into(),
+            std::net::SocketAddr::V6(_) => std::net::Ipv6Addr::LOCALHOST.into(),
+        };
+        addr.set_ip(loopback);
+    }
+    addr
+}
+
+/// Blocks until the listener accepts a connection, the bind fails, or `timeout_ms` elapses.
+pub fn await_ready(state: &McpHttpState, timeout_ms: u64) -> McpReadyStatus {
+    let deadline = Instant::now() + std::time::Duration::from_millis(timeout_ms);
+    let mut last_error: Option<String> = None;
+    loop {
+        if let Some(error) = state.bind_error.lock().unwrap_or_else(|e| e.into_inner()).clone() {
+            return McpReadyStatus {
+                ready: false,
+                port: mcp_port(),
+                bound_address: None,
+                error: Some(error),
+            };
+        }
+        let bound = state.bound_address.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        if let Some(address) = bound.as_deref() {
+            match address.parse::<std::net::SocketAddr>() {
+                Ok(addr) => match std::net::TcpStream::connect_timeout(&connectable_address(addr), std::time::Duration::from_millis(200)) {
+                    Ok(_) => {
+                        return McpReadyStatus {
+                            ready: true,
+                            port: addr.port(),
+                            bound_address: bound,
+                            error: None,
+                        };
+                    }
+                    Err(e) => last_error = Some(format!("连接 MCP Server 失败: {e}")),
+                },
+                Err(e) => last_error = Some(format!("无效的监听地址 {address}: {e}")),
+            }
+        }
+        if Instant::now() >= deadline {
+            return McpReadyStatus {
+                ready: false,
+                port: mcp_port(),
+                bound_address: bound,
+                error: Some(last_error.unwrap_or_else(|| "等待 MCP Server 启动超时".to_string())),
+            };
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+}
+
+const MCP_PORT_SCAN_RANGE: u16 = 20;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpPortSuggestion {
+    pub preferred: u16,
+    pub preferred_available: bool,
+    pub suggested: Option<u16>,
+    pub reason: Option<String>,
+}
+
+fn loopback_port_available(state: &McpHttpState, port: u16) -> Result<(), String> {
+    // The port Maple itself is listening on is not a conflict.
+    let bound = state.bound_address.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    if bound
+        .as_deref()
+        .and_then(|address| address.parse::<std::net::SocketAddr>().ok())
+        .is_some_and(|addr| addr.port() == port)
+    {
+        return Ok(());
+    }
+    std::net::TcpListener::bind((mcp_host().as_str(), port))
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Tests `preferred` (default: the configured MCP port) and otherwise returns the first free loopback
+/// port in the next `MCP_PORT_SCAN_RANGE` ports.
+pub fn suggest_port(state: &McpHttpState, preferred: Option<u16>) -> McpPortSuggestion {
+    let preferred = preferred.filter(|port| *port != 0).unwrap_or_else(mcp_port);
+    let reason = match loopback_port_available(state, preferred) {
+        Ok(()) => {
+            return McpPortSuggestion {
+                preferred,
+                preferred_available: true,
+                suggested: Some(preferred),
+                reason: None,
+            };
+        }
+        Err(e) => format!("端口 {preferred} 不可用: {e}"),
+    };
+    let suggested = (1..=MCP_PORT_SCAN_RANGE)
+        .filter_map(|offset| preferred.checked_add(offset))
+        .find(|port| loopback_port_available(state, *port).is_ok());
+    McpPortSuggestion {
+        preferred,
+        preferred_available: false,
+        suggested,
+        reason: Some(match suggested {
+            Some(_) => reason,
+            None => format!("{reason}；其后 {MCP_PORT_SCAN_RANGE} 个端口也均被占用"),
+        }),
+    }
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct McpRequestRecord {
+    pub method: String,
+    pub tool: Option<String>,
+    pub at: String,
+    pub duration_ms: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpLatencyStats {
+    pub name: String,
+    pub count: usize,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+}
+
+fn record_request(state: &McpHttpState, method: &str, tool: Option<&str>, started: Instant) {
+    let record = McpRequestRecord {
+        method: method.to_string(),
+        tool: tool.map(str::to_string),
+        at: iso_now(),
+        duration_ms: started.elapsed().as_secs_f64() * 1000.0,
+    };
+    let mut recent = state.recent_requests.lock().unwrap_or_else(|e| e.into_inner());
+    if recent.len() >= MCP_RECENT_REQUESTS_CAPACITY {
+        recent.pop_front();
+    }
+    recent.push_back(record);
+}
+
+fn percentile(sorted: &[f64], pct: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = ((pct / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Latency per tool (or per JSON-RPC method for non-tool calls) over the recent-requests buffer.
+pub fn latency_stats(state: &McpHttpState) -> Vec<McpLatencyStats> {
+    let recent = state.recent_requests.lock().unwrap_or_else(|e| e.into_inner());
+    let mut grouped: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+    for record in recent.iter() {
+        let name = record.tool.clone().unwrap_or_else(|| record.method.clone());
+        grouped.entry(name).or_default().push(record.duration_ms);
+    }
+    grouped
+        .into_iter()
+        .map(|(name, mut durations)| {
+            durations.sort_by(|a, b| a.total_cmp(b));
+            McpLatencyStats {
+                name,
+                count: durations.len(),
+                p50_ms: percentile(&durations, 50.0),
+                p95_ms: percentile(&durations, 95.0),
+                max_ms: durations.last().copied().unwrap_or(0.0),
+            }
+        })
+        .collect()
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct McpPauseStatus {
+    pub paused: bool,
+    pub all_tools: bool,
+}
+
+pub fn set_paused(state: &McpHttpState, paused: bool, all_tools: bool) -> McpPauseStatus {
+    state.pause_all_tools.store(paused && all_tools, Ordering::Relaxed);
+    state.paused.store(paused, Ordering::Relaxed);
+    pause_status(state)
+}
+
+pub fn pause_status(state: &McpHttpState) -> McpPauseStatus {
+    McpPauseStatus {
+        paused: state.paused.load(Ordering::Relaxed),
+        all_tools: state.pause_all_tools.load(Ordering::Relaxed),
+    }
+}
+
+fn is_tool_paused(state: &McpHttpState, tool_name: &str) -> bool {
+    pause_blocks_tool(
+        state.paused.load(Ordering::Relaxed),
+        state.pause_all_tools.load(Ordering::Relaxed),
+        tool_name,
+    )
+}
+
+fn pause_blocks_tool(paused: bool, pause_all_tools: bool, tool_name: &str) -> bool {
+    paused && (pause_all_tools || mcp_tools::is_write_tool(tool_name))
 }
 
 fn new_session_id(state: &McpHttpState) -> String {
@@ -162,107 +444,352 @@ fn remove_session(state: &McpHttpState, session_id: &str) -> bool {
 
 #[derive(Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
-struct TaskUpdatedEvent {
-    project_name: String,
-    task: Task,
+pub(crate) struct TaskUpdatedEvent {
+    pub(crate) project_name: String,
+    pub(crate) task: Task,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TaskRemovedEvent {
+    pub(crate) project_name: String,
+    pub(crate) task_id: String,
+}
+
+/// Batched form of `TaskUpdatedEvent` so bulk edits refresh the UI once.
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TasksUpdatedEvent {
+    pub(crate) project_name: String,
+    pub(crate) tasks: Vec<Task>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TagCatalogUpdatedEvent {
+    pub(crate) project_name: String,
+    pub(crate) tag_catalog: BTreeMap<String, TagDefinition>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ProjectSettingsUpdatedEvent {
+    pub(crate) project_name: String,
+    pub(crate) wip_limit: Option<u32>,
+    pub(crate) disabled_tools: Vec<String>,
 }
 
+/// Tells the frontend to re-read `state.json` wholesale.
 #[derive(Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
-struct TagCatalogUpdatedEvent {
-    project_name: String,
-    tag_catalog: BTreeMap<String, TagDefinition>,
+struct StateReloadedEvent {
+    reason: String,
+    project_count: usize,
 }
 
 #[derive(Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
-struct WorkerFinishedEvent {
-    project: String,
-    summary: String,
+pub(crate) struct WorkerFinishedEvent {
+    pub(crate) project: String,
+    pub(crate) summary: String,
 }
 
 // ── Data Types (matching frontend domain.ts) ──
 
 #[derive(Deserialize, Serialize, Clone)]
-struct TaskReport {
-    id: String,
-    author: String,
-    content: String,
+pub(crate) struct TaskReport {
+    pub(crate) id: String,
+    pub(crate) author: String,
+    pub(crate) content: String,
     #[serde(rename = "createdAt")]
-    created_at: String,
+    pub(crate) created_at: String,
 }
 
 #[derive(Deserialize, Serialize, Clone)]
-struct Task {
-    id: String,
-    title: String,
+pub(crate) struct Task {
+    pub(crate) id: String,
+    pub(crate) title: String,
     #[serde(default)]
-    details: String,
+    pub(crate) details: String,
     #[serde(rename = "detailsDoc", default, skip_serializing_if = "Option::is_none")]
-    details_doc: Option<Value>,
-    status: String,
+    pub(crate) details_doc: Option<Value>,
+    pub(crate) status: String,
     #[serde(rename = "targetWorkerKind", default, skip_serializing_if = "Option::is_none")]
-    target_worker_kind: Option<String>,
-    tags: Vec<String>,
+    pub(crate) target_worker_kind: Option<String>,
+    #[serde(rename = "parentId", default, skip_serializing_if = "Option::is_none")]
+    pub(crate) parent_id: Option<String>,
+    #[serde(rename = "dependsOn", default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) depends_on: Vec<String>,
+    #[serde(rename = "externalRef", default, skip_serializing_if = "Option::is_none")]
+    pub(crate) external_ref: Option<ExternalRef>,
+    #[serde(rename = "fileRefs", default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) file_refs: Vec<FileRef>,
+    #[serde(rename = "needsConfirmation", default, skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) needs_confirmation: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) blocker: Option<Blocker>,
+    pub(crate) tags: Vec<String>,
     #[serde(rename = "createdAt")]
-    created_at: String,
+    pub(crate) created_at: String,
     #[serde(rename = "updatedAt")]
-    updated_at: String,
-    reports: Vec<TaskReport>,
+    pub(crate) updated_at: String,
+    pub(crate) reports: Vec<TaskReport>,
+}
+
+impl Task {
+    /// Moves the task to `status`, dropping its blocker once it leaves 已阻塞 so
+    /// `query_blockers` never lists finished work.
+    pub(crate) fn set_status(&mut self, status: &str) {
+        if status != "已阻塞" {
+            self.blocker = None;
+        }
+        self.status = status.to_string();
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, PartialEq)]
+pub(crate) struct ExternalRef {
+    pub(crate) source: String,
+    pub(crate) id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) url: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub(crate) struct FileRef {
+    pub(crate) path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) label: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub(crate) struct Blocker {
+    pub(crate) reason: String,
+    pub(crate) since: String,
+    #[serde(rename = "blockingOn", default, skip_serializing_if = "Option::is_none")]
+    pub(crate) blocking_on: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Clone, Default)]
-struct TagLabel {
+pub(crate) struct TagLabel {
     #[serde(skip_serializing_if = "Option::is_none")]
-    zh: Option<String>,
+    pub(crate) zh: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    en: Option<String>,
+    pub(crate) en: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Clone, Default)]
-struct TagDefinition {
+pub(crate) struct TagDefinition {
     #[serde(skip_serializing_if = "Option::is_none")]
-    color: Option<String>,
+    pub(crate) color: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    icon: Option<String>,
+    pub(crate) icon: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    label: Option<TagLabel>,
+    pub(crate) label: Option<TagLabel>,
 }
 
 #[derive(Deserialize, Serialize, Clone)]
-struct Project {
-    id: String,
-    name: String,
-    directory: String,
+pub(crate) struct Project {
+    pub(crate) id: String,
+    pub(crate) name: String,
+    pub(crate) directory: String,
     #[serde(rename = "workerKind", skip_serializing_if = "Option::is_none")]
-    worker_kind: Option<String>,
-    tasks: Vec<Task>,
+    pub(crate) worker_kind: Option<String>,
+    pub(crate) tasks: Vec<Task>,
     #[serde(rename = "tagCatalog", default)]
-    tag_catalog: BTreeMap<String, TagDefinition>,
+    pub(crate) tag_catalog: BTreeMap<String, TagDefinition>,
+    #[serde(rename = "disabledTools", default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) disabled_tools: Vec<String>,
+    #[serde(rename = "wipLimit", default, skip_serializing_if = "Option::is_none")]
+    pub(crate) wip_limit: Option<u32>,
 }
 
 // ── State File ──
 
-fn state_dir() -> PathBuf {
+pub(crate) fn state_dir() -> PathBuf {
     maple_fs::maple_home_dir().unwrap_or_else(|_| std::env::temp_dir().join(".maple"))
 }
 
-fn read_state() -> Vec<Project> {
+pub(crate) struct CachedState {
+    pub(crate) modified: Option<SystemTime>,
+    pub(crate) len: u64,
+    pub(crate) projects: Vec<Project>,
+}
+
+/// Serializes read-modify-write cycles on `state.json` between MCP write tools,
+/// desktop commands and the UI's `write_state_file`. Always taken before
+/// `STATE_CACHE`, never the other way round.
+///
+/// The UI saves its whole projects array, built from a snapshot that may predate
+/// a report an MCP tool wrote in the meantime. `write_state_file` therefore
+/// re-reads the file under this lock and keeps such reports with
+/// `merge_disk_only_reports` before writing, so a report is never lost even if
+/// the UI missed its update event.
+static STATE_WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+pub fn lock_state() -> std::sync::MutexGuard<'static, ()> {
+    STATE_WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Appends to each task in `incoming` the reports that `on_disk` has for the same
+/// project and task id but `incoming` lacks. The UI never deletes reports, so a
+/// report missing from its save is one it has not seen yet. Tasks and projects the
+/// UI removed stay removed. Returns how many reports were kept.
+pub fn merge_disk_only_reports(incoming: &mut Value, on_disk: &Value) -> usize {
+    let (Some(incoming), Some(on_disk)) = (incoming.as_array_mut(), on_disk.as_array()) else {
+        return 0;
+    };
+    let id_of = |value: &Value| value.get("id").and_then(|v| v.as_str()).map(str::to_string);
+    let mut kept = 0;
+    for project in incoming.iter_mut() {
+        let Some(project_id) = id_of(project) else {
+            continue;
+        };
+        let Some(disk_project) = on_disk.iter().find(|p| id_of(p).as_deref() == Some(&project_id)) else {
+            continue;
+        };
+        let disk_tasks = disk_project.get("tasks").and_then(|v| v.as_array());
+        let Some(tasks) = project.get_mut("tasks").and_then(|v| v.as_array_mut()) else {
+            continue;
+        };
+        for task in tasks.iter_mut() {
+            let Some(task_id) = id_of(task) else {
+                continue;
+            };
+            let Some(disk_reports) = disk_tasks
+                .and_then(|list| list.iter().find(|t| id_of(t).as_deref() == Some(&task_id)))
+                .and_then(|t| t.get("reports"))
+                .and_then(|v| v.as_array())
+            else {
+                continue;
+            };
+            let Some(reports) = task.get_mut("reports").and_then(|v| v.as_array_mut()) else {
+                continue;
+            };
+            let known: HashSet<String> = reports.iter().filter_map(id_of).collect();
+            for report in disk_reports {
+                if id_of(report).is_some_and(|id| !known.contains(&id)) {
+                    reports.push(report.clone());
+                    kept += 1;
+                }
+            }
+        }
+    }
+    kept
+}
+
+/// Parsed `state.json`, reused while the file's mtime and size are unchanged.
+pub(crate) static STATE_CACHE: Mutex<Option<CachedState>> = Mutex::new(None);
+
+pub(crate) fn state_file_stamp(path: &std::path::Path) -> Option<(Option<SystemTime>, u64)> {
+    let meta = fs::metadata(path).ok()?;
+    Some((meta.modified().ok(), meta.len()))
+}
+
+pub(crate) fn read_state() -> Vec<Project> {
     let path = state_dir().join("state.json");
-    if !path.exists() {
+    let mut cache = STATE_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    let Some((modified, len)) = state_file_stamp(&path) else {
+        *cache = None;
         return vec![];
+    };
+    if let Some(cached) = cache.as_ref() {
+        if modified.is_some() && cached.modified == modified && cached.len == len {
+            return cached.projects.clone();
+        }
     }
-    fs::read_to_string(&path)
+
+    let projects: Vec<Project> = fs::read_to_string(&path)
         .ok()
         .and_then(|raw| serde_json::from_str(&raw).ok())
-        .unwrap_or_default()
+        .unwrap_or_default();
+    *cache = Some(CachedState {
+        modified,
+        len,
+        projects: projects.clone(),
+    });
+    projects
+}
+
+pub(crate) const STATE_BACKUP_COUNT: usize = 5;
+
+pub(crate) fn state_backup_path(dir: &std::path::Path, index: usize) -> PathBuf {
+    dir.join(format!("state.json.{index}"))
+}
+
+/// Rotates `state.json.1..5` and copies the current `state.json` into slot 1
+/// before it is overwritten with `next`. Skipped when the write would not change
+/// anything or the current content is already the newest backup, so repeated
+/// saves of the same data don't push older backups out. Caller holds the state lock.
+pub fn backup_state_before_write(next: &[u8]) -> Result<(), String> {
+    let dir = state_dir();
+    let Ok(current) = fs::read(dir.join("state.json")) else {
+        return Ok(());
+    };
+    if current == next {
+        return Ok(());
+    }
+    if fs::read(state_backup_path(&dir, 1)).is_ok_and(|newest| newest == current) {
+        return Ok(());
+    }
+    for index in (1..STATE_BACKUP_COUNT).rev() {
+        let from = state_backup_path(&dir, index);
+        if from.exists() {
+            fs::rename(&from, state_backup_path(&dir, index + 1))
+                .map_err(|e| format!("轮换状态备份失败: {e}"))?;
+        }
+    }
+    maple_fs::write_file_atomic(&state_backup_path(&dir, 1), &current)
+        .map_err(|e| format!("备份当前状态失败: {e}"))
 }
 
-fn write_state(projects: &[Project]) {
+/// Writes already-serialized state; the caller holds the cache lock.
+pub(crate) fn write_state_json_locked(
+    cache: &mut Option<CachedState>,
+    json: &str,
+    projects: Vec<Project>,
+) -> Result<(), String> {
     let dir = state_dir();
     let _ = fs::create_dir_all(&dir);
-    if let Ok(json) = serde_json::to_string_pretty(projects) {
-        let _ = fs::write(dir.join("state.json"), json);
+    let path = dir.join("state.json");
+    if let Err(e) = backup_state_before_write(json.as_bytes()) {
+        eprintln!("[maple-state] {e}");
+    }
+    state_watcher::record_self_write(json.as_bytes());
+    if let Err(e) = maple_fs::write_file_atomic(&path, json.as_bytes()) {
+        *cache = None;
+        return Err(format!("写入状态文件失败: {e}"));
+    }
+    *cache = state_file_stamp(&path).map(|(modified, len)| CachedState {
+        modified,
+        len,
+        projects,
+    });
+    Ok(())
+}
+
+/// Drops the parsed state so the next read goes back to disk.
+pub fn invalidate_state_cache() {
+    *STATE_CACHE.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+pub fn emit_state_reloaded(app_handle: &tauri::AppHandle, reason: &str, project_count: usize) {
+    let _ = app_handle.emit(
+        "maple://state-reloaded",
+        StateReloadedEvent {
+            reason: reason.to_string(),
+            project_count,
+        },
+    );
+}
+
+pub(crate) fn write_state(projects: &[Project]) {
+    let mut cache = STATE_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    match serde_json::to_string_pretty(projects) {
+        Ok(json) => {
+            let _ = write_state_json_locked(&mut cache, &json, projects.to_vec());
+        }
+        Err(_) => *cache = None,
     }
 }
 
@@ -363,7 +890,7 @@ fn normalize_directory_key(value: &str) -> Option<String> {
     }
 }
 
-fn find_project_index(projects: &[Project], name: &str) -> Option<usize> {
+pub(crate) fn find_project_index(projects: &[Project], name: &str) -> Option<usize> {
     let raw_kw = name.trim();
     let kw = raw_kw.to_lowercase();
     if raw_kw.is_empty() {
@@ -386,19 +913,64 @@ fn find_project_index(projects: &[Project], name: &str) -> Option<usize> {
         .or_else(|| projects.iter().position(|p| p.name.to_lowercase().contains(&kw)))
 }
 
-fn iso_now() -> String {
+fn is_tool_disabled(project: &Project, tool_name: &str) -> bool {
+    project
+        .disabled_tools
+        .iter()
+        .any(|name| name.trim().eq_ignore_ascii_case(tool_name))
+}
+
+/// A disabled tool is refused with an `isError` result, not a JSON-RPC error, so
+/// clients show the model why the call did not run.
+fn disabled_tool_result(tool_name: &str, args: &Value) -> Option<ToolError> {
+    let project_name = args.get("project").and_then(|v| v.as_str())?;
+    let projects = read_state();
+    let idx = find_project_index(&projects, project_name)?;
+    let target = &projects[idx];
+    if !is_tool_disabled(target, tool_name) {
+        return None;
+    }
+    Some(ToolError::Refused(format!(
+        "项目「{}」已禁用工具「{tool_name}」，请勿调用。",
+        target.name
+    )))
+}
+
+static NEXT_TASK_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+pub(crate) fn new_task_id(existing: &[Task]) -> String {
+    loop {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let sequence = NEXT_TASK_SEQUENCE.fetch_add(1, Ordering::Relaxed);
+        let mut suffix_seed = (now.subsec_nanos() as u64) ^ (sequence.wrapping_mul(0x9e37_79b9));
+        let mut suffix = String::new();
+        for _ in 0..6 {
+            let digit = (suffix_seed % 36) as u32;
+            suffix.push(std::char::from_digit(digit, 36).unwrap_or('0'));
+            suffix_seed /= 36;
+        }
+        let id = format!("{}-{suffix}", now.as_millis());
+        if !existing.iter().any(|task| task.id == id) {
+            return id;
+        }
+    }
+}
+
+pub(crate) fn iso_now() -> String {
     Utc::now()
         .to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
 }
 
-fn truncate_chars(s: &str, max: usize) -> &str {
+pub(crate) fn truncate_chars(s: &str, max: usize) -> &str {
     match s.char_indices().nth(max) {
         Some((idx, _)) => &s[..idx],
         None => s,
     }
 }
 
-fn summarize_report_content(content: &str, max_chars: usize) -> String {
+pub(crate) fn summarize_report_content(content: &str, max_chars: usize) -> String {
     let (content, _) = rewrite_maple_asset_urls(content);
     let collapsed = content
         .replace("\r\n", "\n")
@@ -418,7 +990,47 @@ fn summarize_report_content(content: &str, max_chars: usize) -> String {
     }
 }
 
-fn build_report_history_lines(reports: &[TaskReport]) -> Vec<String> {
+const REPORT_HISTORY_DEFAULT_LIMIT: usize = 5;
+const REPORT_HISTORY_MAX_LIMIT: usize = 50;
+pub(crate) const REPORT_PREVIEW_DEFAULT_CHARS: usize = 220;
+const REPORT_PREVIEW_MIN_CHARS: usize = 40;
+const REPORT_PREVIEW_MAX_CHARS: usize = 4000;
+
+#[derive(Clone, Copy)]
+pub(crate) struct ReportHistoryOptions {
+    pub(crate) limit: usize,
+    pub(crate) preview_chars: usize,
+}
+
+impl Default for ReportHistoryOptions {
+    fn default() -> Self {
+        Self {
+            limit: REPORT_HISTORY_DEFAULT_LIMIT,
+            preview_chars: REPORT_PREVIEW_DEFAULT_CHARS,
+        }
+    }
+}
+
+impl ReportHistoryOptions {
+    /// Reads `history_limit` / `history_preview_chars`, clamped to sane bounds.
+    pub(crate) fn from_args(args: &Value) -> Self {
+        let defaults = Self::default();
+        Self {
+            limit: args
+                .get("history_limit")
+                .and_then(|v| v.as_u64())
+                .map_or(defaults.limit, |v| (v as usize).clamp(1, REPORT_HISTORY_MAX_LIMIT)),
+            preview_chars: args
+                .get("history_preview_chars")
+                .and_then(|v| v.as_u64())
+                .map_or(defaults.preview_chars, |v| {
+                    (v as usize).clamp(REPORT_PREVIEW_MIN_CHARS, REPORT_PREVIEW_MAX_CHARS)
+                }),
+        }
+    }
+}
+
+pub(crate) fn build_report_history_lines(reports: &[TaskReport], options: ReportHistoryOptions) -> Vec<String> {
     let mut sorted: Vec<&TaskReport> = reports
         .iter()
         .filter(|report| !report.content.trim().is_empty())
@@ -429,7 +1041,7 @@ fn build_report_history_lines(reports: &[TaskReport]) -> Vec<String> {
 
     sorted.sort_by(|a, b| b.created_at.cmp(&a.created_at));
     let total = sorted.len();
-    let max_items = 5usize;
+    let max_items = options.limit;
     let displayed = sorted.into_iter().take(max_items).collect::<Vec<_>>();
 
     let mut lines = vec![format!(
@@ -437,27 +1049,41 @@ fn build_report_history_lines(reports: &[TaskReport]) -> Vec<String> {
         displayed.len(),
         total
     )];
-    lines.extend(displayed.into_iter().map(|report| {
-        let author = if report.author.trim().is_empty() {
-            "unknown"
-        } else {
-            report.author.trim()
-        };
-        let timestamp = if report.created_at.trim().is_empty() {
-            "未知时间"
-        } else {
-            report.created_at.trim()
-        };
-        let preview = summarize_report_content(&report.content, 220);
-        format!("- {author} @ {timestamp}: {preview}")
-    }));
+    lines.extend(
+        displayed
+            .into_iter()
+            .map(|report| format!("- {}", format_report_entry(report, options.preview_chars))),
+    );
     if total > max_items {
         lines.push(format!("... 其余 {} 条已省略。", total - max_items));
     }
     lines
 }
 
-fn latest_execution_summary(reports: &[TaskReport]) -> Option<String> {
+/// `author @ timestamp: preview`, as shown in report history listings.
+pub(crate) fn format_report_entry(report: &TaskReport, preview_chars: usize) -> String {
+    let author = if report.author.trim().is_empty() {
+        "unknown"
+    } else {
+        report.author.trim()
+    };
+    let timestamp = if report.created_at.trim().is_empty() {
+        "未知时间"
+    } else {
+        report.created_at.trim()
+    };
+    let preview = summarize_report_content(&report.content, preview_chars);
+    format!("{author} @ {timestamp}: {preview}")
+}
+
+pub(crate) fn latest_report(reports: &[TaskReport]) -> Option<&TaskReport> {
+    reports
+        .iter()
+        .filter(|report| !report.content.trim().is_empty())
+        .max_by(|a, b| a.created_at.cmp(&b.created_at))
+}
+
+pub(crate) fn latest_execution_summary(reports: &[TaskReport]) -> Option<String> {
     let mut sorted: Vec<&TaskReport> = reports
         .iter()
         .filter(|report| !report.content.trim().is_empty())
@@ -471,15 +1097,106 @@ fn latest_execution_summary(reports: &[TaskReport]) -> Option<String> {
     None
 }
 
-fn is_terminal_task_status(status: &str) -> bool {
+pub(crate) fn is_terminal_task_status(status: &str) -> bool {
     matches!(status, "草稿" | "已完成" | "已阻塞" | "需要更多信息")
 }
 
-fn normalize_tag_id(raw: &str) -> String {
+/// `task_index` is the task being moved; pass `None` for a task that is not in
+/// the project yet, so it is checked against the existing in-progress tasks.
+pub(crate) fn check_wip_limit(project: &Project, task_index: Option<usize>, next: &str) -> Result<(), String> {
+    let Some(limit) = project.wip_limit else {
+        return Ok(());
+    };
+    let already_in_progress = task_index.is_some_and(|index| project.tasks[index].status == "进行中");
+    if next != "进行中" || already_in_progress {
+        return Ok(());
+    }
+    let in_progress: Vec<&Task> = project
+        .tasks
+        .iter()
+        .filter(|task| task.status == "进行中")
+        .collect();
+    if (in_progress.len() as u32) < limit {
+        return Ok(());
+    }
+    let mut lines = vec![format!(
+        "项目「{}」已达到 WIP 上限（{} / {limit}），无法将任务移入进行中。当前进行中的任务：",
+        project.name,
+        in_progress.len()
+    )];
+    lines.extend(
+        in_progress
+            .iter()
+            .map(|task| format!("- {}  (id: {})", task.title, task.id)),
+    );
+    Err(lines.join("\n"))
+}
+
+pub(crate) fn format_elapsed_since(since: &str) -> String {
+    let Ok(start) = chrono::DateTime::parse_from_rfc3339(since.trim()) else {
+        return "未知时长".to_string();
+    };
+    let minutes = (Utc::now() - start.with_timezone(&Utc)).num_minutes().max(0);
+    if minutes < 60 {
+        format!("{minutes} 分钟")
+    } else if minutes < 60 * 24 {
+        format!("{} 小时 {} 分钟", minutes / 60, minutes % 60)
+    } else {
+        format!("{} 天 {} 小时", minutes / (60 * 24), (minutes / 60) % 24)
+    }
+}
+
+pub(crate) fn describe_blocker(blocker: &Blocker) -> String {
+    let blocking_on = blocker
+        .blocking_on
+        .as_deref()
+        .map(|value| format!("，等待：{value}"))
+        .unwrap_or_default();
+    format!(
+        "{}{blocking_on}（已阻塞 {}）",
+        blocker.reason,
+        format_elapsed_since(&blocker.since)
+    )
+}
+
+pub(crate) const TASK_STATUSES: &[&str] = &["草稿", "待办", "待返工", "队列中", "进行中", "需要更多信息", "已完成", "已阻塞"];
+
+#[derive(Deserialize, Default)]
+pub(crate) struct StatusConfig {
+    /// from-status → allowed next statuses. Statuses without an entry are unrestricted.
+    #[serde(default)]
+    transitions: BTreeMap<String, Vec<String>>,
+}
+
+pub(crate) fn read_status_config() -> StatusConfig {
+    let path = state_dir().join("status-config.json");
+    fs::read_to_string(&path)
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub(crate) fn check_status_transition(config: &StatusConfig, from: &str, to: &str) -> Result<(), String> {
+    if from == to {
+        return Ok(());
+    }
+    let Some(allowed) = config.transitions.get(from) else {
+        return Ok(());
+    };
+    if allowed.iter().any(|next| next == to) {
+        return Ok(());
+    }
+    Err(format!(
+        "不允许将状态从「{from}」直接变更为「{to}」。允许的下一状态：{}",
+        if allowed.is_empty() { "（无）".to_string() } else { allowed.join("、") }
+    ))
+}
+
+pub(crate) fn normalize_tag_id(raw: &str) -> String {
     raw.trim().to_lowercase()
 }
 
-fn normalize_and_dedupe_tag_ids(args: &Value, max: usize) -> Result<Vec<String>, String> {
+pub(crate) fn normalize_and_dedupe_tag_ids(args: &Value, max: usize) -> Result<Vec<String>, String> {
     let Some(raw) = args.get("tags") else {
         return Err("缺少参数：tags（必填，1-5 个）。".to_string());
     };
@@ -511,7 +1228,7 @@ fn normalize_and_dedupe_tag_ids(args: &Value, max: usize) -> Result<Vec<String>,
     Ok(out)
 }
 
-fn find_missing_tag_definitions(
+pub(crate) fn find_missing_tag_definitions(
     catalog: &BTreeMap<String, TagDefinition>,
     tag_ids: &[String],
 ) -> Vec<String> {
@@ -522,678 +1239,278 @@ fn find_missing_tag_definitions(
         .collect()
 }
 
-fn is_valid_mingcute_icon(icon: &str) -> bool {
+pub(crate) fn is_valid_mingcute_icon(icon: &str) -> bool {
     icon.trim().to_lowercase().starts_with("mingcute:")
 }
 
-// ── MCP Tool Handlers ──
-
-fn tool_query_project_todos(args: &Value) -> Value {
-    let name = args.get("project").and_then(|v| v.as_str()).unwrap_or("");
-    let worker_kind = args
-        .get("worker_kind")
-        .and_then(|v| v.as_str())
-        .map(|v| v.trim())
-        .filter(|v| !v.is_empty())
-        .map(|v| v.to_lowercase());
-    let projects = read_state();
+const DEFAULT_TAG_ICON: &str = "mingcute:tag-line";
+
+/// Built-in definitions for the PR-style tags generated by the desktop app
+/// (see `lib/pr-tags.ts`): (aliases, icon, zh label, en label).
+const TAG_PRESETS: &[(&[&str], &str, &str, &str)] = &[
+    (&["type:feat", "feat", "新功能"], "mingcute:add-line", "新功能", "Feature"),
+    (&["type:fix", "fix", "bug", "修复"], "mingcute:bug-line", "修复", "Fix"),
+    (&["type:refactor", "refactor", "重构"], "mingcute:refresh-2-line", "重构", "Refactor"),
+    (&["type:docs", "docs", "文档"], "mingcute:document-line", "文档", "Docs"),
+    (&["type:chore", "chore", "配置"], "mingcute:settings-3-line", "配置", "Chore"),
+    (&["area:core", "core", "核心"], "mingcute:layout-grid-line", "核心", "Core"),
+    (&["area:ui", "ui", "界面"], "mingcute:palette-line", "界面", "UI"),
+    (&["area:task-detail", "task-detail", "详情"], "mingcute:task-line", "详情", "Task Detail"),
+    (&["area:markdown", "markdown", "文档格式"], "mingcute:markdown-line", "文档格式", "Markdown"),
+    (&["area:worker", "worker", "执行器"], "mingcute:ai-line", "执行器", "Worker"),
+    (&["area:mcp", "mcp", "协议层"], "mingcute:plugin-2-line", "协议层", "MCP"),
+    (&["area:xterm", "xterm", "终端"], "mingcute:terminal-box-line", "终端", "Terminal"),
+    (&["area:i18n", "i18n", "多语言"], "mingcute:translate-line", "多语言", "i18n"),
+    (&["state:blocked", "blocked", "阻塞"], "mingcute:forbid-circle-line", "阻塞", "Blocked"),
+    (&["state:needs-info", "needs-info", "待补充"], "mingcute:information-line", "待补充", "Needs Info"),
+    (&["security", "安全"], "mingcute:shield-line", "安全", "Security"),
+];
+
+fn resolve_tag_preset(tag_id: &str) -> Option<TagDefinition> {
+    let (_, icon, zh, en) = TAG_PRESETS
+        .iter()
+        .find(|(aliases, ..)| aliases.contains(&tag_id))?;
+    Some(TagDefinition {
+        color: None,
+        icon: Some((*icon).to_string()),
+        label: Some(TagLabel {
+            zh: Some((*zh).to_string()),
+            en: Some((*en).to_string()),
+        }),
+    })
+}
 
-    let Some(idx) = find_project_index(&projects, name) else {
-        let names: Vec<&str> = projects.iter().map(|p| p.name.as_str()).collect();
-        return json!({ "content": [{ "type": "text", "text": format!(
-            "未找到匹配项目「{name}」。可用项目：{}",
-            if names.is_empty() { "（无）".to_string() } else { names.join("、") }
-        )}]});
-    };
-
-    let target = &projects[idx];
-    let mut todos: Vec<&Task> = target
-        .tasks
-        .iter()
-        .filter(|t| t.status != "已完成" && t.status != "草稿")
-        .filter(|task| match worker_kind.as_deref() {
-            None => task
-                .target_worker_kind
-                .as_deref()
-                .map(|value| value.trim().is_empty())
-                .unwrap_or(true),
-            Some(kind) => {
-                if let Some(target_kind) = task.target_worker_kind.as_deref() {
-                    target_kind.trim().eq_ignore_ascii_case(kind)
-                } else if let Some(default_kind) = target.worker_kind.as_deref() {
-                    default_kind.trim().eq_ignore_ascii_case(kind)
-                } else {
-                    true
-                }
+pub(crate) fn merge_tag_definition(into: &mut TagDefinition, from: TagDefinition) {
+    if into.color.is_none() {
+        into.color = from.color;
+    }
+    if into.icon.is_none() {
+        into.icon = from.icon;
+    }
+    match (into.label.as_mut(), from.label) {
+        (Some(label), Some(other)) => {
+            if label.zh.is_none() {
+                label.zh = other.zh;
             }
-        })
-        .collect();
-    todos.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
-
-    if todos.is_empty() {
-        return json!({ "content": [{ "type": "text", "text":
-            format!("项目「{}」暂无待处理任务。", target.name)
-        }]});
+            if label.en.is_none() {
+                label.en = other.en;
+            }
+        }
+        (None, Some(other)) => into.label = Some(other),
+        _ => {}
     }
-
-    let lines: Vec<String> = todos
-        .iter()
-        .enumerate()
-        .map(|(i, t)| {
-            let tags = if t.tags.is_empty() {
-                String::new()
-            } else {
-                format!(" [{}]", t.tags.join(", "))
-            };
-            let title = if t.title.trim().is_empty() {
-                "（无标题）"
-            } else {
-                t.title.as_str()
-            };
-            let details = t.details.trim();
-            let details_text = if details.is_empty() {
-                "（空）".to_string()
-            } else {
-                rewrite_maple_asset_urls(details).0
-            };
-            let mut block = vec![
-                format!("{}. [{}] {}{}  (id: {})", i + 1, t.status, title, tags, t.id),
-                "详情：".to_string(),
-                details_text,
-                String::new(),
-            ];
-            block.extend(build_report_history_lines(&t.reports));
-            block.join("\n")
-        })
-        .collect();
-
-    json!({ "content": [{ "type": "text", "text": format!(
-        "项目「{}」— {} 个待处理任务（不含草稿）：\n\n{}",
-        target.name, todos.len(), lines.join("\n\n---\n\n")
-    )}]})
 }
 
-fn tool_query_recent_context(args: &Value) -> Value {
-    let project_name = args.get("project").and_then(|v| v.as_str());
-    let keyword = args.get("keyword").and_then(|v| v.as_str());
-    let limit = args
-        .get("limit")
-        .and_then(|v| v.as_u64())
-        .unwrap_or(10)
-        .max(1) as usize;
-
-    let projects = read_state();
-    let indices: Vec<usize> = if let Some(name) = project_name {
-        find_project_index(&projects, name).into_iter().collect()
-    } else {
-        (0..projects.len()).collect()
-    };
-
-    let mut items: Vec<(String, String, String, String)> = Vec::new();
-    for idx in indices {
-        let p = &projects[idx];
-        for task in &p.tasks {
-            for report in &task.reports {
-                let content = report.content.trim();
-                if content.is_empty() {
-                    continue;
-                }
-                if let Some(kw) = keyword {
-                    if !content.to_lowercase().contains(&kw.to_lowercase()) {
-                        continue;
-                    }
-                }
-                items.push((
-                    p.name.clone(),
-                    task.title.clone(),
-                    report.created_at.clone(),
-                    content.to_string(),
-                ));
-            }
+/// Makes sure every tag has a catalog entry, filling only the fields that are
+/// still empty from presets (or the default icon). Returns how many entries
+/// were added or enriched.
+pub(crate) fn ensure_tag_catalog_for_tags(
+    catalog: &mut BTreeMap<String, TagDefinition>,
+    tag_ids: &[String],
+) -> usize {
+    let mut enriched = 0usize;
+    for tag_id in tag_ids {
+        let tag_id = normalize_tag_id(tag_id);
+        if tag_id.is_empty() {
+            continue;
+        }
+        let entry = catalog.entry(tag_id.clone()).or_default();
+        // Blank strings count as missing so a cleared field can be refilled.
+        if entry.icon.as_deref().is_some_and(|v| v.trim().is_empty()) {
+            entry.icon = None;
+        }
+        if let Some(label) = entry.label.as_mut() {
+            label.zh = label.zh.take().filter(|v| !v.trim().is_empty());
+            label.en = label.en.take().filter(|v| !v.trim().is_empty());
+        }
+        let before = (
+            entry.icon.is_some(),
+            entry.label.as_ref().map(|l| (l.zh.is_some(), l.en.is_some())),
+        );
+        let fallback = resolve_tag_preset(&tag_id).unwrap_or(TagDefinition {
+            color: None,
+            icon: Some(DEFAULT_TAG_ICON.to_string()),
+            label: None,
+        });
+        merge_tag_definition(entry, fallback);
+        let after = (
+            entry.icon.is_some(),
+            entry.label.as_ref().map(|l| (l.zh.is_some(), l.en.is_some())),
+        );
+        if before != after {
+            enriched += 1;
         }
     }
+    enriched
+}
 
-    items.sort_by(|a, b| b.2.cmp(&a.2));
-    let result: Vec<_> = items.iter().take(limit).collect();
+// ── MCP Resources ──
 
-    if result.is_empty() {
-        return json!({ "content": [{ "type": "text", "text": "未找到匹配的任务报告。" }]});
-    }
+const ASSET_RESOURCE_URI_PREFIX: &str = "maple://asset/";
 
-    let lines: Vec<String> = result
-        .iter()
-        .map(|(proj, task, at, text)| {
-            let (rewritten, _) = rewrite_maple_asset_urls(text);
-            let preview = truncate_chars(&rewritten, 200);
-            format!("[{proj}] {task}\n  时间：{at}\n  内容：{preview}")
+fn list_asset_resources() -> Result<Value, ToolError> {
+    let dir = maple_fs::asset_dir().map_err(ToolError::Internal)?;
+    let entries = fs::read_dir(&dir).map_err(|e| ToolError::Internal(format!("读取 assets 目录失败: {e}")))?;
+    let mut resources: Vec<Value> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !maple_fs::is_valid_asset_file_name(&name) {
+                return None;
+            }
+            let size = entry.metadata().ok().filter(|meta| meta.is_file())?.len();
+            let ext = name.split('.').nth(1).unwrap_or_default();
+            Some(json!({
+                "uri": format!("{ASSET_RESOURCE_URI_PREFIX}{name}"),
+                "name": name,
+                "mimeType": mime_from_extension(ext),
+                "size": size
+            }))
         })
         .collect();
-
-    json!({ "content": [{ "type": "text", "text": lines.join("\n\n") }]})
+    resources.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+    Ok(json!({ "resources": resources }))
 }
 
-fn tool_query_task_details(args: &Value) -> Value {
-    let project_name = args
-        .get("project")
-        .and_then(|v| v.as_str())
-        .unwrap_or("");
-    let task_id = args
-        .get("task_id")
+fn read_asset_resource(params: &Value) -> Result<Value, ToolError> {
+    let uri = params
+        .get("uri")
         .and_then(|v| v.as_str())
+        .map(str::trim)
         .unwrap_or("");
-
-    let projects = read_state();
-    let Some(idx) = find_project_index(&projects, project_name) else {
-        return json!({
-            "content": [{ "type": "text", "text": format!("未找到匹配项目「{project_name}」。") }],
-            "isError": true
-        });
+    let Some(file_name) = parse_maple_asset_file_name(uri) else {
+        return Err(ToolError::InvalidArgs(format!("不支持的资源 URI：{uri}")));
     };
-
-    let target = &projects[idx];
-    let Some(task) = target.tasks.iter().find(|t| t.id == task_id) else {
-        return json!({
-            "content": [{ "type": "text", "text": format!("项目「{}」中未找到任务 ID「{task_id}」。", target.name) }],
-            "isError": true
-        });
-    };
-
-    let tags = if task.tags.is_empty() {
-        "（无）".to_string()
-    } else {
-        task.tags.join("、")
-    };
-    let details = task.details.trim();
-    let (details_text, assets) = if details.is_empty() {
-        ("（空）".to_string(), Vec::new())
-    } else {
-        rewrite_maple_asset_urls(details)
-    };
-    let execution_summary = latest_execution_summary(&task.reports);
-    let report_lines = build_report_history_lines(&task.reports);
-
-    let mut lines: Vec<String> = vec![
-        format!("任务：{}  (id: {})", task.title, task.id),
-        format!("状态：{}", task.status),
-    ];
-    if let Some(summary) = execution_summary {
-        lines.push(format!("执行状态：{}", summary));
-    }
-    lines.extend([
-        format!("标签：{}", tags),
-        format!("更新时间：{}", task.updated_at),
-        String::new(),
-        "详情：".to_string(),
-        details_text,
-        String::new(),
-    ]);
-    lines.extend(report_lines);
-
-    let mut content: Vec<Value> = vec![json!({ "type": "text", "text": lines.join("\n") })];
-    for file_name in assets {
-        match read_asset_base64_image(&file_name) {
-            Ok((data, mime_type)) => {
-                content.push(json!({ "type": "text", "text": format!("图片：{file_name}") }));
-                content.push(json!({ "type": "image", "mimeType": mime_type, "data": data }));
-            }
-            Err(err) => {
-                content.push(json!({ "type": "text", "text": format!("图片读取失败：{file_name}（{err}）") }));
-            }
-        }
-    }
-
-    json!({ "content": content })
-}
-
-fn tool_update_task_details(args: &Value, state: &McpHttpState) -> Value {
-    let project_name = args
-        .get("project")
-        .and_then(|v| v.as_str())
-        .unwrap_or("");
-    let task_id = args
-        .get("task_id")
-        .and_then(|v| v.as_str())
-        .unwrap_or("");
-    let details_value = args.get("details");
-    let details = details_value.and_then(|v| v.as_str()).unwrap_or("");
-    let mode = args
-        .get("mode")
-        .and_then(|v| v.as_str())
-        .unwrap_or("append")
-        .trim()
-        .to_lowercase();
-
-    if details_value.is_none() {
-        return json!({
-            "content": [{ "type": "text", "text": "缺少参数：details。" }],
-            "isError": true
-        });
+    if !maple_fs::is_valid_asset_file_name(file_name) {
+        return Err(ToolError::InvalidArgs("无效的 asset 文件名（必须为 64 位小写 hex + 扩展名）。".to_string()));
     }
-
-    let mut projects = read_state();
-
-    let Some(idx) = find_project_index(&projects, project_name) else {
-        return json!({
-            "content": [{ "type": "text", "text": format!("未找到匹配项目「{project_name}」。") }],
-            "isError": true
-        });
-    };
-
-    let target = &mut projects[idx];
-    let target_name = target.name.clone();
-
-    let Some(task_index) = target.tasks.iter().position(|t| t.id == task_id) else {
-        return json!({
-            "content": [{ "type": "text", "text": format!("项目「{target_name}」中未找到任务 ID「{task_id}」。") }],
-            "isError": true
-        });
+    let path = maple_fs::asset_dir().map_err(ToolError::Internal)?.join(file_name);
+    let Ok(meta) = fs::metadata(&path) else {
+        return Err(ToolError::ResourceNotFound(format!("资源不存在：{uri}")));
     };
+    if meta.len() > MCP_IMAGE_MAX_BYTES as u64 {
+        return Err(ToolError::InvalidArgs(format!(
+            "资源过大（{} bytes，上限 {MCP_IMAGE_MAX_BYTES} bytes）。",
+            meta.len()
+        )));
+    }
+    let bytes = fs::read(&path).map_err(|e| ToolError::Internal(format!("读取 asset 文件失败: {e}")))?;
+    let ext = file_name.split('.').nth(1).unwrap_or_default();
+    Ok(json!({
+        "contents": [{
+            "uri": format!("{ASSET_RESOURCE_URI_PREFIX}{file_name}"),
+            "mimeType": mime_from_extension(ext),
+            "blob": base64::engine::general_purpose::STANDARD.encode(bytes)
+        }]
+    }))
+}
 
-    let now = iso_now();
-    let task_title = target.tasks[task_index].title.clone();
-
-    {
-        let task = &mut target.tasks[task_index];
-        let incoming = details.trim();
-        let current = task.details.trim_end();
-        let next_details = if mode == "replace" {
-            incoming.to_string()
-        } else if incoming.is_empty() {
-            current.to_string()
-        } else if current.is_empty() {
-            incoming.to_string()
-        } else {
-            format!("{current}\n\n{incoming}")
-        };
-
-        task.details = next_details;
-        task.details_doc = None;
-        task.updated_at = now;
-    }
-
-    let task_snapshot = target.tasks[task_index].clone();
-    write_state(&projects);
-    let _ = state.app_handle.emit(
-        "maple://task-updated",
-        TaskUpdatedEvent {
-            project_name: target_name.clone(),
-            task: task_snapshot,
-        },
-    );
+// ── JSON-RPC / MCP Handler ──
 
-    json!({ "content": [{ "type": "text", "text":
-        format!("已更新「{target_name}」任务「{task_title}」的详情。")
-    }]})
+fn tool_error_response(id: Option<Value>, error: &ToolError) -> (StatusCode, HeaderMap, Json<Value>) {
+    (
+        StatusCode::OK,
+        mcp_response_headers(None),
+        Json(json!({ "jsonrpc": "2.0", "id": id, "error": error.to_json() })),
+    )
 }
 
-fn normalize_asset_file_name_arg(raw: &str) -> Option<&str> {
-    let trimmed = raw.trim();
-    if trimmed.is_empty() {
-        return None;
-    }
-    if let Some(rest) = trimmed.strip_prefix("asset://") {
-        return Some(rest.trim());
-    }
-    if let Some(rest) = parse_maple_asset_file_name(trimmed) {
-        return Some(rest.trim());
-    }
-    Some(trimmed)
+fn accepts_event_stream(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|v| {
+            v.split(';')
+                .next()
+                .unwrap_or("")
+                .trim()
+                .eq_ignore_ascii_case("text/event-stream")
+        })
 }
 
-fn tool_read_asset_image(args: &Value) -> Value {
-    let raw = args
-        .get("file_name")
-        .and_then(|v| v.as_str())
-        .or_else(|| args.get("url").and_then(|v| v.as_str()))
-        .unwrap_or("");
-
-    let Some(file_name) = normalize_asset_file_name_arg(raw) else {
-        return json!({
-            "content": [{ "type": "text", "text": "缺少参数：file_name / url。" }],
-            "isError": true
-        });
-    };
-
-    match read_asset_base64_image(file_name) {
-        Ok((data, mime_type)) => json!({
-            "content": [
-                { "type": "text", "text": format!("图片：{file_name}") },
-                { "type": "image", "mimeType": mime_type, "data": data }
-            ]
-        }),
-        Err(err) => json!({
-            "content": [{ "type": "text", "text": format!("图片读取失败：{file_name}（{err}）") }],
-            "isError": true
-        }),
-    }
+/// Streamable HTTP: answers with a single `message` SSE event and closes the stream.
+fn sse_response(mut headers: HeaderMap, body: &Value) -> Response {
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("text/event-stream"));
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    let payload = serde_json::to_string(body).unwrap_or_else(|_| "null".to_string());
+    (StatusCode::OK, headers, format!("event: message\ndata: {payload}\n\n")).into_response()
 }
 
-fn tool_submit_task_report(args: &Value, state: &McpHttpState) -> Value {
-    let project_name = args
-        .get("project")
-        .and_then(|v| v.as_str())
-        .unwrap_or("");
-    let task_id = args
-        .get("task_id")
-        .and_then(|v| v.as_str())
-        .unwrap_or("");
-    let status = args.get("status").and_then(|v| v.as_str());
-    let report_content = args.get("report").and_then(|v| v.as_str()).unwrap_or("");
-    let tags = match normalize_and_dedupe_tag_ids(args, 5) {
-        Ok(tag_ids) => tag_ids,
-        Err(err) => {
-            return json!({
-                "content": [{ "type": "text", "text": err }],
-                "isError": true
-            });
-        }
-    };
-
-    let mut projects = read_state();
-
-    let Some(idx) = find_project_index(&projects, project_name) else {
-        return json!({
-            "content": [{ "type": "text", "text": format!("未找到匹配项目「{project_name}」。") }],
-            "isError": true
-        });
-    };
-
-    let target = &mut projects[idx];
-    let target_name = target.name.clone();
-
-    let Some(task_index) = target.tasks.iter().position(|t| t.id == task_id) else {
-        return json!({
-            "content": [{ "type": "text", "text": format!("项目「{target_name}」中未找到任务 ID「{task_id}」。") }],
-            "isError": true
-        });
-    };
-
-    let now = iso_now();
-    let task_title = target.tasks[task_index].title.clone();
-    let ts = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_millis();
-
-    let missing = find_missing_tag_definitions(&target.tag_catalog, &tags);
-    if !missing.is_empty() {
-        return json!({
-            "content": [{
-                "type": "text",
-                "text": format!(
-                    "以下 Tag 尚未在 Tag Catalog 中定义，禁止提交报告：{}\n请先为每个 Tag 调用 upsert_tag_definition（icon 必须为 mingcute:*，可选填写 label.zh / label.en）。",
-                    missing.join("、")
-                )
-            }],
-            "isError": true
-        });
+async fn handle_mcp_post(
+    AxumState(state): AxumState<Arc<McpHttpState>>,
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+) -> Response {
+    if !is_authorized(&headers) {
+        return unauthorized_response();
     }
-
-    {
-        let task = &mut target.tasks[task_index];
-        task.reports.push(TaskReport {
-            id: format!("report-{ts}"),
-            author: "mcp".into(),
-            content: report_content.into(),
-            created_at: now.clone(),
-        });
-        task.updated_at = now;
-        if let Some(s) = status {
-            task.status = s.into();
-        }
-        task.tags = tags.clone();
+    if let Value::Array(requests) = body {
+        return handle_mcp_batch(state, &headers, requests);
     }
-
-    let task_snapshot = target.tasks[task_index].clone();
-
-    write_state(&projects);
-    let _ = state.app_handle.emit(
-        "maple://task-updated",
-        TaskUpdatedEvent {
-            project_name: target_name.clone(),
-            task: task_snapshot,
-        },
-    );
-
-    let status_text = status
-        .map(|s| format!("状态已更新为「{s}」"))
-        .unwrap_or_else(|| "状态未变更".into());
-
-    json!({ "content": [{ "type": "text", "text":
-        format!("已提交报告至「{target_name}」任务「{task_title}」。{status_text}。")
-    }]})
-}
-
-fn tool_query_tag_catalog(args: &Value) -> Value {
-    let name = args.get("project").and_then(|v| v.as_str()).unwrap_or("");
-    let projects = read_state();
-
-    let Some(idx) = find_project_index(&projects, name) else {
-        return json!({
-            "content": [{ "type": "text", "text": format!("未找到匹配项目「{name}」。") }],
-            "isError": true
-        });
-    };
-
-    let target = &projects[idx];
-    if target.tag_catalog.is_empty() {
-        return json!({ "content": [{ "type": "text", "text":
-            format!("项目「{}」暂无 Tag Catalog。", target.name)
-        }]});
-    }
-
-    let mut lines: Vec<String> = Vec::new();
-    for (tag, def) in &target.tag_catalog {
-        let color = def
-            .color
-            .as_deref()
-            .unwrap_or("（未设置）");
-        let icon = def
-            .icon
-            .as_deref()
-            .unwrap_or("（未设置）");
-        let label_zh = def
-            .label
-            .as_ref()
-            .and_then(|label| label.zh.as_deref())
-            .unwrap_or("（未设置）");
-        let label_en = def
-            .label
-            .as_ref()
-            .and_then(|label| label.en.as_deref())
-            .unwrap_or("（未设置）");
-        lines.push(format!(
-            "- {}  color: {}  icon: {}  label.zh: {}  label.en: {}",
-            tag, color, icon, label_zh, label_en
-        ));
+    let (status, response_headers, Json(response)) = dispatch_mcp_request(state, &headers, body);
+    if status == StatusCode::OK && accepts_event_stream(&headers) {
+        return sse_response(response_headers, &response);
     }
-
-    json!({ "content": [{ "type": "text", "text": format!(
-        "项目「{}」Tag Catalog：\n{}",
-        target.name,
-        lines.join("\n")
-    )}]})
+    (status, response_headers, Json(response)).into_response()
 }
 
-fn tool_upsert_tag_definition(args: &Value, state: &McpHttpState) -> Value {
-    let project_name = args.get("project").and_then(|v| v.as_str()).unwrap_or("");
-    let tag_raw = args.get("tag").and_then(|v| v.as_str()).unwrap_or("");
-    let tag_id = normalize_tag_id(tag_raw);
-    if tag_id.is_empty() {
-        return json!({
-            "content": [{ "type": "text", "text": "tag 不能为空。"}],
-            "isError": true
-        });
-    }
-
-    let color = args.get("color").and_then(|v| v.as_str()).map(|s| s.trim()).filter(|s| !s.is_empty());
-    let icon = args.get("icon").and_then(|v| v.as_str()).map(|s| s.trim()).filter(|s| !s.is_empty());
-    if let Some(i) = icon {
-        if !is_valid_mingcute_icon(i) {
-            return json!({
-                "content": [{ "type": "text", "text": "icon 必须是 Iconify 的 mingcute 图标（例如 mingcute:tag-line）。"}],
-                "isError": true
-            });
-        }
+/// JSON-RPC batch: responses keep request order and notifications get none.
+/// A session created by an `initialize` in the batch applies to the requests after it.
+fn handle_mcp_batch(state: Arc<McpHttpState>, headers: &HeaderMap, requests: Vec<Value>) -> Response {
+    if requests.is_empty() {
+        return response_with_json(
+            StatusCode::BAD_REQUEST,
+            mcp_response_headers(None),
+            json!({
+                "jsonrpc": "2.0",
+                "id": Value::Null,
+                "error": { "code": -32600, "message": "Invalid Request: empty batch" }
+            }),
+        );
     }
-    let label_zh = args.get("label_zh").and_then(|v| v.as_str()).map(|s| s.trim()).filter(|s| !s.is_empty());
-    let label_en = args.get("label_en").and_then(|v| v.as_str()).map(|s| s.trim()).filter(|s| !s.is_empty());
 
-    let mut projects = read_state();
-    let Some(idx) = find_project_index(&projects, project_name) else {
-        return json!({
-            "content": [{ "type": "text", "text": format!("未找到匹配项目「{project_name}」。") }],
-            "isError": true
-        });
-    };
-
-    let target = &mut projects[idx];
-    let target_name = target.name.clone();
-
-    let entry = target.tag_catalog.entry(tag_id.clone()).or_default();
-    if let Some(c) = color {
-        entry.color = Some(c.to_string());
-    }
-    if let Some(i) = icon {
-        entry.icon = Some(i.to_lowercase());
-    }
-    if label_zh.is_some() || label_en.is_some() {
-        let mut label = entry.label.clone().unwrap_or_default();
-        if let Some(zh) = label_zh {
-            label.zh = Some(zh.to_string());
+    let mut request_headers = headers.clone();
+    let mut session_id: Option<String> = None;
+    let mut responses: Vec<Value> = Vec::new();
+    for request in requests {
+        if !request.is_object() {
+            responses.push(json!({
+                "jsonrpc": "2.0",
+                "id": Value::Null,
+                "error": { "code": -32600, "message": "Invalid Request" }
+            }));
+            continue;
         }
-        if let Some(en) = label_en {
-            label.en = Some(en.to_string());
+        let (status, response_headers, Json(response)) =
+            dispatch_mcp_request(state.clone(), &request_headers, request);
+        if let Some(value) = response_headers.get("mcp-session-id") {
+            request_headers.insert("mcp-session-id", value.clone());
+            session_id = value.to_str().ok().map(str::to_string);
         }
-        if label.zh.is_none() && label.en.is_none() {
-            entry.label = None;
-        } else {
-            entry.label = Some(label);
+        if status == StatusCode::ACCEPTED {
+            continue;
         }
+        responses.push(response);
     }
 
-    let catalog_snapshot = target.tag_catalog.clone();
-
-    write_state(&projects);
-    let _ = state.app_handle.emit(
-        "maple://tag-catalog-updated",
-        TagCatalogUpdatedEvent {
-            project_name: target_name.clone(),
-            tag_catalog: catalog_snapshot.clone(),
-        },
-    );
-
-    json!({ "content": [{ "type": "text", "text":
-        format!("已更新「{target_name}」Tag「{tag_id}」定义。")
-    }]})
-}
-
-fn tool_finish_worker(args: &Value, state: &McpHttpState) -> Value {
-    let project_name = args
-        .get("project")
-        .and_then(|v| v.as_str())
-        .unwrap_or("");
-    let worker_kind = args
-        .get("worker_kind")
-        .and_then(|v| v.as_str())
-        .map(|v| v.trim())
-        .filter(|v| !v.is_empty())
-        .map(|v| v.to_lowercase());
-    let summary = args
-        .get("summary")
-        .and_then(|v| v.as_str())
-        .unwrap_or("");
-
-    let projects = read_state();
-    let Some(idx) = find_project_index(&projects, project_name) else {
-        return json!({
-            "content": [{ "type": "text", "text": format!("未找到匹配项目「{project_name}」。") }],
-            "isError": true
-        });
-    };
-
-    let target = &projects[idx];
-    let unresolved_tasks: Vec<&Task> = target
-        .tasks
-        .iter()
-        .filter(|task| !is_terminal_task_status(&task.status))
-        .filter(|task| match worker_kind.as_deref() {
-            None => true,
-            Some(kind) => {
-                if let Some(target_kind) = task.target_worker_kind.as_deref() {
-                    target_kind.trim().eq_ignore_ascii_case(kind)
-                } else if let Some(default_kind) = target.worker_kind.as_deref() {
-                    default_kind.trim().eq_ignore_ascii_case(kind)
-                } else {
-                    true
-                }
-            }
-        })
-        .collect();
-
-    if !unresolved_tasks.is_empty() {
-        let mut lines: Vec<String> = vec![
-            format!(
-                "项目「{}」仍有 {} 个任务未收敛，禁止 finish_worker。",
-                target.name,
-                unresolved_tasks.len()
-            ),
-            "请先对每条任务调用 submit_task_report，将状态更新为：草稿 / 已完成 / 已阻塞 / 需要更多信息。".into(),
-            String::new(),
-        ];
-        lines.extend(
-            unresolved_tasks
-                .iter()
-                .enumerate()
-                .map(|(index, task)| {
-                    format!(
-                        "{}. [{}] {}  (id: {})",
-                        index + 1,
-                        task.status,
-                        task.title,
-                        task.id
-                    )
-                }),
-        );
-        return json!({
-            "content": [{ "type": "text", "text": lines.join("\n") }],
-            "isError": true
-        });
+    let response_headers = mcp_response_headers(session_id.as_deref());
+    if responses.is_empty() {
+        return (StatusCode::ACCEPTED, response_headers, "").into_response();
     }
-
-    let dir = state_dir();
-    let _ = fs::create_dir_all(&dir);
-    let signal = json!({
-        "project": target.name,
-        "workerKind": worker_kind,
-        "summary": summary,
-        "timestamp": iso_now(),
-        "action": "finish"
-    });
-    let _ = fs::write(
-        dir.join("worker-signal.json"),
-        serde_json::to_string_pretty(&signal).unwrap_or_default(),
-    );
-    let _ = state.app_handle.emit(
-        "maple://worker-finished",
-        WorkerFinishedEvent {
-            project: target.name.clone(),
-            summary: summary.to_string(),
-        },
-    );
-
-    json!({ "content": [{ "type": "text", "text":
-        format!("已通知 Maple 项目「{}」的 Worker 执行完毕。", target.name)
-    }]})
+    let body = Value::Array(responses);
+    if accepts_event_stream(headers) {
+        return sse_response(response_headers, &body);
+    }
+    response_with_json(StatusCode::OK, response_headers, body)
 }
 
-// ── JSON-RPC / MCP Handler ──
-
-async fn handle_mcp_post(
-    AxumState(state): AxumState<Arc<McpHttpState>>,
-    headers: HeaderMap,
-    Json(body): Json<Value>,
-) -> impl IntoResponse {
+fn dispatch_mcp_request(
+    state: Arc<McpHttpState>,
+    headers: &HeaderMap,
+    body: Value,
+) -> (StatusCode, HeaderMap, Json<Value>) {
     let id = body.get("id").cloned();
     let method = body
         .get("method")
@@ -1249,6 +1566,12 @@ async fn handle_mcp_post(
     }
 
     let mut response_session_id: Option<String> = None;
+    let started = Instant::now();
+    let called_tool = if method == "tools/call" {
+        params.get("name").and_then(|v| v.as_str()).map(str::to_string)
+    } else {
+        None
+    };
 
     let result = match method {
         "initialize" => {
@@ -1256,14 +1579,49 @@ async fn handle_mcp_post(
             response_session_id = Some(session_id);
             json!({
                 "protocolVersion": MCP_PROTOCOL_VERSION,
-                "capabilities": { "tools": {} },
-                "serverInfo": { "name": "maple", "version": "0.1.6" }
+                "capabilities": { "tools": {}, "resources": {} },
+                "serverInfo": { "name": "maple", "version": env!("CARGO_PKG_VERSION") }
             })
         }
 
         "ping" => json!({}),
 
-        "tools/list" => json!({ "tools": tool_definitions() }),
+        "resources/list" | "resources/read" => {
+            let outcome = if method == "resources/list" {
+                list_asset_resources()
+            } else {
+                read_asset_resource(&params)
+            };
+            match outcome {
+                Ok(result) => result,
+                Err(error) => {
+                    record_request(state.as_ref(), method, None, started);
+                    return tool_error_response(id, &error);
+                }
+            }
+        }
+
+        "tools/list" => {
+            let projects = read_state();
+            let project = params
+                .get("project")
+                .and_then(|v| v.as_str())
+                .and_then(|name| find_project_index(&projects, name))
+                .map(|idx| &projects[idx]);
+            let mut tools = mcp_tools::tool_definitions();
+            if let Some(project) = project {
+                for tool in tools.iter_mut() {
+                    let name = tool.get("name").and_then(|v| v.as_str()).unwrap_or("");
+                    if !is_tool_disabled(project, name) {
+                        continue;
+                    }
+                    let description = tool.get("description").and_then(|v| v.as_str()).unwrap_or("");
+                    tool["description"] =
+                        json!(format!("【项目「{}」已禁用】{description}", project.name));
+                }
+            }
+            json!({ "tools": tools })
+        }
 
         "tools/call" => {
             let tool_name = params
@@ -1271,20 +1629,34 @@ async fn handle_mcp_post(
                 .and_then(|v| v.as_str())
                 .unwrap_or("");
             let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
-            match tool_name {
-                "query_project_todos" => tool_query_project_todos(&arguments),
-                "query_recent_context" => tool_query_recent_context(&arguments),
-                "query_task_details" => tool_query_task_details(&arguments),
-                "update_task_details" => tool_update_task_details(&arguments, state.as_ref()),
-                "read_asset_image" => tool_read_asset_image(&arguments),
-                "submit_task_report" => tool_submit_task_report(&arguments, state.as_ref()),
-                "query_tag_catalog" => tool_query_tag_catalog(&arguments),
-                "upsert_tag_definition" => tool_upsert_tag_definition(&arguments, state.as_ref()),
-                "finish_worker" => tool_finish_worker(&arguments, state.as_ref()),
-                _ => json!({
-                    "content": [{ "type": "text", "text": format!("未知工具：{tool_name}") }],
+            if is_tool_paused(state.as_ref(), tool_name) {
+                return (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    mcp_response_headers(None),
+                    Json(json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": -32003, "message": "Maple MCP is paused by the user" }
+                    })),
+                );
+            }
+            // Write tools run their whole read-modify-write under the state lock.
+            let _state_guard = mcp_tools::is_write_tool(tool_name).then(lock_state);
+            let outcome = if let Some(disabled) = disabled_tool_result(tool_name, &arguments) {
+                Err(disabled)
+            } else {
+                mcp_tools::call_tool(tool_name, &arguments, state.as_ref())
+            };
+            match outcome {
+                Ok(result) => result,
+                Err(ToolError::Refused(message)) => json!({
+                    "content": [{ "type": "text", "text": message }],
                     "isError": true
                 }),
+                Err(error) => {
+                    record_request(state.as_ref(), method, called_tool.as_deref(), started);
+                    return tool_error_response(id, &error);
+                }
             }
         }
 
@@ -1301,6 +1673,8 @@ async fn handle_mcp_post(
         }
     };
 
+    record_request(state.as_ref(), method, called_tool.as_deref(), started);
+
     (
         StatusCode::OK,
         mcp_response_headers(response_session_id.as_deref()),
@@ -1308,7 +1682,19 @@ async fn handle_mcp_post(
     )
 }
 
-async fn handle_mcp_get() -> impl IntoResponse {
+/// Readiness probe; deliberately left unauthenticated.
+async fn handle_health() -> Json<Value> {
+    Json(json!({
+        "status": "ok",
+        "projects": read_state().len(),
+        "port": mcp_port()
+    }))
+}
+
+async fn handle_mcp_get(headers: HeaderMap) -> impl IntoResponse {
+    if !is_authorized(&headers) {
+        return unauthorized_response();
+    }
     method_not_allowed_response()
 }
 
@@ -1316,6 +1702,9 @@ async fn handle_mcp_delete(
     AxumState(state): AxumState<Arc<McpHttpState>>,
     headers: HeaderMap,
 ) -> impl IntoResponse {
+    if !is_authorized(&headers) {
+        return unauthorized_response();
+    }
     let header_session_id = headers
         .get("mcp-session-id")
         .and_then(|v| v.to_str().ok())
@@ -1393,174 +1782,203 @@ fn mcp_response_headers(session_id: Option<&str>) -> HeaderMap {
     headers
 }
 
-fn tool_definitions() -> Vec<Value> {
-    vec![
-        json!({
-            "name": "query_project_todos",
-            "description": "按项目名查询待处理任务（不含草稿/已完成），返回状态、标签、详情与历史报告摘要。",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "project": { "type": "string", "description": "项目名称（模糊匹配）" },
-                    "worker_kind": {
-                        "type": "string",
-                        "enum": ["claude", "codex", "iflow", "gemini", "opencode"],
-                        "description": "可选：按 Worker kind 过滤可见任务（用于任务指定 Worker 派发）。"
-                    }
-                },
-                "required": ["project"]
-            }
-        }),
-        json!({
-            "name": "query_task_details",
-            "description": "查询指定任务的详情内容（包含 markdown、图片、文件引用等）。",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "project": { "type": "string", "description": "项目名称（模糊匹配）" },
-                    "task_id": { "type": "string", "description": "任务 ID" }
-                },
-                "required": ["project", "task_id"]
-            }
-        }),
-        json!({
-            "name": "update_task_details",
-            "description": "更新指定任务的详情内容（支持追加或替换）。",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "project": { "type": "string", "description": "项目名称（模糊匹配）" },
-                    "task_id": { "type": "string", "description": "任务 ID" },
-                    "details": { "type": "string", "description": "详情内容（Markdown）" },
-                    "mode": {
-                        "type": "string",
-                        "enum": ["append", "replace"],
-                        "description": "更新方式：append 追加 / replace 覆盖（可选，默认 append）"
-                    }
-                },
-                "required": ["project", "task_id", "details"]
-            }
-        }),
-        json!({
-            "name": "read_asset_image",
-            "description": "读取任务中的本地图片 asset，并以 MCP image 内容块返回（避免 maple://）。",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "file_name": { "type": "string", "description": "asset 文件名（hash.ext），也支持 asset://... / maple://... 形式。" }
-                },
-                "required": ["file_name"]
-            }
-        }),
-        json!({
-            "name": "query_recent_context",
-            "description": "查询最近任务报告，支持项目名和关键词过滤。",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "project": { "type": "string", "description": "项目名称（可选，模糊匹配）" },
-                    "keyword": { "type": "string", "description": "搜索关键词（可选）" },
-                    "limit": { "type": "number", "description": "最多返回条数" }
-                }
-            }
-        }),
-        json!({
-            "name": "submit_task_report",
-            "description": "提交任务执行报告，并可修改任务状态。",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "project": { "type": "string", "description": "项目名称" },
-                    "task_id": { "type": "string", "description": "任务 ID" },
-                    "status": {
-                        "type": "string",
-                        "enum": ["草稿", "待办", "待返工", "队列中", "进行中", "需要更多信息", "已完成", "已阻塞"],
-                        "description": "新状态（可选）"
-                    },
-                    "report": { "type": "string", "description": "报告内容" },
-                    "tags": {
-                        "type": "array",
-                        "items": { "type": "string" },
-                        "minItems": 1,
-                        "maxItems": 5,
-                        "description": "标签列表（必填，1-5 个）。提交报告时必须严格更新 task.tags。使用新 Tag 前，请先调用 upsert_tag_definition 创建/完善定义；若 Tag Catalog 中缺少该 Tag，submit_task_report 会报错。"
-                    }
-                },
-                "required": ["project", "task_id", "report", "tags"]
-            }
-        }),
-        json!({
-            "name": "query_tag_catalog",
-            "description": "查询项目 Tag Catalog（标签定义：颜色/图标/多语言 label）。",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "project": { "type": "string", "description": "项目名称（模糊匹配）" }
-                },
-                "required": ["project"]
-            }
-        }),
-        json!({
-            "name": "upsert_tag_definition",
-            "description": "创建或更新 Tag 定义（用于 UI 渲染颜色/图标/多语言 label）。",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "project": { "type": "string", "description": "项目名称（模糊匹配）" },
-                    "tag": { "type": "string", "description": "Tag ID（会被 trim + lower-case 归一化）" },
-                    "color": { "type": "string", "description": "CSS 颜色（例如 #22c55e / hsl(...) / var(--color-primary)）" },
-                    "icon": { "type": "string", "description": "Iconify 图标（仅允许 mingcute 集，例如 mingcute:tag-line）" },
-                    "label_zh": { "type": "string", "description": "中文展示名（可选）" },
-                    "label_en": { "type": "string", "description": "英文展示名（可选）" }
-                },
-                "required": ["project", "tag"]
-            }
-        }),
-        json!({
-            "name": "finish_worker",
-            "description": "通知 Maple 当前 Worker 已执行完毕。调用前必须确保项目内无待办/待返工/队列中/进行中任务。",
-            "inputSchema": {
-                "type": "object",
-                "properties": {
-                    "project": { "type": "string", "description": "项目名称" },
-                    "worker_kind": {
-                        "type": "string",
-                        "enum": ["claude", "codex", "iflow", "gemini", "opencode"],
-                        "description": "可选：当前 Worker kind（用于按任务指定 Worker 分流 finish_worker 校验）。"
-                    },
-                    "summary": { "type": "string", "description": "执行总结（可选）" }
-                },
-                "required": ["project"]
-            }
-        }),
-    ]
-}
-
 // ── Server Startup ──
 
 pub fn start(app_handle: tauri::AppHandle) {
     let state = Arc::new(McpHttpState {
-        app_handle,
+        app_handle: app_handle.clone(),
         sessions: Mutex::new(HashSet::new()),
         next_session_id: AtomicU64::new(1),
+        paused: AtomicBool::new(false),
+        pause_all_tools: AtomicBool::new(false),
+        recent_requests: Mutex::new(VecDeque::new()),
+        bound_address: Mutex::new(None),
+        bind_error: Mutex::new(None),
     });
+    app_handle.manage(state.clone());
+    let port = mcp_port();
+    let host = mcp_host();
+    // Anything beyond loopback is reachable from the network, so refuse to serve it unauthenticated.
+    if !is_loopback_host(&host) && mcp_token().is_none() {
+        eprintln!("Refusing to bind Maple MCP HTTP server on {host}: {MCP_TOKEN_ENV} is not set");
+        *state.bind_error.lock().unwrap_or_else(|e| e.into_inner()) = Some(format!(
+            "MAPLE_MCP_HOST={host} 不是本机回环地址，必须同时设置 {MCP_TOKEN_ENV} 才能启动 MCP Server。"
+        ));
+        return;
+    }
     tauri::async_runtime::spawn(async move {
         let app = Router::new()
             .route("/mcp", post(handle_mcp_post).get(handle_mcp_get).delete(handle_mcp_delete))
-            .with_state(state);
+            .route("/health", get(handle_health))
+            .with_state(state.clone());
 
-        match tokio::net::TcpListener::bind(format!("127.0.0.1:{MCP_PORT}")).await {
+        match tokio::net::TcpListener::bind((host.as_str(), port)).await {
             Ok(listener) => {
-                eprintln!("Maple MCP HTTP server listening on 127.0.0.1:{MCP_PORT}");
+                let address = listener
+                    .local_addr()
+                    .map(|addr| addr.to_string())
+                    .unwrap_or_else(|_| format!("{host}:{port}"));
+                eprintln!("Maple MCP HTTP server listening on {address}");
+                *state.bound_address.lock().unwrap_or_else(|e| e.into_inner()) = Some(address);
                 if let Err(e) = axum::serve(listener, app).await {
                     eprintln!("Maple MCP HTTP server error: {e}");
                 }
             }
             Err(e) => {
                 eprintln!(
-                    "Failed to bind Maple MCP HTTP server on port {MCP_PORT}: {e}"
+                    "Failed to bind Maple MCP HTTP server on {host}:{port}: {e}"
                 );
+                *state.bind_error.lock().unwrap_or_else(|e| e.into_inner()) =
+                    Some(format!("绑定 {host}:{port} 失败: {e}"));
             }
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp_tools::{is_write_tool, tool_definitions};
+
+    fn tool_names() -> Vec<String> {
+        tool_definitions()
+            .iter()
+            .filter_map(|tool| tool.get("name").and_then(|v| v.as_str()).map(str::to_string))
+            .collect()
+    }
+
+    #[test]
+    fn pause_blocks_every_state_mutating_tool() {
+        let mutating = [
+            "update_task_details",
+            "append_task_detail",
+            "attach_file_reference",
+            "submit_task_report",
+            "create_task",
+            "set_task_status",
+            "bulk_status_by_tag",
+            "update_task_statuses",
+            "duplicate_task",
+            "delete_task",
+            "set_blocker",
+            "clear_blocker",
+            "set_wip_limit",
+            "upsert_tag_definition",
+            "delete_tag_definition",
+            "normalize_project_tags",
+            "rename_tag",
+            "regenerate_tag_catalog",
+            "finish_worker",
+        ];
+        let names = tool_names();
+        for tool in mutating {
+            assert!(names.iter().any(|name| name == tool), "{tool} is not a defined tool");
+            assert!(is_write_tool(tool), "{tool} must run under the state lock");
+            assert!(pause_blocks_tool(true, false, tool), "{tool} must be blocked while paused");
+        }
+    }
+
+    #[test]
+    fn pause_lets_read_only_tools_through_unless_all_tools_are_paused() {
+        let read_only: Vec<String> =
+            tool_names().into_iter().filter(|name| !is_write_tool(name)).collect();
+        assert!(read_only.iter().any(|name| name == "query_project_todos"));
+        for tool in &read_only {
+            assert!(!pause_blocks_tool(true, false, tool), "{tool} should stay available");
+            assert!(pause_blocks_tool(true, true, tool), "{tool} should stop when all tools are paused");
+            assert!(!pause_blocks_tool(false, true, tool));
+        }
+    }
+
+    fn project_with_statuses(wip_limit: Option<u32>, statuses: &[&str]) -> Project {
+        let tasks: Vec<Value> = statuses
+            .iter()
+            .enumerate()
+            .map(|(index, status)| {
+                json!({
+                    "id": format!("task-{index}"),
+                    "title": format!("任务 {index}"),
+                    "status": status,
+                    "tags": [],
+                    "createdAt": "2026-01-01T00:00:00Z",
+                    "updatedAt": "2026-01-01T00:00:00Z",
+                    "reports": []
+                })
+            })
+            .collect();
+        serde_json::from_value(json!({
+            "id": "p1",
+            "name": "Maple",
+            "directory": "/tmp/maple",
+            "tasks": tasks,
+            "wipLimit": wip_limit
+        }))
+        .expect("valid project")
+    }
+
+    #[test]
+    fn wip_limit_refuses_a_new_in_progress_task_at_the_limit() {
+        let project = project_with_statuses(Some(2), &["进行中", "进行中", "待办"]);
+        let err = check_wip_limit(&project, None, "进行中").expect_err("limit reached");
+        assert!(err.contains("task-0") && err.contains("task-1"), "{err}");
+        assert!(check_wip_limit(&project, None, "待办").is_ok());
+
+        let below = project_with_statuses(Some(3), &["进行中", "进行中"]);
+        assert!(check_wip_limit(&below, None, "进行中").is_ok());
+    }
+
+    #[test]
+    fn ui_save_keeps_reports_only_on_disk() {
+        let report = |id: &str| {
+            json!({ "id": id, "author": "mcp", "content": id, "createdAt": "2026-01-01T00:00:00Z" })
+        };
+        let on_disk = json!([{
+            "id": "p1",
+            "tasks": [
+                { "id": "t1", "reports": [report("r1"), report("r2")] },
+                { "id": "t2", "reports": [report("r3")] }
+            ]
+        }]);
+        // The UI saved before seeing r2 and deleted t2.
+        let mut incoming = json!([{
+            "id": "p1",
+            "tasks": [{ "id": "t1", "title": "edited", "reports": [report("r1")] }]
+        }]);
+        assert_eq!(merge_disk_only_reports(&mut incoming, &on_disk), 1);
+        let tasks = incoming[0]["tasks"].as_array().unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0]["title"], "edited");
+        let ids: Vec<&str> = tasks[0]["reports"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(ids, ["r1", "r2"]);
+        assert_eq!(merge_disk_only_reports(&mut incoming, &on_disk), 0);
+    }
+
+    #[test]
+    fn leaving_blocked_status_clears_the_blocker() {
+        let mut project = project_with_statuses(None, &["已阻塞"]);
+        let task = &mut project.tasks[0];
+        task.blocker = Some(Blocker {
+            reason: "等待接口".to_string(),
+            since: "2026-01-01T00:00:00Z".to_string(),
+            blocking_on: None,
+        });
+        task.set_status("已阻塞");
+        assert!(task.blocker.is_some());
+        task.set_status("已完成");
+        assert_eq!(task.status, "已完成");
+        assert!(task.blocker.is_none());
+    }
+
+    #[test]
+    fn wip_limit_allows_a_task_that_is_already_in_progress() {
+        let project = project_with_statuses(Some(1), &["进行中", "待办"]);
+        assert!(check_wip_limit(&project, Some(0), "进行中").is_ok());
+        assert!(check_wip_limit(&project, Some(1), "进行中").is_err());
+        assert!(check_wip_limit(&project_with_statuses(None, &["进行中"]), None, "进行中").is_ok());
+    }
+}
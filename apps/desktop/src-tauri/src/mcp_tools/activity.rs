@@ -0,0 +1,364 @@
+use chrono::Utc;
+use serde_json::{json, Value};
+
+use crate::mcp_http::{
+    find_project_index, read_state, rewrite_maple_asset_urls, summarize_report_content,
+    truncate_chars, Project, Task, TaskReport,
+};
+use super::{with_json_block, ToolError};
+
+/// Parses an RFC 3339 timestamp or a bare `YYYY-MM-DD` date. A bare date covers the
+/// whole UTC day, so `end_of_day` picks its last instant for inclusive upper bounds.
+fn parse_time_bound(raw: &str, end_of_day: bool) -> Option<chrono::DateTime<Utc>> {
+    let raw = raw.trim();
+    if let Ok(parsed) = chrono::DateTime::parse_from_rfc3339(raw) {
+        return Some(parsed.with_timezone(&Utc));
+    }
+    let date = chrono::NaiveDate::parse_from_str(raw, "%Y-%m-%d").ok()?;
+    let time = if end_of_day {
+        date.and_hms_milli_opt(23, 59, 59, 999)?
+    } else {
+        date.and_hms_opt(0, 0, 0)?
+    };
+    Some(time.and_utc())
+}
+
+pub(super) fn tool_query_recent_context(args: &Value) -> Result<Value, ToolError> {
+    let project_name = args.get("project").and_then(|v| v.as_str());
+    let keyword = args.get("keyword").and_then(|v| v.as_str());
+    let limit = args
+        .get("limit")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(10)
+        .max(1) as usize;
+    let mut bounds = [None, None];
+    for (slot, key) in bounds.iter_mut().zip(["since", "until"]) {
+        let Some(raw) = args
+            .get(key)
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+        else {
+            continue;
+        };
+        let Some(parsed) = parse_time_bound(raw, key == "until") else {
+            return Err(ToolError::InvalidArgs(format!(
+                "无法解析 {key}「{raw}」，请使用 ISO-8601 格式，例如 2025-01-31T08:00:00Z 或 2025-01-31。"
+            )));
+        };
+        *slot = Some(parsed);
+    }
+    let [since, until] = bounds;
+
+    let projects = read_state();
+    let indices: Vec<usize> = if let Some(name) = project_name {
+        find_project_index(&projects, name).into_iter().collect()
+    } else {
+        (0..projects.len()).collect()
+    };
+
+    let mut items: Vec<(String, String, String, String)> = Vec::new();
+    for idx in indices {
+        let p = &projects[idx];
+        for task in &p.tasks {
+            for report in &task.reports {
+                let content = report.content.trim();
+                if content.is_empty() {
+                    continue;
+                }
+                if let Some(kw) = keyword {
+                    if !content.to_lowercase().contains(&kw.to_lowercase()) {
+                        continue;
+                    }
+                }
+                if since.is_some() || until.is_some() {
+                    let Some(created) = parse_time_bound(&report.created_at, false) else {
+                        continue;
+                    };
+                    if since.is_some_and(|since| created < since) || until.is_some_and(|until| created > until) {
+                        continue;
+                    }
+                }
+                items.push((
+                    p.name.clone(),
+                    task.title.clone(),
+                    report.created_at.clone(),
+                    content.to_string(),
+                ));
+            }
+        }
+    }
+
+    items.sort_by(|a, b| b.2.cmp(&a.2));
+    let result: Vec<_> = items.iter().take(limit).collect();
+
+    let json_data = json!({
+        "reports": result
+            .iter()
+            .map(|(project, task, created_at, content)| json!({
+                "project": project,
+                "task": task,
+                "createdAt": created_at,
+                "content": rewrite_maple_asset_urls(content).0,
+            }))
+            .collect::<Vec<_>>()
+    });
+
+    if result.is_empty() {
+        let empty = json!({ "content": [{ "type": "text", "text": "未找到匹配的任务报告。" }]});
+        return Ok(with_json_block(args, empty, json_data));
+    }
+
+    let lines: Vec<String> = result
+        .iter()
+        .map(|(proj, task, at, text)| {
+            let (rewritten, _) = rewrite_maple_asset_urls(text);
+            let preview = truncate_chars(&rewritten, 200);
+            format!("[{proj}] {task}\n  时间：{at}\n  内容：{preview}")
+        })
+        .collect();
+
+    let text = json!({ "content": [{ "type": "text", "text": lines.join("\n\n") }]});
+    Ok(with_json_block(args, text, json_data))
+}
+
+const SEARCH_TITLE_WEIGHT: usize = 10;
+
+const SEARCH_DETAILS_WEIGHT: usize = 3;
+
+const SEARCH_REPORT_WEIGHT: usize = 1;
+
+/// Case-insensitive occurrences of `needle` in `haystack`, as char offsets.
+fn find_case_insensitive(haystack: &[char], needle: &[char]) -> Vec<usize> {
+    if needle.is_empty() || haystack.len() < needle.len() {
+        return Vec::new();
+    }
+    (0..=haystack.len() - needle.len())
+        .filter(|&start| {
+            haystack[start..start + needle.len()]
+                .iter()
+                .zip(needle)
+                .all(|(a, b)| a.to_lowercase().eq(b.to_lowercase()))
+        })
+        .collect()
+}
+
+fn search_snippet(chars: &[char], at: usize, len: usize) -> String {
+    let start = at.saturating_sub(30);
+    let before: String = chars[start..at].iter().collect();
+    let matched: String = chars[at..at + len].iter().collect();
+    let after: String = chars[at + len..].iter().collect();
+    let after = truncate_chars(&after, 60);
+    let snippet = format!(
+        "{}{before}【{matched}】{after}{}",
+        if start > 0 { "..." } else { "" },
+        if chars.len() > at + len + 60 { "..." } else { "" }
+    );
+    snippet.replace(['\r', '\n'], " ")
+}
+
+pub(super) fn tool_search_tasks(args: &Value) -> Result<Value, ToolError> {
+    let query = args
+        .get("query")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .unwrap_or("");
+    let project_name = args
+        .get("project")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|v| !v.is_empty());
+    let limit = args
+        .get("limit")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(10)
+        .max(1) as usize;
+
+    if query.is_empty() {
+        return Err(ToolError::InvalidArgs("缺少参数：query。".to_string()));
+    }
+
+    let projects = read_state();
+    let indices: Vec<usize> = match project_name {
+        Some(name) => {
+            let Some(idx) = find_project_index(&projects, name) else {
+                return Err(ToolError::NotFound(format!("未找到匹配项目「{name}」。")));
+            };
+            vec![idx]
+        }
+        None => (0..projects.len()).collect(),
+    };
+
+    let needle: Vec<char> = query.chars().collect();
+    // (score, project, task, matched field, snippet)
+    let mut hits: Vec<(usize, &Project, &Task, &str, String)> = Vec::new();
+    for idx in indices {
+        let project = &projects[idx];
+        for task in &project.tasks {
+            let mut score = 0usize;
+            let mut best: Option<(usize, &str, String)> = None;
+            let mut consider = |text: &str, field: &'static str, weight: usize| {
+                let chars: Vec<char> = text.chars().collect();
+                let found = find_case_insensitive(&chars, &needle);
+                let Some(&first) = found.first() else {
+                    return;
+                };
+                score += weight * found.len();
+                if best.as_ref().map(|(w, ..)| weight > *w).unwrap_or(true) {
+                    best = Some((weight, field, search_snippet(&chars, first, needle.len())));
+                }
+            };
+            consider(&task.title, "标题", SEARCH_TITLE_WEIGHT);
+            consider(&task.details, "详情", SEARCH_DETAILS_WEIGHT);
+            for report in &task.reports {
+                consider(&report.content, "报告", SEARCH_REPORT_WEIGHT);
+            }
+            if let Some((_, field, snippet)) = best {
+                hits.push((score, project, task, field, snippet));
+            }
+        }
+    }
+
+    if hits.is_empty() {
+        return Ok(json!({ "content": [{ "type": "text", "text":
+            format!("未找到包含「{query}」的任务。")
+        }]}));
+    }
+
+    hits.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| b.2.updated_at.cmp(&a.2.updated_at)));
+    let total = hits.len();
+    let mut lines: Vec<String> = vec![format!(
+        "「{query}」共匹配 {total} 个任务，显示前 {} 个：",
+        total.min(limit)
+    )];
+    lines.extend(hits.iter().take(limit).map(|(score, project, task, field, snippet)| {
+        format!(
+            "[{}] {}  (id: {}, 状态: {}, 得分: {score})\n  命中{field}：{snippet}",
+            project.name, task.title, task.id, task.status
+        )
+    }));
+
+    Ok(json!({ "content": [{ "type": "text", "text": lines.join("\n\n") }] }))
+}
+
+pub(super) fn tool_query_latest_activity(args: &Value) -> Result<Value, ToolError> {
+    let limit = args
+        .get("limit")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(1)
+        .max(1) as usize;
+
+    let projects = read_state();
+    let mut items: Vec<(&Project, &Task, &TaskReport)> = Vec::new();
+    for project in &projects {
+        for task in &project.tasks {
+            for report in &task.reports {
+                if report.content.trim().is_empty() {
+                    continue;
+                }
+                items.push((project, task, report));
+            }
+        }
+    }
+
+    if items.is_empty() {
+        return Ok(json!({ "content": [{ "type": "text", "text": "暂无任何任务报告。" }]}));
+    }
+
+    items.sort_by(|a, b| b.2.created_at.cmp(&a.2.created_at));
+    let lines: Vec<String> = items
+        .iter()
+        .take(limit)
+        .map(|(project, task, report)| {
+            let author = if report.author.trim().is_empty() {
+                "unknown"
+            } else {
+                report.author.trim()
+            };
+            let preview = summarize_report_content(&report.content, 220);
+            format!(
+                "[{}] {}  (id: {}, 状态: {})\n  {author} @ {}\n  内容：{preview}",
+                project.name, task.title, task.id, task.status, report.created_at
+            )
+        })
+        .collect();
+
+    Ok(json!({ "content": [{ "type": "text", "text": lines.join("\n\n") }]}))
+}
+
+pub(super) fn tool_query_reports_by_author(args: &Value) -> Result<Value, ToolError> {
+    let project_name = args
+        .get("project")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|v| !v.is_empty());
+    let author = args
+        .get("author")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .unwrap_or("");
+    let limit = args
+        .get("limit")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(20)
+        .max(1) as usize;
+
+    if author.is_empty() {
+        return Err(ToolError::InvalidArgs("缺少参数：author。".to_string()));
+    }
+
+    let projects = read_state();
+    let indices: Vec<usize> = match project_name {
+        Some(name) => {
+            let Some(idx) = find_project_index(&projects, name) else {
+                return Err(ToolError::NotFound(format!("未找到匹配项目「{name}」。")));
+            };
+            vec![idx]
+        }
+        None => (0..projects.len()).collect(),
+    };
+
+    let needle = author.to_lowercase();
+    let mut items: Vec<(&Project, &Task, &TaskReport)> = Vec::new();
+    for idx in indices {
+        let project = &projects[idx];
+        for task in &project.tasks {
+            for report in &task.reports {
+                if report.content.trim().is_empty() {
+                    continue;
+                }
+                if !report.author.trim().to_lowercase().contains(&needle) {
+                    continue;
+                }
+                items.push((project, task, report));
+            }
+        }
+    }
+
+    if items.is_empty() {
+        return Ok(json!({ "content": [{ "type": "text", "text":
+            format!("未找到作者为「{author}」的任务报告。")
+        }]}));
+    }
+
+    items.sort_by(|a, b| b.2.created_at.cmp(&a.2.created_at));
+    let total = items.len();
+    let mut lines: Vec<String> = vec![format!(
+        "作者「{author}」共有 {total} 条报告，显示最新 {} 条：",
+        total.min(limit)
+    )];
+    lines.extend(items.iter().take(limit).map(|(project, task, report)| {
+        let preview = summarize_report_content(&report.content, 220);
+        format!(
+            "[{}] {}  (id: {}, 状态: {})\n  {} @ {}\n  内容：{preview}",
+            project.name,
+            task.title,
+            task.id,
+            task.status,
+            report.author.trim(),
+            report.created_at
+        )
+    }));
+
+    Ok(json!({ "content": [{ "type": "text", "text": lines.join("\n\n") }]}))
+}
@@ -0,0 +1,102 @@
+use base64::Engine;
+use serde_json::{json, Value};
+use std::fs;
+
+use crate::asset_thumbnail;
+use crate::maple_fs;
+use crate::mcp_http::{mime_from_extension, parse_maple_asset_file_name, MCP_IMAGE_MAX_BYTES};
+use super::ToolError;
+
+pub(super) fn read_asset_base64_image(file_name: &str) -> Result<(String, &'static str), String> {
+    let trimmed = file_name.trim();
+    if !maple_fs::is_valid_asset_file_name(trimmed) {
+        return Err("无效的 asset 文件名（必须为 64 位小写 hex + 扩展名）。".to_string());
+    }
+
+    let dir = maple_fs::asset_dir()?;
+    let path = dir.join(trimmed);
+    if !path.exists() {
+        return Err("asset 文件不存在。".to_string());
+    }
+
+    let bytes = fs::read(&path).map_err(|e| format!("读取 asset 文件失败: {e}"))?;
+    if bytes.len() > MCP_IMAGE_MAX_BYTES {
+        return Err(format!("图片过大（{} bytes），已跳过内联。", bytes.len()));
+    }
+
+    let ext = trimmed.split('.').nth(1).unwrap_or_default();
+    let mime = mime_from_extension(ext);
+    if mime == "application/octet-stream" {
+        return Err("不支持的图片类型。".to_string());
+    }
+
+    let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
+    Ok((encoded, mime))
+}
+
+fn normalize_asset_file_name_arg(raw: &str) -> Option<&str> {
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    if let Some(rest) = trimmed.strip_prefix("asset://") {
+        return Some(rest.trim());
+    }
+    if let Some(rest) = parse_maple_asset_file_name(trimmed) {
+        return Some(rest.trim());
+    }
+    Some(trimmed)
+}
+
+pub(super) fn tool_read_asset_image(args: &Value) -> Result<Value, ToolError> {
+    let raw = args
+        .get("file_name")
+        .and_then(|v| v.as_str())
+        .or_else(|| args.get("url").and_then(|v| v.as_str()))
+        .unwrap_or("");
+
+    let Some(file_name) = normalize_asset_file_name_arg(raw) else {
+        return Err(ToolError::InvalidArgs("缺少参数：file_name / url。".to_string()));
+    };
+
+    match read_asset_base64_image(file_name) {
+        Ok((data, mime_type)) => Ok(json!({
+            "content": [
+                { "type": "text", "text": format!("图片：{file_name}") },
+                { "type": "image", "mimeType": mime_type, "data": data }
+            ]
+        })),
+        Err(err) => Err(ToolError::Internal(format!("图片读取失败：{file_name}（{err}）"))),
+    }
+}
+
+pub(super) fn tool_read_asset_thumbnail(args: &Value) -> Result<Value, ToolError> {
+    let raw = args
+        .get("file_name")
+        .and_then(|v| v.as_str())
+        .or_else(|| args.get("url").and_then(|v| v.as_str()))
+        .unwrap_or("");
+
+    let Some(file_name) = normalize_asset_file_name_arg(raw) else {
+        return Err(ToolError::InvalidArgs("缺少参数：file_name / url。".to_string()));
+    };
+    let width = asset_thumbnail::clamp_width(
+        args.get("width")
+            .and_then(|v| v.as_u64())
+            .map(|v| v.min(u32::MAX as u64) as u32),
+    );
+
+    match asset_thumbnail::load_or_create(file_name, width) {
+        Ok(bytes) => Ok(json!({
+            "content": [
+                { "type": "text", "text": format!("缩略图（宽 ≤ {width}px）：{file_name}") },
+                {
+                    "type": "image",
+                    "mimeType": "image/png",
+                    "data": base64::engine::general_purpose::STANDARD.encode(bytes)
+                }
+            ]
+        })),
+        Err(err) => Err(ToolError::Internal(format!("缩略图生成失败：{file_name}（{}）", err.message()))),
+    }
+}
@@ -0,0 +1,265 @@
+use serde_json::{json, Value};
+use tauri::Emitter;
+
+use crate::mcp_http::{
+    check_status_transition, check_wip_limit, describe_blocker, find_project_index,
+    format_report_entry, iso_now, latest_report, read_state, read_status_config, write_state,
+    Blocker, McpHttpState, Project, Task, TaskUpdatedEvent, REPORT_PREVIEW_DEFAULT_CHARS,
+    TASK_STATUSES,
+};
+use super::ToolError;
+
+pub(super) fn tool_set_blocker(args: &Value, state: &McpHttpState) -> Result<Value, ToolError> {
+    let project_name = args
+        .get("project")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let task_id = args
+        .get("task_id")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let reason = args
+        .get("reason")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .unwrap_or("");
+    let blocking_on = args
+        .get("blocking_on")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string);
+
+    if reason.is_empty() {
+        return Err(ToolError::InvalidArgs("缺少参数：reason。".to_string()));
+    }
+
+    let mut projects = read_state();
+    let Some(idx) = find_project_index(&projects, project_name) else {
+        return Err(ToolError::NotFound(format!("未找到匹配项目「{project_name}」。")));
+    };
+
+    let target = &mut projects[idx];
+    let target_name = target.name.clone();
+
+    let Some(task_index) = target.tasks.iter().position(|t| t.id == task_id) else {
+        return Err(ToolError::NotFound(format!("项目「{target_name}」中未找到任务 ID「{task_id}」。")));
+    };
+
+    let config = read_status_config();
+    check_status_transition(&config, &target.tasks[task_index].status, "已阻塞").map_err(ToolError::Refused)?;
+
+    let now = iso_now();
+    {
+        let task = &mut target.tasks[task_index];
+        // Keep the original start time when only the reason is refined.
+        let since = task
+            .blocker
+            .as_ref()
+            .filter(|_| task.status == "已阻塞")
+            .map(|blocker| blocker.since.clone())
+            .unwrap_or_else(|| now.clone());
+        task.blocker = Some(Blocker {
+            reason: reason.to_string(),
+            since,
+            blocking_on,
+        });
+        task.status = "已阻塞".to_string();
+        task.updated_at = now;
+    }
+
+    let task_snapshot = target.tasks[task_index].clone();
+    write_state(&projects);
+    let _ = state.app_handle.emit(
+        "maple://task-updated",
+        TaskUpdatedEvent {
+            project_name: target_name.clone(),
+            task: task_snapshot.clone(),
+        },
+    );
+
+    Ok(json!({ "content": [{ "type": "text", "text":
+        format!("已将「{target_name}」任务「{}」标记为已阻塞：{reason}", task_snapshot.title)
+    }]}))
+}
+
+pub(super) fn tool_clear_blocker(args: &Value, state: &McpHttpState) -> Result<Value, ToolError> {
+    let project_name = args
+        .get("project")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let task_id = args
+        .get("task_id")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let next_status = args
+        .get("status")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .unwrap_or("待办");
+
+    if !TASK_STATUSES.contains(&next_status) {
+        return Err(ToolError::InvalidArgs(format!("无效状态「{next_status}」。")));
+    }
+
+    let mut projects = read_state();
+    let Some(idx) = find_project_index(&projects, project_name) else {
+        return Err(ToolError::NotFound(format!("未找到匹配项目「{project_name}」。")));
+    };
+
+    let target = &mut projects[idx];
+    let target_name = target.name.clone();
+
+    let Some(task_index) = target.tasks.iter().position(|t| t.id == task_id) else {
+        return Err(ToolError::NotFound(format!("项目「{target_name}」中未找到任务 ID「{task_id}」。")));
+    };
+
+    let was_blocked = target.tasks[task_index].status == "已阻塞";
+    if was_blocked {
+        let config = read_status_config();
+        check_status_transition(&config, "已阻塞", next_status)
+            .and_then(|_| check_wip_limit(target, Some(task_index), next_status))
+            .map_err(ToolError::Refused)?;
+    }
+
+    {
+        let task = &mut target.tasks[task_index];
+        task.blocker = None;
+        if was_blocked {
+            task.status = next_status.to_string();
+        }
+        task.updated_at = iso_now();
+    }
+
+    let task_snapshot = target.tasks[task_index].clone();
+    write_state(&projects);
+    let _ = state.app_handle.emit(
+        "maple://task-updated",
+        TaskUpdatedEvent {
+            project_name: target_name.clone(),
+            task: task_snapshot.clone(),
+        },
+    );
+
+    Ok(json!({ "content": [{ "type": "text", "text":
+        format!("已解除「{target_name}」任务「{}」的阻塞，当前状态：{}", task_snapshot.title, task_snapshot.status)
+    }]}))
+}
+
+pub(super) fn tool_query_blockers(args: &Value) -> Result<Value, ToolError> {
+    let name = args.get("project").and_then(|v| v.as_str()).unwrap_or("");
+    let projects = read_state();
+
+    let Some(idx) = find_project_index(&projects, name) else {
+        return Err(ToolError::NotFound(format!("未找到匹配项目「{name}」。")));
+    };
+
+    let target = &projects[idx];
+    let blocked: Vec<&Task> = target
+        .tasks
+        .iter()
+        .filter(|task| task.status == "已阻塞" || task.blocker.is_some())
+        .collect();
+
+    if blocked.is_empty() {
+        return Ok(json!({ "content": [{ "type": "text", "text":
+            format!("项目「{}」暂无阻塞任务。", target.name)
+        }]}));
+    }
+
+    let lines: Vec<String> = blocked
+        .iter()
+        .enumerate()
+        .map(|(i, task)| {
+            let reason = task
+                .blocker
+                .as_ref()
+                .map(describe_blocker)
+                .unwrap_or_else(|| "（未记录原因）".to_string());
+            format!("{}. [{}] {}  (id: {})\n  阻塞原因：{reason}", i + 1, task.status, task.title, task.id)
+        })
+        .collect();
+
+    Ok(json!({ "content": [{ "type": "text", "text": format!(
+        "项目「{}」— {} 个阻塞任务：\n\n{}",
+        target.name,
+        blocked.len(),
+        lines.join("\n")
+    )}]}))
+}
+
+pub(super) fn tool_query_blocked_tasks(args: &Value) -> Result<Value, ToolError> {
+    let project_name = args
+        .get("project")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|v| !v.is_empty());
+
+    let projects = read_state();
+    let indices: Vec<usize> = match project_name {
+        Some(name) => {
+            let Some(idx) = find_project_index(&projects, name) else {
+                return Err(ToolError::NotFound(format!("未找到匹配项目「{name}」。")));
+            };
+            vec![idx]
+        }
+        None => (0..projects.len()).collect(),
+    };
+
+    let mut items: Vec<(&Project, &Task)> = indices
+        .iter()
+        .flat_map(|&idx| {
+            let project = &projects[idx];
+            project
+                .tasks
+                .iter()
+                .filter(|task| task.status == "已阻塞" || task.status == "需要更多信息")
+                .map(move |task| (project, task))
+        })
+        .collect();
+
+    if items.is_empty() {
+        let text = match project_name {
+            Some(_) => format!("项目「{}」暂无已阻塞或需要更多信息的任务。", projects[indices[0]].name),
+            None => "暂无已阻塞或需要更多信息的任务。".to_string(),
+        };
+        return Ok(json!({ "content": [{ "type": "text", "text": text }]}));
+    }
+
+    items.sort_by(|a, b| b.1.updated_at.cmp(&a.1.updated_at));
+    let lines: Vec<String> = items
+        .iter()
+        .enumerate()
+        .map(|(i, (project, task))| {
+            let heading = if project_name.is_some() {
+                format!("{}. [{}] {}  (id: {}, 更新于 {})", i + 1, task.status, task.title, task.id, task.updated_at)
+            } else {
+                format!(
+                    "{}. [{}] [{}] {}  (id: {}, 更新于 {})",
+                    i + 1,
+                    project.name,
+                    task.status,
+                    task.title,
+                    task.id,
+                    task.updated_at
+                )
+            };
+            let reason = latest_report(&task.reports)
+                .map(|report| format_report_entry(report, REPORT_PREVIEW_DEFAULT_CHARS))
+                .unwrap_or_else(|| "（无报告）".to_string());
+            let blocker = task
+                .blocker
+                .as_ref()
+                .map(|blocker| format!("\n  阻塞原因：{}", describe_blocker(blocker)))
+                .unwrap_or_default();
+            format!("{heading}\n  最新报告：{reason}{blocker}")
+        })
+        .collect();
+
+    Ok(json!({ "content": [{ "type": "text", "text": format!(
+        "共 {} 个已阻塞 / 需要更多信息的任务：\n\n{}",
+        items.len(),
+        lines.join("\n")
+    )}]}))
+}
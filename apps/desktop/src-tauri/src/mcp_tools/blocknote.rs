@@ -0,0 +1,134 @@
+use serde_json::Value;
+
+fn render_blocknote_inline(content: &Value) -> String {
+    if let Some(text) = content.as_str() {
+        return text.to_string();
+    }
+    let Some(items) = content.as_array() else {
+        return String::new();
+    };
+    let mut out = String::new();
+    for item in items {
+        match item.get("type").and_then(|v| v.as_str()) {
+            Some("text") => {
+                let text = item.get("text").and_then(|v| v.as_str()).unwrap_or("");
+                let styles = item.get("styles");
+                let style = |name: &str| {
+                    styles
+                        .and_then(|s| s.get(name))
+                        .and_then(|v| v.as_bool())
+                        .unwrap_or(false)
+                };
+                let mut piece = text.to_string();
+                if style("code") {
+                    piece = format!("`{piece}`");
+                }
+                if style("bold") {
+                    piece = format!("**{piece}**");
+                }
+                if style("italic") {
+                    piece = format!("*{piece}*");
+                }
+                if style("strike") {
+                    piece = format!("~~{piece}~~");
+                }
+                out.push_str(&piece);
+            }
+            Some("link") => {
+                let href = item.get("href").and_then(|v| v.as_str()).unwrap_or("");
+                let label = item
+                    .get("content")
+                    .map(render_blocknote_inline)
+                    .unwrap_or_default();
+                out.push_str(&format!("[{label}]({href})"));
+            }
+            _ => {
+                if let Some(text) = item.get("text").and_then(|v| v.as_str()) {
+                    out.push_str(text);
+                }
+            }
+        }
+    }
+    out
+}
+
+fn render_blocknote_blocks(blocks: &[Value], depth: usize, out: &mut Vec<String>) {
+    let indent = "  ".repeat(depth);
+    let mut number = 0usize;
+    for block in blocks {
+        let block_type = block.get("type").and_then(|v| v.as_str()).unwrap_or("paragraph");
+        let props = block.get("props");
+        let prop_str = |name: &str| {
+            props
+                .and_then(|p| p.get(name))
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+        };
+        let text = block
+            .get("content")
+            .map(render_blocknote_inline)
+            .unwrap_or_default();
+        if block_type == "numberedListItem" {
+            number += 1;
+        } else {
+            number = 0;
+        }
+
+        match block_type {
+            "heading" => {
+                let level = props
+                    .and_then(|p| p.get("level"))
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(1)
+                    .clamp(1, 6) as usize;
+                out.push(format!("{} {text}", "#".repeat(level)));
+            }
+            "bulletListItem" => out.push(format!("{indent}- {text}")),
+            "numberedListItem" => out.push(format!("{indent}{number}. {text}")),
+            "checkListItem" => {
+                let checked = props
+                    .and_then(|p| p.get("checked"))
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                out.push(format!("{indent}- [{}] {text}", if checked { "x" } else { " " }));
+            }
+            "codeBlock" => {
+                out.push(format!("```{}", prop_str("language")));
+                out.push(text);
+                out.push("```".to_string());
+            }
+            "quote" => out.push(format!("> {text}")),
+            "image" => {
+                let caption = prop_str("caption");
+                let alt = if caption.is_empty() { prop_str("name") } else { caption };
+                out.push(format!("![{alt}]({})", prop_str("url")));
+            }
+            "file" | "video" | "audio" => {
+                let name = prop_str("name");
+                let url = prop_str("url");
+                out.push(format!("[{}]({url})", if name.is_empty() { url } else { name }));
+            }
+            _ => out.push(format!("{indent}{text}")),
+        }
+
+        if let Some(children) = block.get("children").and_then(|v| v.as_array()) {
+            render_blocknote_blocks(children, depth + 1, out);
+        }
+    }
+}
+
+/// Renders a BlockNote document to markdown; `None` when it isn't a usable block list.
+pub(super) fn render_blocknote_markdown(doc: &Value) -> Option<String> {
+    let blocks = doc.as_array()?;
+    if !blocks.iter().all(|block| block.is_object()) {
+        return None;
+    }
+    let mut lines: Vec<String> = Vec::new();
+    render_blocknote_blocks(blocks, 0, &mut lines);
+    let markdown = lines.join("\n").trim().to_string();
+    if markdown.is_empty() {
+        None
+    } else {
+        Some(markdown)
+    }
+}
@@ -0,0 +1,559 @@
+use serde_json::{json, Value};
+
+pub(crate) fn tool_definitions() -> Vec<Value> {
+    vec![
+        json!({
+            "name": "list_projects",
+            "description": "列出所有项目的名称、目录、默认 Worker、任务总数与待处理任务数（按待处理数降序），用于确认项目名称。",
+            "annotations": { "readOnlyHint": true },
+            "inputSchema": {
+                "type": "object",
+                "properties": {}
+            }
+        }),
+        json!({
+            "name": "query_project_todos",
+            "description": "按项目名查询待处理任务（不含草稿/已完成），返回状态、标签、详情与历史报告摘要。",
+            "annotations": { "readOnlyHint": true },
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "project": { "type": "string", "description": "项目名称（模糊匹配）" },
+                    "worker_kind": {
+                        "type": "string",
+                        "enum": ["claude", "codex", "iflow", "gemini", "opencode"],
+                        "description": "可选：按 Worker kind 过滤可见任务（用于任务指定 Worker 派发）。"
+                    },
+                    "tags": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "可选：仅返回带有这些 Tag 的任务（匹配方式由 match 决定）"
+                    },
+                    "match": {
+                        "type": "string",
+                        "enum": ["any", "all"],
+                        "description": "可选：tags 匹配方式，any = 任一命中（默认），all = 全部命中"
+                    },
+                    "statuses": {
+                        "type": "array",
+                        "items": {
+                            "type": "string",
+                            "enum": ["待办", "待返工", "队列中", "进行中", "需要更多信息", "已阻塞"]
+                        },
+                        "description": "可选：仅返回这些状态的任务"
+                    },
+                    "offset": { "type": "number", "description": "分页起始位置（可选，默认 0）" },
+                    "limit": { "type": "number", "description": "每页条数（可选，默认 20，最大 100）" },
+                    "history_limit": { "type": "number", "description": "每个任务显示的历史报告条数（可选，默认 5，最大 50）" },
+                    "history_preview_chars": { "type": "number", "description": "每条历史报告的预览字符数（可选，默认 220，范围 40-4000）" },
+                    "format": { "type": "string", "enum": ["text", "json"], "description": "可选：json 时额外返回一个 JSON 内容块，便于程序解析（默认 text）" }
+                },
+                "required": ["project"]
+            }
+        }),
+        json!({
+            "name": "query_task_details",
+            "description": "查询指定任务的详情内容（包含 markdown、图片、文件引用等）。",
+            "annotations": { "readOnlyHint": true },
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "project": { "type": "string", "description": "项目名称（模糊匹配）" },
+                    "task_id": { "type": "string", "description": "任务 ID" },
+                    "format": { "type": "string", "enum": ["text", "json"], "description": "可选：json 时额外返回一个 JSON 内容块，便于程序解析（默认 text）" }
+                },
+                "required": ["project", "task_id"]
+            }
+        }),
+        json!({
+            "name": "summarize_task",
+            "description": "生成任务的简短进展摘要（不调用模型）：当前状态、创建/更新时长、阻塞原因、最近几条报告预览，适合开工前快速了解任务。",
+            "annotations": { "readOnlyHint": true },
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "project": { "type": "string", "description": "项目名称（模糊匹配）" },
+                    "task_id": { "type": "string", "description": "任务 ID" }
+                },
+                "required": ["project", "task_id"]
+            }
+        }),
+        json!({
+            "name": "query_task_counts",
+            "description": "按状态统计任务数量（与托盘 TrayTaskSnapshot 字段一致：unresolvedCount / inProgressCount / queuedCount / todoCount / needInfoCount / confirmCount / blockedCount / completedCount），以 JSON 返回。",
+            "annotations": { "readOnlyHint": true },
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "project": { "type": "string", "description": "项目名称（可选，模糊匹配；不填则统计全部项目）" }
+                }
+            }
+        }),
+        json!({
+            "name": "get_project_stats",
+            "description": "获取项目统计：各状态任务数、报告总数、使用中的 Tag 数量与最近更新时间。第二个 content 为结构化 JSON。",
+            "annotations": { "readOnlyHint": true },
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "project": { "type": "string", "description": "项目名称（模糊匹配）" }
+                },
+                "required": ["project"]
+            }
+        }),
+        json!({
+            "name": "query_task_graph",
+            "description": "查询任务的结构关系（父任务、子任务、依赖的任务、依赖它的任务），以 JSON 返回各任务的 id/title/status。",
+            "annotations": { "readOnlyHint": true },
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "project": { "type": "string", "description": "项目名称（模糊匹配）" },
+                    "task_id": { "type": "string", "description": "任务 ID" }
+                },
+                "required": ["project", "task_id"]
+            }
+        }),
+        json!({
+            "name": "query_project_tree",
+            "description": "返回项目目录的文件树概览（遵循 .gitignore，限制深度与条目数，node_modules / .git 等大目录只列出不展开），用于开工前快速了解仓库结构。",
+            "annotations": { "readOnlyHint": true },
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "project": { "type": "string", "description": "项目名称（模糊匹配）" },
+                    "path": { "type": "string", "description": "项目目录内的相对子路径（可选，默认项目根目录）" },
+                    "max_depth": { "type": "number", "description": "最大深度（可选，默认 3，最大 8）" },
+                    "max_entries": { "type": "number", "description": "最多返回的条目数（可选，默认 200，最大 2000）" }
+                },
+                "required": ["project"]
+            }
+        }),
+        json!({
+            "name": "update_task_details",
+            "description": "更新指定任务的详情内容（支持追加或替换）。",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "project": { "type": "string", "description": "项目名称（模糊匹配）" },
+                    "task_id": { "type": "string", "description": "任务 ID" },
+                    "details": { "type": "string", "description": "详情内容（Markdown）" },
+                    "mode": {
+                        "type": "string",
+                        "enum": ["append", "replace"],
+                        "description": "更新方式：append 追加 / replace 覆盖（可选，默认 append）"
+                    }
+                },
+                "required": ["project", "task_id", "details"]
+            }
+        }),
+        json!({
+            "name": "append_task_detail",
+            "description": "在任务详情末尾追加一段文本（以空行分隔），不提交报告。若任务有富文本详情（detailsDoc）会被清除，以 Markdown 文本为准。返回追加后的详情长度。",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "project": { "type": "string", "description": "项目名称（模糊匹配）" },
+                    "task_id": { "type": "string", "description": "任务 ID" },
+                    "text": { "type": "string", "description": "要追加的内容（Markdown）" }
+                },
+                "required": ["project", "task_id", "text"]
+            }
+        }),
+        json!({
+            "name": "read_asset_image",
+            "description": "读取任务中的本地图片 asset，并以 MCP image 内容块返回（避免 maple://）。",
+            "annotations": { "readOnlyHint": true },
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "file_name": { "type": "string", "description": "asset 文件名（hash.ext），也支持 asset://... / maple://... 形式。" }
+                },
+                "required": ["file_name"]
+            }
+        }),
+        json!({
+            "name": "read_asset_thumbnail",
+            "description": "读取图片 asset 的缩略图（PNG，按比例缩放），适合快速预览大截图。",
+            "annotations": { "readOnlyHint": true },
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "file_name": { "type": "string", "description": "asset 文件名（hash.ext），也支持 asset://... / maple://... 形式。" },
+                    "width": { "type": "integer", "description": "最大宽度（像素，可选，默认 320）" }
+                },
+                "required": ["file_name"]
+            }
+        }),
+        json!({
+            "name": "attach_file_reference",
+            "description": "为任务关联一个项目内的文件路径引用（不会复制文件），在任务详情中展示，可由 Maple 直接打开。",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "project": { "type": "string", "description": "项目名称（模糊匹配）" },
+                    "task_id": { "type": "string", "description": "任务 ID" },
+                    "path": { "type": "string", "description": "文件路径（绝对路径，或相对项目目录的路径）" },
+                    "label": { "type": "string", "description": "展示名（可选）" }
+                },
+                "required": ["project", "task_id", "path"]
+            }
+        }),
+        json!({
+            "name": "query_recent_context",
+            "description": "查询最近任务报告，支持项目名、关键词和时间范围过滤。",
+            "annotations": { "readOnlyHint": true },
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "project": { "type": "string", "description": "项目名称（可选，模糊匹配）" },
+                    "keyword": { "type": "string", "description": "搜索关键词（可选）" },
+                    "since": { "type": "string", "description": "可选：只返回此时间及之后的报告。ISO-8601，如 2025-01-31T08:00:00Z 或 2025-01-31（当天 00:00 UTC）" },
+                    "until": { "type": "string", "description": "可选：只返回此时间及之前的报告。ISO-8601，仅日期时包含当天全天（UTC）" },
+                    "limit": { "type": "number", "description": "最多返回条数" },
+                    "format": { "type": "string", "enum": ["text", "json"], "description": "可选：json 时额外返回一个 JSON 内容块，便于程序解析（默认 text）" }
+                }
+            }
+        }),
+        json!({
+            "name": "search_tasks",
+            "description": "全文搜索任务标题、详情与报告内容（大小写不敏感），按相关度排序（标题命中权重最高），返回命中字段与高亮片段。",
+            "annotations": { "readOnlyHint": true },
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "搜索关键词" },
+                    "project": { "type": "string", "description": "项目名称（可选，不填则搜索所有项目）" },
+                    "limit": { "type": "number", "description": "最多返回条数（可选，默认 10）" }
+                },
+                "required": ["query"]
+            }
+        }),
+        json!({
+            "name": "query_latest_activity",
+            "description": "查询全局最新的任务报告（跨所有项目），适合恢复工作时快速了解最近动态。",
+            "annotations": { "readOnlyHint": true },
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "limit": { "type": "number", "description": "最多返回条数（可选，默认 1）" }
+                }
+            }
+        }),
+        json!({
+            "name": "query_reports_by_author",
+            "description": "按作者（如 mcp、Claude、Codex，大小写不敏感、部分匹配）查询任务报告，按时间倒序返回，附带项目与任务上下文，用于审计某个 Agent 的行为。",
+            "annotations": { "readOnlyHint": true },
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "author": { "type": "string", "description": "报告作者" },
+                    "project": { "type": "string", "description": "项目名称（可选，不填则查询所有项目）" },
+                    "limit": { "type": "number", "description": "最多返回条数（可选，默认 20）" }
+                },
+                "required": ["author"]
+            }
+        }),
+        json!({
+            "name": "submit_task_report",
+            "description": "提交任务执行报告，并可修改任务状态。",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "project": { "type": "string", "description": "项目名称" },
+                    "task_id": { "type": "string", "description": "任务 ID" },
+                    "status": {
+                        "type": "string",
+                        "enum": ["草稿", "待办", "待返工", "队列中", "进行中", "需要更多信息", "已完成", "已阻塞"],
+                        "description": "新状态（可选）"
+                    },
+                    "report": { "type": "string", "description": "报告内容" },
+                    "tags": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "minItems": 1,
+                        "maxItems": 5,
+                        "description": "标签列表（必填，1-5 个）。提交报告时必须严格更新 task.tags。使用新 Tag 前，请先调用 upsert_tag_definition 创建/完善定义；若 Tag Catalog 中缺少该 Tag，submit_task_report 会报错。"
+                    }
+                },
+                "required": ["project", "task_id", "report", "tags"]
+            }
+        }),
+        json!({
+            "name": "create_task",
+            "description": "在项目中创建新任务。未在 Tag Catalog 中定义的 Tag 会自动补全定义。",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "project": { "type": "string", "description": "项目名称" },
+                    "title": { "type": "string", "description": "任务标题" },
+                    "details": { "type": "string", "description": "任务详情（可选，markdown）" },
+                    "status": {
+                        "type": "string",
+                        "enum": ["草稿", "待办", "待返工", "队列中", "进行中", "需要更多信息", "已完成", "已阻塞"],
+                        "description": "初始状态（可选，默认 待办）"
+                    },
+                    "tags": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "maxItems": 5,
+                        "description": "标签列表（可选，最多 5 个）"
+                    }
+                },
+                "required": ["project", "title"]
+            }
+        }),
+        json!({
+            "name": "set_task_status",
+            "description": "仅修改任务状态（不提交报告）。若配置了状态流转规则（~/.maple/status-config.json），非法流转会被拒绝并提示允许的下一状态。",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "project": { "type": "string", "description": "项目名称" },
+                    "task_id": { "type": "string", "description": "任务 ID" },
+                    "status": {
+                        "type": "string",
+                        "enum": ["草稿", "待办", "待返工", "队列中", "进行中", "需要更多信息", "已完成", "已阻塞"],
+                        "description": "新状态"
+                    }
+                },
+                "required": ["project", "task_id", "status"]
+            }
+        }),
+        json!({
+            "name": "bulk_status_by_tag",
+            "description": "将项目内所有带指定 Tag 的任务批量改为同一状态，可附带一条共享报告。遵循状态流转规则与 WIP 上限，不符合规则的任务会被跳过并列出。",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "project": { "type": "string", "description": "项目名称" },
+                    "tag": { "type": "string", "description": "Tag（大小写与首尾空白不敏感）" },
+                    "status": {
+                        "type": "string",
+                        "enum": ["草稿", "待办", "待返工", "队列中", "进行中", "需要更多信息", "已完成", "已阻塞"],
+                        "description": "新状态"
+                    },
+                    "report": { "type": "string", "description": "附加到每个任务的报告内容（可选）" }
+                },
+                "required": ["project", "tag", "status"]
+            }
+        }),
+        json!({
+            "name": "update_task_statuses",
+            "description": "在一次读写中批量设置多个任务的状态（逐项遵循状态流转规则与 WIP 上限），返回每一项的成功/失败。",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "project": { "type": "string", "description": "项目名称（模糊匹配）" },
+                    "updates": {
+                        "type": "array",
+                        "description": "要更新的任务列表",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "task_id": { "type": "string", "description": "任务 ID" },
+                                "status": {
+                                    "type": "string",
+                                    "enum": ["草稿", "待办", "待返工", "队列中", "进行中", "需要更多信息", "已完成", "已阻塞"],
+                                    "description": "新状态"
+                                }
+                            },
+                            "required": ["task_id", "status"]
+                        }
+                    },
+                    "format": { "type": "string", "enum": ["text", "json"], "description": "可选：json 时额外返回一个 JSON 内容块，便于程序解析（默认 text）" }
+                },
+                "required": ["project", "updates"]
+            }
+        }),
+        json!({
+            "name": "duplicate_task",
+            "description": "复制任务（标题追加 \" (copy)\"，保留详情与标签，状态重置为待办，清空报告），插入在原任务之后，返回新任务 ID。",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "project": { "type": "string", "description": "项目名称（模糊匹配）" },
+                    "task_id": { "type": "string", "description": "要复制的任务 ID" }
+                },
+                "required": ["project", "task_id"]
+            }
+        }),
+        json!({
+            "name": "delete_task",
+            "description": "从项目中永久删除任务（不可恢复）。必须显式传入 confirm: true，否则拒绝执行。",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "project": { "type": "string", "description": "项目名称" },
+                    "task_id": { "type": "string", "description": "任务 ID" },
+                    "confirm": { "type": "boolean", "description": "必须为 true 才会执行删除" }
+                },
+                "required": ["project", "task_id", "confirm"]
+            }
+        }),
+        json!({
+            "name": "set_blocker",
+            "description": "记录任务的阻塞原因并将状态设为已阻塞。",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "project": { "type": "string", "description": "项目名称（模糊匹配）" },
+                    "task_id": { "type": "string", "description": "任务 ID" },
+                    "reason": { "type": "string", "description": "阻塞原因" },
+                    "blocking_on": { "type": "string", "description": "等待的对象（可选，例如任务 ID、人或外部依赖）" }
+                },
+                "required": ["project", "task_id", "reason"]
+            }
+        }),
+        json!({
+            "name": "clear_blocker",
+            "description": "清除任务的阻塞记录；若任务处于已阻塞，则切换到 status（默认待办）。",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "project": { "type": "string", "description": "项目名称（模糊匹配）" },
+                    "task_id": { "type": "string", "description": "任务 ID" },
+                    "status": {
+                        "type": "string",
+                        "enum": ["草稿", "待办", "待返工", "队列中", "进行中", "需要更多信息", "已完成"],
+                        "description": "解除阻塞后的状态（可选，默认待办）"
+                    }
+                },
+                "required": ["project", "task_id"]
+            }
+        }),
+        json!({
+            "name": "query_blockers",
+            "description": "列出项目内所有阻塞任务、阻塞原因及已阻塞时长。",
+            "annotations": { "readOnlyHint": true },
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "project": { "type": "string", "description": "项目名称（模糊匹配）" }
+                },
+                "required": ["project"]
+            }
+        }),
+        json!({
+            "name": "query_blocked_tasks",
+            "description": "列出状态为已阻塞或需要更多信息的任务（按更新时间倒序），并附上最新一条报告作为原因。不传 project 时扫描全部项目。",
+            "annotations": { "readOnlyHint": true },
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "project": { "type": "string", "description": "可选：项目名称（模糊匹配）" }
+                }
+            }
+        }),
+        json!({
+            "name": "set_wip_limit",
+            "description": "设置项目的 WIP 上限（同时处于进行中的任务数）。limit 为 0 或不填表示取消上限。",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "project": { "type": "string", "description": "项目名称（模糊匹配）" },
+                    "limit": { "type": "number", "description": "WIP 上限（可选，0/不填为不限制）" }
+                },
+                "required": ["project"]
+            }
+        }),
+        json!({
+            "name": "query_tag_catalog",
+            "description": "查询项目 Tag Catalog（标签定义：颜色/图标/多语言 label）。",
+            "annotations": { "readOnlyHint": true },
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "project": { "type": "string", "description": "项目名称（模糊匹配）" },
+                    "format": { "type": "string", "enum": ["text", "json"], "description": "可选：json 时额外返回一个 JSON 内容块，便于程序解析（默认 text）" }
+                },
+                "required": ["project"]
+            }
+        }),
+        json!({
+            "name": "upsert_tag_definition",
+            "description": "创建或更新 Tag 定义（用于 UI 渲染颜色/图标/多语言 label）。",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "project": { "type": "string", "description": "项目名称（模糊匹配）" },
+                    "tag": { "type": "string", "description": "Tag ID（会被 trim + lower-case 归一化）" },
+                    "color": { "type": "string", "description": "CSS 颜色（例如 #22c55e / hsl(...) / var(--color-primary)）" },
+                    "icon": { "type": "string", "description": "Iconify 图标（仅允许 mingcute 集，例如 mingcute:tag-line）" },
+                    "label_zh": { "type": "string", "description": "中文展示名（可选）" },
+                    "label_en": { "type": "string", "description": "英文展示名（可选）" }
+                },
+                "required": ["project", "tag"]
+            }
+        }),
+        json!({
+            "name": "delete_tag_definition",
+            "description": "删除 Tag Catalog 中的 Tag 定义；传 remove_from_tasks: true 时同时从所有任务中移除该 Tag。",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "project": { "type": "string", "description": "项目名称（模糊匹配）" },
+                    "tag": { "type": "string", "description": "Tag ID（会被 trim + lower-case 归一化）" },
+                    "remove_from_tasks": { "type": "boolean", "description": "是否同时从任务中移除该 Tag（默认 false）" }
+                },
+                "required": ["project", "tag"]
+            }
+        }),
+        json!({
+            "name": "normalize_project_tags",
+            "description": "规范化项目内所有任务的 Tag（trim + lower-case + 去重），并合并归一化后重复的 Tag Catalog 定义。",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "project": { "type": "string", "description": "项目名称（模糊匹配）" }
+                },
+                "required": ["project"]
+            }
+        }),
+        json!({
+            "name": "rename_tag",
+            "description": "重命名项目内的 Tag：替换所有任务中的该 Tag，并将 Tag Catalog 定义迁移到新名称（目标已存在时合并，已有设置优先）。",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "project": { "type": "string", "description": "项目名称（模糊匹配）" },
+                    "from": { "type": "string", "description": "原 Tag ID（会被 trim + lower-case 归一化）" },
+                    "to": { "type": "string", "description": "新 Tag ID（会被 trim + lower-case 归一化）" }
+                },
+                "required": ["project", "from", "to"]
+            }
+        }),
+        json!({
+            "name": "regenerate_tag_catalog",
+            "description": "为项目任务中用到的所有 Tag 补全 Tag Catalog：缺失的定义会被创建，空缺的 icon / label 按内置预设补齐，已有的用户设置不会被覆盖。",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "project": { "type": "string", "description": "项目名称（模糊匹配）" }
+                },
+                "required": ["project"]
+            }
+        }),
+        json!({
+            "name": "finish_worker",
+            "description": "通知 Maple 当前 Worker 已执行完毕。调用前必须确保项目内无待办/待返工/队列中/进行中任务（除非传入 force: true）。",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "project": { "type": "string", "description": "项目名称" },
+                    "worker_kind": {
+                        "type": "string",
+                        "enum": ["claude", "codex", "iflow", "gemini", "opencode"],
+                        "description": "可选：当前 Worker kind（用于按任务指定 Worker 分流 finish_worker 校验）。"
+                    },
+                    "summary": { "type": "string", "description": "执行总结（可选）" },
+                    "force": {
+                        "type": "boolean",
+                        "description": "可选：为 true 时即使仍有未收敛任务也结束（例如有意保留的待办），响应中会列出这些任务。"
+                    }
+                },
+                "required": ["project"]
+            }
+        }),
+    ]
+}
@@ -0,0 +1,150 @@
+mod activity;
+mod assets;
+mod blockers;
+mod blocknote;
+mod definitions;
+mod projects;
+mod tags;
+mod task_details;
+mod tasks;
+mod worker;
+
+use serde_json::{json, Value};
+use std::collections::HashSet;
+
+use crate::mcp_http::McpHttpState;
+
+pub(crate) use definitions::tool_definitions;
+
+/// Runs the MCP tool `tool_name` with its call arguments.
+pub(crate) fn call_tool(tool_name: &str, args: &Value, state: &McpHttpState) -> Result<Value, ToolError> {
+    match tool_name {
+        "list_projects" => projects::tool_list_projects(),
+        "query_project_todos" => projects::tool_query_project_todos(args),
+        "query_recent_context" => activity::tool_query_recent_context(args),
+        "search_tasks" => activity::tool_search_tasks(args),
+        "query_latest_activity" => activity::tool_query_latest_activity(args),
+        "query_reports_by_author" => activity::tool_query_reports_by_author(args),
+        "query_task_details" => task_details::tool_query_task_details(args),
+        "summarize_task" => task_details::tool_summarize_task(args),
+        "query_task_counts" => projects::tool_query_task_counts(args),
+        "get_project_stats" => projects::tool_get_project_stats(args),
+        "query_task_graph" => task_details::tool_query_task_graph(args),
+        "query_project_tree" => projects::tool_query_project_tree(args),
+        "update_task_details" => task_details::tool_update_task_details(args, state),
+        "append_task_detail" => task_details::tool_append_task_detail(args, state),
+        "read_asset_image" => assets::tool_read_asset_image(args),
+        "read_asset_thumbnail" => assets::tool_read_asset_thumbnail(args),
+        "attach_file_reference" => task_details::tool_attach_file_reference(args, state),
+        "submit_task_report" => tasks::tool_submit_task_report(args, state),
+        "create_task" => tasks::tool_create_task(args, state),
+        "set_task_status" => tasks::tool_set_task_status(args, state),
+        "bulk_status_by_tag" => tasks::tool_bulk_status_by_tag(args, state),
+        "update_task_statuses" => tasks::tool_update_task_statuses(args, state),
+        "duplicate_task" => tasks::tool_duplicate_task(args, state),
+        "delete_task" => tasks::tool_delete_task(args, state),
+        "set_blocker" => blockers::tool_set_blocker(args, state),
+        "clear_blocker" => blockers::tool_clear_blocker(args, state),
+        "query_blockers" => blockers::tool_query_blockers(args),
+        "query_blocked_tasks" => blockers::tool_query_blocked_tasks(args),
+        "set_wip_limit" => projects::tool_set_wip_limit(args, state),
+        "query_tag_catalog" => tags::tool_query_tag_catalog(args),
+        "upsert_tag_definition" => tags::tool_upsert_tag_definition(args, state),
+        "delete_tag_definition" => tags::tool_delete_tag_definition(args, state),
+        "normalize_project_tags" => tags::tool_normalize_project_tags(args, state),
+        "rename_tag" => tags::tool_rename_tag(args, state),
+        "regenerate_tag_catalog" => tags::tool_regenerate_tag_catalog(args, state),
+        "finish_worker" => worker::tool_finish_worker(args, state),
+        _ => Err(ToolError::InvalidArgs(format!("未知工具：{tool_name}"))),
+    }
+}
+
+/// Trimmed, non-empty strings from an optional array argument.
+fn string_array_arg(args: &Value, key: &str) -> Vec<String> {
+    args.get(key)
+        .and_then(|v| v.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| item.as_str())
+                .map(|item| item.trim().to_string())
+                .filter(|item| !item.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn wants_json_output(args: &Value) -> bool {
+    args.get("format")
+        .and_then(|v| v.as_str())
+        .is_some_and(|format| format.trim().eq_ignore_ascii_case("json"))
+}
+
+/// Appends `data` as a second, machine-readable content block when `format: "json"`
+/// was requested; the human-readable text block stays first.
+fn with_json_block(args: &Value, mut result: Value, data: Value) -> Value {
+    if !wants_json_output(args) {
+        return result;
+    }
+    if let Some(content) = result.get_mut("content").and_then(|v| v.as_array_mut()) {
+        content.push(json!({
+            "type": "text",
+            "text": serde_json::to_string_pretty(&data).unwrap_or_default()
+        }));
+    }
+    result
+}
+
+/// Every defined tool that does not declare `readOnlyHint: true`, so a new tool is
+/// locked and pausable by default instead of needing to be listed here.
+pub(crate) fn is_write_tool(tool_name: &str) -> bool {
+    static WRITE_TOOLS: std::sync::OnceLock<HashSet<String>> = std::sync::OnceLock::new();
+    WRITE_TOOLS
+        .get_or_init(|| {
+            tool_definitions()
+                .iter()
+                .filter(|tool| {
+                    tool.pointer("/annotations/readOnlyHint").and_then(|v| v.as_bool()) != Some(true)
+                })
+                .filter_map(|tool| tool.get("name").and_then(|v| v.as_str()).map(str::to_string))
+                .collect()
+        })
+        .contains(tool_name)
+}
+
+/// Tool failures. `Refused` is a business-rule refusal (status transition, WIP
+/// limit, unfinished tasks) and is returned as `isError` content so the model
+/// reads it as a tool result; every other variant becomes a top-level JSON-RPC
+/// `error`.
+pub(crate) enum ToolError {
+    NotFound(String),
+    ResourceNotFound(String),
+    InvalidArgs(String),
+    Refused(String),
+    Internal(String),
+}
+
+impl ToolError {
+    pub(crate) fn code(&self) -> i64 {
+        match self {
+            ToolError::NotFound(_) => -32004,
+            ToolError::ResourceNotFound(_) => -32002,
+            ToolError::InvalidArgs(_) => -32602,
+            ToolError::Refused(_) | ToolError::Internal(_) => -32603,
+        }
+    }
+
+    pub(crate) fn message(&self) -> &str {
+        match self {
+            ToolError::NotFound(message)
+            | ToolError::ResourceNotFound(message)
+            | ToolError::InvalidArgs(message)
+            | ToolError::Refused(message)
+            | ToolError::Internal(message) => message,
+        }
+    }
+
+    pub(crate) fn to_json(&self) -> Value {
+        json!({ "code": self.code(), "message": self.message() })
+    }
+}
@@ -0,0 +1,472 @@
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+use tauri::Emitter;
+
+use crate::mcp_http::{
+    build_report_history_lines, describe_blocker, find_project_index, is_terminal_task_status,
+    normalize_tag_id, read_state, rewrite_maple_asset_urls, write_state, McpHttpState, Project,
+    ProjectSettingsUpdatedEvent, ReportHistoryOptions, Task, TASK_STATUSES,
+};
+use crate::tray_status::TrayTaskSnapshot;
+use super::{string_array_arg, with_json_block, ToolError};
+
+pub(super) fn tool_list_projects() -> Result<Value, ToolError> {
+    let projects = read_state();
+    if projects.is_empty() {
+        return Ok(json!({ "content": [{ "type": "text", "text": "暂无项目。" }]}));
+    }
+
+    let mut rows: Vec<(&Project, usize)> = projects
+        .iter()
+        .map(|project| {
+            let unresolved = project
+                .tasks
+                .iter()
+                .filter(|task| !is_terminal_task_status(&task.status))
+                .count();
+            (project, unresolved)
+        })
+        .collect();
+    rows.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.name.cmp(&b.0.name)));
+
+    let lines: Vec<String> = rows
+        .iter()
+        .map(|(project, unresolved)| {
+            let worker = project
+                .worker_kind
+                .as_deref()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .unwrap_or("未指定");
+            let directory = if project.directory.trim().is_empty() {
+                "（未配置目录）"
+            } else {
+                project.directory.as_str()
+            };
+            format!(
+                "- {}  待处理 {unresolved} / 共 {} 个任务  worker: {worker}\n  目录：{directory}",
+                project.name,
+                project.tasks.len()
+            )
+        })
+        .collect();
+
+    Ok(json!({ "content": [{ "type": "text", "text": format!(
+        "共 {} 个项目（按待处理任务数排序）：\n{}",
+        rows.len(),
+        lines.join("\n")
+    )}]}))
+}
+
+const TODOS_DEFAULT_PAGE_SIZE: usize = 20;
+
+const TODOS_MAX_PAGE_SIZE: usize = 100;
+
+pub(super) fn tool_query_project_todos(args: &Value) -> Result<Value, ToolError> {
+    let name = args.get("project").and_then(|v| v.as_str()).unwrap_or("");
+    let worker_kind = args
+        .get("worker_kind")
+        .and_then(|v| v.as_str())
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .map(|v| v.to_lowercase());
+    let offset = args
+        .get("offset")
+        .and_then(|v| v.as_i64())
+        .unwrap_or(0)
+        .max(0) as usize;
+    let limit = args
+        .get("limit")
+        .and_then(|v| v.as_i64())
+        .unwrap_or(TODOS_DEFAULT_PAGE_SIZE as i64)
+        .clamp(1, TODOS_MAX_PAGE_SIZE as i64) as usize;
+    let tag_filter: Vec<String> = string_array_arg(args, "tags")
+        .iter()
+        .map(|tag| normalize_tag_id(tag))
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    let match_all = args.get("match").and_then(|v| v.as_str()) == Some("all");
+    let status_filter = string_array_arg(args, "statuses");
+    let history_options = ReportHistoryOptions::from_args(args);
+    if let Some(invalid) = status_filter.iter().find(|s| !TASK_STATUSES.contains(&s.as_str())) {
+        return Err(ToolError::InvalidArgs(format!(
+            "无效状态「{invalid}」。可选：{}",
+            TASK_STATUSES.join("、")
+        )));
+    }
+    let projects = read_state();
+
+    let Some(idx) = find_project_index(&projects, name) else {
+        let names: Vec<&str> = projects.iter().map(|p| p.name.as_str()).collect();
+        return Err(ToolError::NotFound(format!(
+            "未找到匹配项目「{name}」。可用项目：{}",
+            if names.is_empty() { "（无）".to_string() } else { names.join("、") }
+        )));
+    };
+
+    let target = &projects[idx];
+    let mut todos: Vec<&Task> = target
+        .tasks
+        .iter()
+        .filter(|t| t.status != "已完成" && t.status != "草稿")
+        .filter(|task| match worker_kind.as_deref() {
+            None => task
+                .target_worker_kind
+                .as_deref()
+                .map(|value| value.trim().is_empty())
+                .unwrap_or(true),
+            Some(kind) => {
+                if let Some(target_kind) = task.target_worker_kind.as_deref() {
+                    target_kind.trim().eq_ignore_ascii_case(kind)
+                } else if let Some(default_kind) = target.worker_kind.as_deref() {
+                    default_kind.trim().eq_ignore_ascii_case(kind)
+                } else {
+                    true
+                }
+            }
+        })
+        .filter(|task| status_filter.is_empty() || status_filter.contains(&task.status))
+        .filter(|task| {
+            if tag_filter.is_empty() {
+                return true;
+            }
+            let has_tag = |tag: &String| task.tags.iter().any(|t| normalize_tag_id(t) == *tag);
+            if match_all {
+                tag_filter.iter().all(has_tag)
+            } else {
+                tag_filter.iter().any(has_tag)
+            }
+        })
+        .collect();
+    // Id tiebreak keeps the order identical between page requests.
+    todos.sort_by(|a, b| b.updated_at.cmp(&a.updated_at).then_with(|| a.id.cmp(&b.id)));
+
+    let total = todos.len();
+    let start = offset.min(total);
+    let end = (start + limit).min(total);
+    let json_data = json!({
+        "project": target.name,
+        "total": total,
+        "offset": start,
+        "limit": limit,
+        "nextOffset": if end < total { Some(end) } else { None },
+        "wipLimit": target.wip_limit,
+        "tasks": &todos[start..end],
+    });
+
+    if todos.is_empty() {
+        let result = json!({ "content": [{ "type": "text", "text":
+            format!("项目「{}」暂无待处理任务。", target.name)
+        }]});
+        return Ok(with_json_block(args, result, json_data));
+    }
+
+    let lines: Vec<String> = todos[start..end]
+        .iter()
+        .enumerate()
+        .map(|(i, t)| {
+            let tags = if t.tags.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", t.tags.join(", "))
+            };
+            let title = if t.title.trim().is_empty() {
+                "（无标题）"
+            } else {
+                t.title.as_str()
+            };
+            let details = t.details.trim();
+            let details_text = if details.is_empty() {
+                "（空）".to_string()
+            } else {
+                rewrite_maple_asset_urls(details).0
+            };
+            let mut block = vec![format!("{}. [{}] {}{}  (id: {})", start + i + 1, t.status, title, tags, t.id)];
+            if let Some(blocker) = t.blocker.as_ref() {
+                block.push(format!("阻塞原因：{}", describe_blocker(blocker)));
+            }
+            block.extend(["详情：".to_string(), details_text, String::new()]);
+            block.extend(build_report_history_lines(&t.reports, history_options));
+            block.join("\n")
+        })
+        .collect();
+
+    let wip = target.wip_limit.map(|limit| {
+        let in_progress = target.tasks.iter().filter(|t| t.status == "进行中").count();
+        format!("WIP：{in_progress} / {limit}\n")
+    });
+    let page = if start >= end {
+        format!("offset={offset} 超出范围，共 {total} 个")
+    } else if end < total {
+        format!("显示 {}-{end}，共 {total} 个（使用 offset={end} 获取下一页）", start + 1)
+    } else {
+        format!("显示 {}-{end}，共 {total} 个", start + 1)
+    };
+    let result = json!({ "content": [{ "type": "text", "text": format!(
+        "项目「{}」— {} 个待处理任务（不含草稿）：\n{}\n{}\n\n{page}",
+        target.name, total, wip.unwrap_or_default(), lines.join("\n\n---\n\n")
+    )}]});
+    Ok(with_json_block(args, result, json_data))
+}
+
+pub(super) fn tool_query_task_counts(args: &Value) -> Result<Value, ToolError> {
+    let project_name = args
+        .get("project")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|v| !v.is_empty());
+
+    let projects = read_state();
+    let indices: Vec<usize> = if let Some(name) = project_name {
+        let Some(idx) = find_project_index(&projects, name) else {
+            return Err(ToolError::NotFound(format!("未找到匹配项目「{name}」。")));
+        };
+        vec![idx]
+    } else {
+        (0..projects.len()).collect()
+    };
+
+    let mut snapshot = TrayTaskSnapshot::default();
+    for idx in indices {
+        for task in &projects[idx].tasks {
+            snapshot.count(&task.status, task.needs_confirmation);
+        }
+    }
+
+    Ok(json!({ "content": [{ "type": "text", "text":
+        serde_json::to_string_pretty(&snapshot).unwrap_or_default()
+    }]}))
+}
+
+pub(super) fn tool_get_project_stats(args: &Value) -> Result<Value, ToolError> {
+    let project_name = args.get("project").and_then(|v| v.as_str()).unwrap_or("");
+
+    let projects = read_state();
+    let Some(idx) = find_project_index(&projects, project_name) else {
+        return Err(ToolError::NotFound(format!("未找到匹配项目「{project_name}」。")));
+    };
+
+    let target = &projects[idx];
+    let mut status_counts: BTreeMap<&str, usize> = TASK_STATUSES.iter().map(|status| (*status, 0)).collect();
+    let mut tags: HashSet<String> = HashSet::new();
+    let mut total_reports = 0usize;
+    let mut last_updated_at: Option<&str> = None;
+    for task in &target.tasks {
+        *status_counts.entry(task.status.as_str()).or_default() += 1;
+        tags.extend(task.tags.iter().map(|tag| normalize_tag_id(tag)).filter(|tag| !tag.is_empty()));
+        total_reports += task.reports.len();
+        if last_updated_at.is_none_or(|last| task.updated_at.as_str() > last) {
+            last_updated_at = Some(task.updated_at.as_str());
+        }
+    }
+
+    let mut lines: Vec<String> = vec![format!("项目「{}」— 共 {} 个任务", target.name, target.tasks.len())];
+    lines.extend(
+        TASK_STATUSES
+            .iter()
+            .map(|status| format!("- {status}：{}", status_counts.get(status).copied().unwrap_or(0))),
+    );
+    lines.push(format!("报告总数：{total_reports}"));
+    lines.push(format!("使用中的 Tag：{}", tags.len()));
+    lines.push(format!("最近更新：{}", last_updated_at.unwrap_or("（无）")));
+
+    let stats = json!({
+        "project": target.name,
+        "totalTasks": target.tasks.len(),
+        "statusCounts": status_counts,
+        "totalReports": total_reports,
+        "distinctTags": tags.len(),
+        "lastUpdatedAt": last_updated_at
+    });
+    Ok(json!({ "content": [
+        { "type": "text", "text": lines.join("\n") },
+        { "type": "text", "text": serde_json::to_string_pretty(&stats).unwrap_or_default() }
+    ]}))
+}
+
+const PROJECT_TREE_DEFAULT_DEPTH: usize = 3;
+
+const PROJECT_TREE_MAX_DEPTH: usize = 8;
+
+const PROJECT_TREE_DEFAULT_ENTRIES: usize = 200;
+
+const PROJECT_TREE_MAX_ENTRIES: usize = 2000;
+
+/// Directories listed in the tree but never descended into.
+const PROJECT_TREE_SKIPPED_DIRS: &[&str] = &[
+    ".git",
+    "node_modules",
+    "target",
+    "dist",
+    "build",
+    ".next",
+    ".venv",
+    "__pycache__",
+];
+
+/// Maps a project directory stored in either Windows (`C:\…`) or WSL
+/// (`/mnt/c/…`) form onto a path the current host can open.
+fn resolve_host_directory(directory: &str) -> PathBuf {
+    let trimmed = directory.trim();
+    if cfg!(windows) {
+        let normalized = trimmed.replace('\\', "/");
+        if let Some(rest) = normalized.strip_prefix("/mnt/") {
+            let mut parts = rest.splitn(2, '/');
+            let drive = parts.next().unwrap_or("");
+            if drive.len() == 1 && drive.chars().all(|ch| ch.is_ascii_alphabetic()) {
+                let tail = parts.next().unwrap_or("").replace('/', "\\");
+                return PathBuf::from(format!("{}:\\{tail}", drive.to_ascii_uppercase()));
+            }
+        }
+    } else {
+        let bytes = trimmed.as_bytes();
+        if bytes.len() >= 2 && bytes[1] == b':' && bytes[0].is_ascii_alphabetic() {
+            let tail = trimmed[2..].replace('\\', "/");
+            return PathBuf::from(format!(
+                "/mnt/{}/{}",
+                (bytes[0] as char).to_ascii_lowercase(),
+                tail.trim_start_matches('/')
+            ));
+        }
+    }
+    PathBuf::from(trimmed)
+}
+
+pub(super) fn tool_query_project_tree(args: &Value) -> Result<Value, ToolError> {
+    let project_name = args
+        .get("project")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let subpath = args
+        .get("path")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|v| !v.is_empty() && *v != ".");
+    let max_depth = args
+        .get("max_depth")
+        .and_then(|v| v.as_u64())
+        .map(|v| v as usize)
+        .unwrap_or(PROJECT_TREE_DEFAULT_DEPTH)
+        .clamp(1, PROJECT_TREE_MAX_DEPTH);
+    let max_entries = args
+        .get("max_entries")
+        .and_then(|v| v.as_u64())
+        .map(|v| v as usize)
+        .unwrap_or(PROJECT_TREE_DEFAULT_ENTRIES)
+        .clamp(1, PROJECT_TREE_MAX_ENTRIES);
+
+    let projects = read_state();
+    let Some(idx) = find_project_index(&projects, project_name) else {
+        return Err(ToolError::NotFound(format!("未找到匹配项目「{project_name}」。")));
+    };
+    let target = &projects[idx];
+    if target.directory.trim().is_empty() {
+        return Err(ToolError::Refused(format!("项目「{}」未配置目录。", target.name)));
+    }
+
+    let project_root = resolve_host_directory(&target.directory);
+    let mut root = project_root.clone();
+    if let Some(sub) = subpath {
+        let relative = PathBuf::from(sub.replace('\\', "/"));
+        let escapes = relative.components().any(|part| {
+            !matches!(part, std::path::Component::Normal(_) | std::path::Component::CurDir)
+        });
+        if escapes {
+            return Err(ToolError::InvalidArgs("path 必须是项目目录内的相对路径（不能包含 .. 或绝对路径）。".to_string()));
+        }
+        root = project_root.join(relative);
+    }
+    if !root.is_dir() {
+        return Err(ToolError::NotFound(format!("目录不存在或不可访问：{}", root.display())));
+    }
+
+    let walker = ignore::WalkBuilder::new(&root)
+        .max_depth(Some(max_depth))
+        .hidden(false)
+        .require_git(false)
+        .filter_entry(|entry| entry.depth() == 0 || !entry.file_name().eq_ignore_ascii_case(".git"))
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build();
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut truncated = false;
+    let mut skipped_depth: Option<usize> = None;
+    for entry in walker.flatten() {
+        let depth = entry.depth();
+        if depth == 0 {
+            continue;
+        }
+        if let Some(skip) = skipped_depth {
+            if depth > skip {
+                continue;
+            }
+            skipped_depth = None;
+        }
+        if lines.len() >= max_entries {
+            truncated = true;
+            break;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        let indent = "  ".repeat(depth - 1);
+        let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+        if is_dir && PROJECT_TREE_SKIPPED_DIRS.contains(&name.as_str()) {
+            lines.push(format!("{indent}{name}/ （已跳过）"));
+            skipped_depth = Some(depth);
+        } else if is_dir {
+            lines.push(format!("{indent}{name}/"));
+        } else {
+            lines.push(format!("{indent}{name}"));
+        }
+    }
+
+    let mut text = format!(
+        "项目「{}」目录树：{}（深度 ≤ {max_depth}，条目 ≤ {max_entries}，已遵循 .gitignore）\n",
+        target.name,
+        root.display()
+    );
+    if lines.is_empty() {
+        text.push_str("（空目录）");
+    } else {
+        text.push_str(&lines.join("\n"));
+    }
+    if truncated {
+        text.push_str(&format!(
+            "\n\n（已截断：仅显示前 {max_entries} 个条目，可缩小 path 或调整 max_depth / max_entries）"
+        ));
+    }
+
+    Ok(json!({ "content": [{ "type": "text", "text": text }] }))
+}
+
+pub(super) fn tool_set_wip_limit(args: &Value, state: &McpHttpState) -> Result<Value, ToolError> {
+    let project_name = args.get("project").and_then(|v| v.as_str()).unwrap_or("");
+    let limit = args
+        .get("limit")
+        .and_then(|v| v.as_u64())
+        .filter(|v| *v > 0)
+        .map(|v| v.min(u32::MAX as u64) as u32);
+
+    let mut projects = read_state();
+    let Some(idx) = find_project_index(&projects, project_name) else {
+        return Err(ToolError::NotFound(format!("未找到匹配项目「{project_name}」。")));
+    };
+
+    let target = &mut projects[idx];
+    target.wip_limit = limit;
+    let target_name = target.name.clone();
+    let in_progress = target.tasks.iter().filter(|t| t.status == "进行中").count();
+    let settings = ProjectSettingsUpdatedEvent {
+        project_name: target_name.clone(),
+        wip_limit: target.wip_limit,
+        disabled_tools: target.disabled_tools.clone(),
+    };
+    write_state(&projects);
+    let _ = state.app_handle.emit("maple://project-settings-updated", settings);
+
+    let text = match limit {
+        Some(limit) => format!("已将「{target_name}」的 WIP 上限设为 {limit}（当前进行中 {in_progress}）。"),
+        None => format!("已取消「{target_name}」的 WIP 上限。"),
+    };
+    Ok(json!({ "content": [{ "type": "text", "text": text }]}))
+}
@@ -0,0 +1,407 @@
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use tauri::Emitter;
+
+use crate::mcp_http::{
+    ensure_tag_catalog_for_tags, find_project_index, is_valid_mingcute_icon, iso_now,
+    merge_tag_definition, normalize_tag_id, read_state, write_state, McpHttpState,
+    TagCatalogUpdatedEvent, TagDefinition, Task, TaskUpdatedEvent,
+};
+use super::{with_json_block, ToolError};
+
+pub(super) fn tool_query_tag_catalog(args: &Value) -> Result<Value, ToolError> {
+    let name = args.get("project").and_then(|v| v.as_str()).unwrap_or("");
+    let projects = read_state();
+
+    let Some(idx) = find_project_index(&projects, name) else {
+        return Err(ToolError::NotFound(format!("未找到匹配项目「{name}」。")));
+    };
+
+    let target = &projects[idx];
+    let json_data = json!({ "project": target.name, "tagCatalog": target.tag_catalog });
+    if target.tag_catalog.is_empty() {
+        let result = json!({ "content": [{ "type": "text", "text":
+            format!("项目「{}」暂无 Tag Catalog。", target.name)
+        }]});
+        return Ok(with_json_block(args, result, json_data));
+    }
+
+    let mut lines: Vec<String> = Vec::new();
+    for (tag, def) in &target.tag_catalog {
+        let color = def
+            .color
+            .as_deref()
+            .unwrap_or("（未设置）");
+        let icon = def
+            .icon
+            .as_deref()
+            .unwrap_or("（未设置）");
+        let label_zh = def
+            .label
+            .as_ref()
+            .and_then(|label| label.zh.as_deref())
+            .unwrap_or("（未设置）");
+        let label_en = def
+            .label
+            .as_ref()
+            .and_then(|label| label.en.as_deref())
+            .unwrap_or("（未设置）");
+        lines.push(format!(
+            "- {}  color: {}  icon: {}  label.zh: {}  label.en: {}",
+            tag, color, icon, label_zh, label_en
+        ));
+    }
+
+    let result = json!({ "content": [{ "type": "text", "text": format!(
+        "项目「{}」Tag Catalog：\n{}",
+        target.name,
+        lines.join("\n")
+    )}]});
+    Ok(with_json_block(args, result, json_data))
+}
+
+pub(super) fn tool_upsert_tag_definition(args: &Value, state: &McpHttpState) -> Result<Value, ToolError> {
+    let project_name = args.get("project").and_then(|v| v.as_str()).unwrap_or("");
+    let tag_raw = args.get("tag").and_then(|v| v.as_str()).unwrap_or("");
+    let tag_id = normalize_tag_id(tag_raw);
+    if tag_id.is_empty() {
+        return Err(ToolError::InvalidArgs("tag 不能为空。".to_string()));
+    }
+
+    let color = args.get("color").and_then(|v| v.as_str()).map(|s| s.trim()).filter(|s| !s.is_empty());
+    let icon = args.get("icon").and_then(|v| v.as_str()).map(|s| s.trim()).filter(|s| !s.is_empty());
+    if let Some(i) = icon {
+        if !is_valid_mingcute_icon(i) {
+            return Err(ToolError::InvalidArgs("icon 必须是 Iconify 的 mingcute 图标（例如 mingcute:tag-line）。".to_string()));
+        }
+    }
+    let label_zh = args.get("label_zh").and_then(|v| v.as_str()).map(|s| s.trim()).filter(|s| !s.is_empty());
+    let label_en = args.get("label_en").and_then(|v| v.as_str()).map(|s| s.trim()).filter(|s| !s.is_empty());
+
+    let mut projects = read_state();
+    let Some(idx) = find_project_index(&projects, project_name) else {
+        return Err(ToolError::NotFound(format!("未找到匹配项目「{project_name}」。")));
+    };
+
+    let target = &mut projects[idx];
+    let target_name = target.name.clone();
+
+    let entry = target.tag_catalog.entry(tag_id.clone()).or_default();
+    if let Some(c) = color {
+        entry.color = Some(c.to_string());
+    }
+    if let Some(i) = icon {
+        entry.icon = Some(i.to_lowercase());
+    }
+    if label_zh.is_some() || label_en.is_some() {
+        let mut label = entry.label.clone().unwrap_or_default();
+        if let Some(zh) = label_zh {
+            label.zh = Some(zh.to_string());
+        }
+        if let Some(en) = label_en {
+            label.en = Some(en.to_string());
+        }
+        if label.zh.is_none() && label.en.is_none() {
+            entry.label = None;
+        } else {
+            entry.label = Some(label);
+        }
+    }
+
+    let catalog_snapshot = target.tag_catalog.clone();
+
+    write_state(&projects);
+    let _ = state.app_handle.emit(
+        "maple://tag-catalog-updated",
+        TagCatalogUpdatedEvent {
+            project_name: target_name.clone(),
+            tag_catalog: catalog_snapshot.clone(),
+        },
+    );
+
+    Ok(json!({ "content": [{ "type": "text", "text":
+        format!("已更新「{target_name}」Tag「{tag_id}」定义。")
+    }]}))
+}
+
+pub(super) fn tool_delete_tag_definition(args: &Value, state: &McpHttpState) -> Result<Value, ToolError> {
+    let project_name = args.get("project").and_then(|v| v.as_str()).unwrap_or("");
+    let tag_id = normalize_tag_id(args.get("tag").and_then(|v| v.as_str()).unwrap_or(""));
+    if tag_id.is_empty() {
+        return Err(ToolError::InvalidArgs("tag 不能为空。".to_string()));
+    }
+    let remove_from_tasks = args
+        .get("remove_from_tasks")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let mut projects = read_state();
+    let Some(idx) = find_project_index(&projects, project_name) else {
+        return Err(ToolError::NotFound(format!("未找到匹配项目「{project_name}」。")));
+    };
+
+    let target = &mut projects[idx];
+    let target_name = target.name.clone();
+    if target.tag_catalog.remove(&tag_id).is_none() {
+        return Err(ToolError::NotFound(format!("项目「{target_name}」的 Tag Catalog 中不存在「{tag_id}」。")));
+    }
+
+    let mut changed_tasks: Vec<Task> = Vec::new();
+    if remove_from_tasks {
+        let now = iso_now();
+        for task in target.tasks.iter_mut() {
+            let before = task.tags.len();
+            task.tags.retain(|tag| normalize_tag_id(tag) != tag_id);
+            if task.tags.len() != before {
+                task.updated_at = now.clone();
+                changed_tasks.push(task.clone());
+            }
+        }
+    }
+    let catalog_snapshot = target.tag_catalog.clone();
+
+    write_state(&projects);
+    for task in &changed_tasks {
+        let _ = state.app_handle.emit(
+            "maple://task-updated",
+            TaskUpdatedEvent {
+                project_name: target_name.clone(),
+                task: task.clone(),
+            },
+        );
+    }
+    let _ = state.app_handle.emit(
+        "maple://tag-catalog-updated",
+        TagCatalogUpdatedEvent {
+            project_name: target_name.clone(),
+            tag_catalog: catalog_snapshot,
+        },
+    );
+
+    let text = if remove_from_tasks {
+        format!(
+            "已删除「{target_name}」的 Tag「{tag_id}」定义，并从 {} 个任务中移除该 Tag。",
+            changed_tasks.len()
+        )
+    } else {
+        format!("已删除「{target_name}」的 Tag「{tag_id}」定义。")
+    };
+    Ok(json!({ "content": [{ "type": "text", "text": text }]}))
+}
+
+pub(super) fn tool_normalize_project_tags(args: &Value, state: &McpHttpState) -> Result<Value, ToolError> {
+    let project_name = args.get("project").and_then(|v| v.as_str()).unwrap_or("");
+
+    let mut projects = read_state();
+    let Some(idx) = find_project_index(&projects, project_name) else {
+        return Err(ToolError::NotFound(format!("未找到匹配项目「{project_name}」。")));
+    };
+
+    let target = &mut projects[idx];
+    let target_name = target.name.clone();
+    let now = iso_now();
+
+    let mut normalized_tags = 0usize;
+    let mut changed_tasks: Vec<Task> = Vec::new();
+    for task in target.tasks.iter_mut() {
+        let mut next: Vec<String> = Vec::new();
+        for tag in &task.tags {
+            let tag_id = normalize_tag_id(tag);
+            if tag_id.is_empty() || next.contains(&tag_id) {
+                normalized_tags += 1;
+                continue;
+            }
+            if &tag_id != tag {
+                normalized_tags += 1;
+            }
+            next.push(tag_id);
+        }
+        if next != task.tags {
+            task.tags = next;
+            task.updated_at = now.clone();
+            changed_tasks.push(task.clone());
+        }
+    }
+
+    let mut merged_definitions = 0usize;
+    let mut catalog: BTreeMap<String, TagDefinition> = BTreeMap::new();
+    let original = std::mem::take(&mut target.tag_catalog);
+    // Entries that are already normalized win over their variants.
+    let (canonical, variants): (Vec<_>, Vec<_>) = original
+        .into_iter()
+        .partition(|(tag, _)| normalize_tag_id(tag) == *tag);
+    for (tag, def) in canonical.into_iter().chain(variants) {
+        let tag_id = normalize_tag_id(&tag);
+        if tag_id.is_empty() {
+            merged_definitions += 1;
+            continue;
+        }
+        if tag_id != tag {
+            merged_definitions += 1;
+        }
+        match catalog.get_mut(&tag_id) {
+            Some(existing) => merge_tag_definition(existing, def),
+            None => {
+                catalog.insert(tag_id, def);
+            }
+        }
+    }
+    target.tag_catalog = catalog;
+    let catalog_snapshot = target.tag_catalog.clone();
+
+    if normalized_tags == 0 && merged_definitions == 0 {
+        return Ok(json!({ "content": [{ "type": "text", "text":
+            format!("项目「{target_name}」的 Tag 已是规范形式，无需调整。")
+        }]}));
+    }
+
+    write_state(&projects);
+    for task in &changed_tasks {
+        let _ = state.app_handle.emit(
+            "maple://task-updated",
+            TaskUpdatedEvent {
+                project_name: target_name.clone(),
+                task: task.clone(),
+            },
+        );
+    }
+    let _ = state.app_handle.emit(
+        "maple://tag-catalog-updated",
+        TagCatalogUpdatedEvent {
+            project_name: target_name.clone(),
+            tag_catalog: catalog_snapshot,
+        },
+    );
+
+    Ok(json!({ "content": [{ "type": "text", "text": format!(
+        "已规范化「{target_name}」的 Tag：{normalized_tags} 个任务 Tag 被归一化/去重（涉及 {} 个任务），{merged_definitions} 个 Catalog 定义被合并。",
+        changed_tasks.len()
+    )}]}))
+}
+
+pub(super) fn tool_rename_tag(args: &Value, state: &McpHttpState) -> Result<Value, ToolError> {
+    let project_name = args.get("project").and_then(|v| v.as_str()).unwrap_or("");
+    let from = normalize_tag_id(args.get("from").and_then(|v| v.as_str()).unwrap_or(""));
+    let to = normalize_tag_id(args.get("to").and_then(|v| v.as_str()).unwrap_or(""));
+    if from.is_empty() || to.is_empty() {
+        return Err(ToolError::InvalidArgs("from 和 to 都不能为空。".to_string()));
+    }
+
+    let mut projects = read_state();
+    let Some(idx) = find_project_index(&projects, project_name) else {
+        return Err(ToolError::NotFound(format!("未找到匹配项目「{project_name}」。")));
+    };
+
+    let target = &mut projects[idx];
+    let target_name = target.name.clone();
+    if from == to {
+        return Ok(json!({ "content": [{ "type": "text", "text":
+            format!("Tag「{from}」与目标名称相同，无需重命名。")
+        }]}));
+    }
+
+    let now = iso_now();
+    let mut changed_tasks: Vec<Task> = Vec::new();
+    for task in target.tasks.iter_mut() {
+        if !task.tags.iter().any(|tag| normalize_tag_id(tag) == from) {
+            continue;
+        }
+        let mut next: Vec<String> = Vec::new();
+        for tag in &task.tags {
+            let tag_id = if normalize_tag_id(tag) == from { to.clone() } else { tag.clone() };
+            if !next.contains(&tag_id) {
+                next.push(tag_id);
+            }
+        }
+        task.tags = next;
+        task.updated_at = now.clone();
+        changed_tasks.push(task.clone());
+    }
+
+    let moved_definition = match target.tag_catalog.remove(&from) {
+        Some(def) => {
+            match target.tag_catalog.get_mut(&to) {
+                Some(existing) => merge_tag_definition(existing, def),
+                None => {
+                    target.tag_catalog.insert(to.clone(), def);
+                }
+            }
+            true
+        }
+        None => false,
+    };
+
+    if changed_tasks.is_empty() && !moved_definition {
+        return Err(ToolError::NotFound(format!("项目「{target_name}」中未找到 Tag「{from}」。")));
+    }
+    let catalog_snapshot = target.tag_catalog.clone();
+
+    write_state(&projects);
+    for task in &changed_tasks {
+        let _ = state.app_handle.emit(
+            "maple://task-updated",
+            TaskUpdatedEvent {
+                project_name: target_name.clone(),
+                task: task.clone(),
+            },
+        );
+    }
+    let _ = state.app_handle.emit(
+        "maple://tag-catalog-updated",
+        TagCatalogUpdatedEvent {
+            project_name: target_name.clone(),
+            tag_catalog: catalog_snapshot,
+        },
+    );
+
+    Ok(json!({ "content": [{ "type": "text", "text": format!(
+        "已将「{target_name}」的 Tag「{from}」重命名为「{to}」：更新 {} 个任务{}。",
+        changed_tasks.len(),
+        if moved_definition { "，Catalog 定义已迁移" } else { "" }
+    )}]}))
+}
+
+pub(super) fn tool_regenerate_tag_catalog(args: &Value, state: &McpHttpState) -> Result<Value, ToolError> {
+    let project_name = args.get("project").and_then(|v| v.as_str()).unwrap_or("");
+
+    let mut projects = read_state();
+    let Some(idx) = find_project_index(&projects, project_name) else {
+        return Err(ToolError::NotFound(format!("未找到匹配项目「{project_name}」。")));
+    };
+
+    let target = &mut projects[idx];
+    let target_name = target.name.clone();
+
+    let mut tag_ids: Vec<String> = Vec::new();
+    for task in &target.tasks {
+        for tag in &task.tags {
+            let tag_id = normalize_tag_id(tag);
+            if !tag_id.is_empty() && !tag_ids.contains(&tag_id) {
+                tag_ids.push(tag_id);
+            }
+        }
+    }
+
+    let enriched = ensure_tag_catalog_for_tags(&mut target.tag_catalog, &tag_ids);
+    if enriched == 0 {
+        return Ok(json!({ "content": [{ "type": "text", "text": format!(
+            "项目「{target_name}」的 {} 个 Tag 定义均已完整，无需补全。",
+            tag_ids.len()
+        )}]}));
+    }
+    let catalog_snapshot = target.tag_catalog.clone();
+
+    write_state(&projects);
+    let _ = state.app_handle.emit(
+        "maple://tag-catalog-updated",
+        TagCatalogUpdatedEvent {
+            project_name: target_name.clone(),
+            tag_catalog: catalog_snapshot,
+        },
+    );
+
+    Ok(json!({ "content": [{ "type": "text", "text": format!(
+        "已重新生成「{target_name}」的 Tag Catalog：共 {} 个 Tag，补全 {enriched} 个定义。",
+        tag_ids.len()
+    )}]}))
+}
@@ -0,0 +1,448 @@
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use tauri::Emitter;
+
+use crate::mcp_http::{
+    build_report_history_lines, describe_blocker, find_project_index, format_elapsed_since,
+    iso_now, latest_execution_summary, read_state, rewrite_maple_asset_urls,
+    summarize_report_content, write_state, FileRef, McpHttpState, ReportHistoryOptions, Task,
+    TaskReport, TaskUpdatedEvent,
+};
+use super::assets::read_asset_base64_image;
+use super::blocknote::render_blocknote_markdown;
+use super::{with_json_block, ToolError};
+
+pub(super) fn tool_query_task_details(args: &Value) -> Result<Value, ToolError> {
+    let project_name = args
+        .get("project")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let task_id = args
+        .get("task_id")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+
+    let projects = read_state();
+    let Some(idx) = find_project_index(&projects, project_name) else {
+        return Err(ToolError::NotFound(format!("未找到匹配项目「{project_name}」。")));
+    };
+
+    let target = &projects[idx];
+    let Some(task) = target.tasks.iter().find(|t| t.id == task_id) else {
+        return Err(ToolError::NotFound(format!("项目「{}」中未找到任务 ID「{task_id}」。", target.name)));
+    };
+
+    let tags = if task.tags.is_empty() {
+        "（无）".to_string()
+    } else {
+        task.tags.join("、")
+    };
+    let details = task
+        .details_doc
+        .as_ref()
+        .and_then(render_blocknote_markdown)
+        .unwrap_or_else(|| task.details.trim().to_string());
+    let (details_text, assets) = if details.is_empty() {
+        ("（空）".to_string(), Vec::new())
+    } else {
+        rewrite_maple_asset_urls(&details)
+    };
+    let execution_summary = latest_execution_summary(&task.reports);
+    let report_lines = build_report_history_lines(&task.reports, ReportHistoryOptions::default());
+    let json_data = json!({
+        "project": target.name,
+        "executionSummary": execution_summary,
+        "detailsMarkdown": if details.is_empty() { "" } else { details_text.as_str() },
+        "task": task,
+    });
+
+    let mut lines: Vec<String> = vec![
+        format!("任务：{}  (id: {})", task.title, task.id),
+        format!("状态：{}", task.status),
+    ];
+    if let Some(summary) = execution_summary {
+        lines.push(format!("执行状态：{}", summary));
+    }
+    lines.extend([
+        format!("标签：{}", tags),
+        format!("更新时间：{}", task.updated_at),
+        String::new(),
+        "详情：".to_string(),
+        details_text,
+        String::new(),
+    ]);
+    if !task.file_refs.is_empty() {
+        lines.push("文件引用：".to_string());
+        lines.extend(task.file_refs.iter().map(|file_ref| match file_ref.label.as_deref() {
+            Some(label) => format!("- {label}: {}", file_ref.path),
+            None => format!("- {}", file_ref.path),
+        }));
+        lines.push(String::new());
+    }
+    lines.extend(report_lines);
+
+    let mut content: Vec<Value> = vec![json!({ "type": "text", "text": lines.join("\n") })];
+    for file_name in assets {
+        match read_asset_base64_image(&file_name) {
+            Ok((data, mime_type)) => {
+                content.push(json!({ "type": "text", "text": format!("图片：{file_name}") }));
+                content.push(json!({ "type": "image", "mimeType": mime_type, "data": data }));
+            }
+            Err(err) => {
+                content.push(json!({ "type": "text", "text": format!("图片读取失败：{file_name}（{err}）") }));
+            }
+        }
+    }
+
+    Ok(with_json_block(args, json!({ "content": content }), json_data))
+}
+
+fn task_graph_node(task: &Task) -> Value {
+    json!({ "id": task.id, "title": task.title, "status": task.status })
+}
+
+const TASK_SUMMARY_REPORTS: usize = 3;
+
+pub(super) fn tool_summarize_task(args: &Value) -> Result<Value, ToolError> {
+    let project_name = args
+        .get("project")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let task_id = args
+        .get("task_id")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+
+    let projects = read_state();
+    let Some(idx) = find_project_index(&projects, project_name) else {
+        return Err(ToolError::NotFound(format!("未找到匹配项目「{project_name}」。")));
+    };
+
+    let target = &projects[idx];
+    let Some(task) = target.tasks.iter().find(|t| t.id == task_id) else {
+        return Err(ToolError::NotFound(format!("项目「{}」中未找到任务 ID「{task_id}」。", target.name)));
+    };
+
+    let mut parts: Vec<String> = vec![format!(
+        "任务「{}」当前状态为「{}」，创建于 {} 前，最近更新于 {} 前",
+        task.title,
+        task.status,
+        format_elapsed_since(&task.created_at),
+        format_elapsed_since(&task.updated_at)
+    )];
+    if let Some(blocker) = task.blocker.as_ref() {
+        parts.push(format!("阻塞原因：{}", describe_blocker(blocker)));
+    }
+    if !task.tags.is_empty() {
+        parts.push(format!("Tag：{}", task.tags.join("、")));
+    }
+
+    let mut reports: Vec<&TaskReport> = task
+        .reports
+        .iter()
+        .filter(|report| !report.content.trim().is_empty())
+        .collect();
+    reports.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    if reports.is_empty() {
+        parts.push("暂无报告".to_string());
+    } else {
+        let recent: Vec<String> = reports
+            .iter()
+            .take(TASK_SUMMARY_REPORTS)
+            .map(|report| {
+                let author = if report.author.trim().is_empty() {
+                    "unknown"
+                } else {
+                    report.author.trim()
+                };
+                format!(
+                    "{author}（{} 前）：{}",
+                    format_elapsed_since(&report.created_at),
+                    summarize_report_content(&report.content, 160)
+                )
+            })
+            .collect();
+        parts.push(format!(
+            "共 {} 条报告，最近 {} 条——{}",
+            reports.len(),
+            recent.len(),
+            recent.join("；")
+        ));
+    }
+
+    Ok(json!({ "content": [{ "type": "text", "text": format!("{}。", parts.join("。")) }] }))
+}
+
+pub(super) fn tool_query_task_graph(args: &Value) -> Result<Value, ToolError> {
+    let project_name = args
+        .get("project")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let task_id = args
+        .get("task_id")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+
+    let projects = read_state();
+    let Some(idx) = find_project_index(&projects, project_name) else {
+        return Err(ToolError::NotFound(format!("未找到匹配项目「{project_name}」。")));
+    };
+
+    let target = &projects[idx];
+    let Some(task) = target.tasks.iter().find(|t| t.id == task_id) else {
+        return Err(ToolError::NotFound(format!("项目「{}」中未找到任务 ID「{task_id}」。", target.name)));
+    };
+
+    let find = |id: &str| target.tasks.iter().find(|t| t.id == id);
+    let parent = task.parent_id.as_deref().map(|id| match find(id) {
+        Some(parent) => task_graph_node(parent),
+        None => json!({ "id": id, "missing": true }),
+    });
+    let subtasks: Vec<Value> = target
+        .tasks
+        .iter()
+        .filter(|t| t.parent_id.as_deref() == Some(task.id.as_str()))
+        .map(task_graph_node)
+        .collect();
+    let depends_on: Vec<Value> = task
+        .depends_on
+        .iter()
+        .map(|id| match find(id) {
+            Some(dep) => task_graph_node(dep),
+            None => json!({ "id": id, "missing": true }),
+        })
+        .collect();
+    let dependents: Vec<Value> = target
+        .tasks
+        .iter()
+        .filter(|t| t.depends_on.iter().any(|id| id == &task.id))
+        .map(task_graph_node)
+        .collect();
+
+    let graph = json!({
+        "project": target.name,
+        "task": task_graph_node(task),
+        "parent": parent,
+        "subtasks": subtasks,
+        "dependsOn": depends_on,
+        "dependents": dependents
+    });
+
+    let text = serde_json::to_string_pretty(&graph)
+        .map_err(|e| ToolError::Internal(format!("序列化任务关系失败: {e}")))?;
+    Ok(json!({ "content": [{ "type": "text", "text": text }] }))
+}
+
+/// Appends `incoming` after a blank line, ignoring surrounding whitespace.
+fn append_details(current: &str, incoming: &str) -> String {
+    let incoming = incoming.trim();
+    let current = current.trim_end();
+    if incoming.is_empty() {
+        current.to_string()
+    } else if current.is_empty() {
+        incoming.to_string()
+    } else {
+        format!("{current}\n\n{incoming}")
+    }
+}
+
+pub(super) fn tool_append_task_detail(args: &Value, state: &McpHttpState) -> Result<Value, ToolError> {
+    let project_name = args.get("project").and_then(|v| v.as_str()).unwrap_or("");
+    let task_id = args.get("task_id").and_then(|v| v.as_str()).unwrap_or("");
+    let text = args.get("text").and_then(|v| v.as_str()).unwrap_or("");
+    if text.trim().is_empty() {
+        return Err(ToolError::InvalidArgs("缺少参数：text。".to_string()));
+    }
+
+    let mut projects = read_state();
+    let Some(idx) = find_project_index(&projects, project_name) else {
+        return Err(ToolError::NotFound(format!("未找到匹配项目「{project_name}」。")));
+    };
+
+    let target = &mut projects[idx];
+    let target_name = target.name.clone();
+    let Some(task) = target.tasks.iter_mut().find(|t| t.id == task_id) else {
+        return Err(ToolError::NotFound(format!("项目「{target_name}」中未找到任务 ID「{task_id}」。")));
+    };
+
+    task.details = append_details(&task.details, text);
+    // The rich-text document would no longer match the appended Markdown, so drop it.
+    let cleared_doc = task.details_doc.take().is_some();
+    task.updated_at = iso_now();
+    let task_title = task.title.clone();
+    let new_length = task.details.chars().count();
+    let task_snapshot = task.clone();
+
+    write_state(&projects);
+    let _ = state.app_handle.emit(
+        "maple://task-updated",
+        TaskUpdatedEvent {
+            project_name: target_name.clone(),
+            task: task_snapshot,
+        },
+    );
+
+    let mut lines = vec![format!(
+        "已向「{target_name}」任务「{task_title}」的详情追加内容，当前详情长度 {new_length} 字符。"
+    )];
+    if cleared_doc {
+        lines.push("注意：该任务原有的富文本详情（detailsDoc）已清除，详情以 Markdown 文本为准。".to_string());
+    }
+    Ok(json!({ "content": [{ "type": "text", "text": lines.join("\n") }]}))
+}
+
+pub(super) fn tool_update_task_details(args: &Value, state: &McpHttpState) -> Result<Value, ToolError> {
+    let project_name = args
+        .get("project")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let task_id = args
+        .get("task_id")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let details_value = args.get("details");
+    let details = details_value.and_then(|v| v.as_str()).unwrap_or("");
+    let mode = args
+        .get("mode")
+        .and_then(|v| v.as_str())
+        .unwrap_or("append")
+        .trim()
+        .to_lowercase();
+
+    if details_value.is_none() {
+        return Err(ToolError::InvalidArgs("缺少参数：details。".to_string()));
+    }
+
+    let mut projects = read_state();
+
+    let Some(idx) = find_project_index(&projects, project_name) else {
+        return Err(ToolError::NotFound(format!("未找到匹配项目「{project_name}」。")));
+    };
+
+    let target = &mut projects[idx];
+    let target_name = target.name.clone();
+
+    let Some(task_index) = target.tasks.iter().position(|t| t.id == task_id) else {
+        return Err(ToolError::NotFound(format!("项目「{target_name}」中未找到任务 ID「{task_id}」。")));
+    };
+
+    let now = iso_now();
+    let task_title = target.tasks[task_index].title.clone();
+
+    {
+        let task = &mut target.tasks[task_index];
+        let next_details = if mode == "replace" {
+            details.trim().to_string()
+        } else {
+            append_details(&task.details, details)
+        };
+
+        task.details = next_details;
+        task.details_doc = None;
+        task.updated_at = now;
+    }
+
+    let task_snapshot = target.tasks[task_index].clone();
+    write_state(&projects);
+    let _ = state.app_handle.emit(
+        "maple://task-updated",
+        TaskUpdatedEvent {
+            project_name: target_name.clone(),
+            task: task_snapshot,
+        },
+    );
+
+    Ok(json!({ "content": [{ "type": "text", "text":
+        format!("已更新「{target_name}」任务「{task_title}」的详情。")
+    }]}))
+}
+
+fn resolve_file_reference_path(project_dir: &str, raw: &str) -> Result<String, String> {
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        return Err("缺少参数：path。".to_string());
+    }
+    if trimmed.len() > 4096 || trimmed.chars().any(|ch| ch == '\0' || ch == '\n' || ch == '\r') {
+        return Err("path 不是合法的文件路径。".to_string());
+    }
+    if trimmed.contains("://") {
+        return Err("path 必须是本地文件路径（不支持 URL；图片请使用 asset）。".to_string());
+    }
+    let path = PathBuf::from(trimmed);
+    let bytes = trimmed.as_bytes();
+    let is_absolute = path.is_absolute()
+        || trimmed.starts_with('/')
+        || (bytes.len() >= 2 && bytes[1] == b':' && bytes[0].is_ascii_alphabetic());
+    if is_absolute {
+        return Ok(trimmed.to_string());
+    }
+    let base = project_dir.trim();
+    if base.is_empty() {
+        return Err("项目未配置目录，无法解析相对路径。".to_string());
+    }
+    Ok(PathBuf::from(base).join(trimmed).to_string_lossy().to_string())
+}
+
+pub(super) fn tool_attach_file_reference(args: &Value, state: &McpHttpState) -> Result<Value, ToolError> {
+    let project_name = args
+        .get("project")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let task_id = args
+        .get("task_id")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let raw_path = args.get("path").and_then(|v| v.as_str()).unwrap_or("");
+    let label = args
+        .get("label")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string);
+
+    let mut projects = read_state();
+    let Some(idx) = find_project_index(&projects, project_name) else {
+        return Err(ToolError::NotFound(format!("未找到匹配项目「{project_name}」。")));
+    };
+
+    let target = &mut projects[idx];
+    let target_name = target.name.clone();
+
+    let path = resolve_file_reference_path(&target.directory, raw_path).map_err(ToolError::InvalidArgs)?;
+
+    let Some(task_index) = target.tasks.iter().position(|t| t.id == task_id) else {
+        return Err(ToolError::NotFound(format!("项目「{target_name}」中未找到任务 ID「{task_id}」。")));
+    };
+
+    {
+        let task = &mut target.tasks[task_index];
+        match task.file_refs.iter_mut().find(|file_ref| file_ref.path == path) {
+            Some(existing) => {
+                if label.is_some() {
+                    existing.label = label;
+                }
+            }
+            None => task.file_refs.push(FileRef { path: path.clone(), label }),
+        }
+        task.updated_at = iso_now();
+    }
+
+    let task_snapshot = target.tasks[task_index].clone();
+    write_state(&projects);
+    let _ = state.app_handle.emit(
+        "maple://task-updated",
+        TaskUpdatedEvent {
+            project_name: target_name.clone(),
+            task: task_snapshot.clone(),
+        },
+    );
+
+    let note = if PathBuf::from(&path).exists() {
+        String::new()
+    } else {
+        "（注意：该路径当前不存在）".to_string()
+    };
+    Ok(json!({ "content": [{ "type": "text", "text":
+        format!("已为「{target_name}」任务「{}」关联文件：{path}{note}", task_snapshot.title)
+    }]}))
+}
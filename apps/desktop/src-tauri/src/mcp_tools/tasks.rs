@@ -0,0 +1,549 @@
+use serde_json::{json, Value};
+use tauri::Emitter;
+
+use crate::mcp_http::{
+    check_status_transition, check_wip_limit, ensure_tag_catalog_for_tags,
+    find_missing_tag_definitions, find_project_index, iso_now, new_task_id,
+    normalize_and_dedupe_tag_ids, normalize_tag_id, read_state, read_status_config, write_state,
+    McpHttpState, TagCatalogUpdatedEvent, Task, TaskRemovedEvent, TaskReport, TaskUpdatedEvent,
+    TasksUpdatedEvent, TASK_STATUSES,
+};
+use super::{with_json_block, ToolError};
+
+pub(super) fn tool_submit_task_report(args: &Value, state: &McpHttpState) -> Result<Value, ToolError> {
+    let project_name = args
+        .get("project")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let task_id = args
+        .get("task_id")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let status = args.get("status").and_then(|v| v.as_str());
+    let report_content = args.get("report").and_then(|v| v.as_str()).unwrap_or("");
+    let tags = normalize_and_dedupe_tag_ids(args, 5).map_err(ToolError::InvalidArgs)?;
+
+    let mut projects = read_state();
+
+    let Some(idx) = find_project_index(&projects, project_name) else {
+        return Err(ToolError::NotFound(format!("未找到匹配项目「{project_name}」。")));
+    };
+
+    let target = &mut projects[idx];
+    let target_name = target.name.clone();
+
+    let Some(task_index) = target.tasks.iter().position(|t| t.id == task_id) else {
+        return Err(ToolError::NotFound(format!("项目「{target_name}」中未找到任务 ID「{task_id}」。")));
+    };
+
+    let now = iso_now();
+    let task_title = target.tasks[task_index].title.clone();
+    let ts = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+
+    if let Some(next) = status {
+        let config = read_status_config();
+        check_status_transition(&config, &target.tasks[task_index].status, next)
+            .and_then(|_| check_wip_limit(target, Some(task_index), next))
+            .map_err(ToolError::Refused)?;
+    }
+
+    let missing = find_missing_tag_definitions(&target.tag_catalog, &tags);
+    if !missing.is_empty() {
+        return Err(ToolError::Refused(format!(
+            "以下 Tag 尚未在 Tag Catalog 中定义，禁止提交报告：{}\n请先为每个 Tag 调用 upsert_tag_definition（icon 必须为 mingcute:*，可选填写 label.zh / label.en）。",
+            missing.join("、")
+        )));
+    }
+
+    {
+        let task = &mut target.tasks[task_index];
+        task.reports.push(TaskReport {
+            id: format!("report-{ts}"),
+            author: "mcp".into(),
+            content: report_content.into(),
+            created_at: now.clone(),
+        });
+        task.updated_at = now;
+        if let Some(s) = status {
+            task.set_status(s);
+        }
+        task.tags = tags.clone();
+    }
+
+    let task_snapshot = target.tasks[task_index].clone();
+
+    write_state(&projects);
+    let _ = state.app_handle.emit(
+        "maple://task-updated",
+        TaskUpdatedEvent {
+            project_name: target_name.clone(),
+            task: task_snapshot,
+        },
+    );
+
+    let status_text = status
+        .map(|s| format!("状态已更新为「{s}」"))
+        .unwrap_or_else(|| "状态未变更".into());
+
+    Ok(json!({ "content": [{ "type": "text", "text":
+        format!("已提交报告至「{target_name}」任务「{task_title}」。{status_text}。")
+    }]}))
+}
+
+pub(super) fn tool_create_task(args: &Value, state: &McpHttpState) -> Result<Value, ToolError> {
+    let project_name = args
+        .get("project")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let title = args
+        .get("title")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .unwrap_or("");
+    let details = args
+        .get("details")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+    let status = args
+        .get("status")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .unwrap_or("待办");
+
+    if title.is_empty() {
+        return Err(ToolError::InvalidArgs("缺少参数：title。".to_string()));
+    }
+    if !TASK_STATUSES.contains(&status) {
+        return Err(ToolError::InvalidArgs(format!(
+            "无效状态「{status}」。可选：{}",
+            TASK_STATUSES.join("、")
+        )));
+    }
+    let has_tags = args
+        .get("tags")
+        .and_then(|v| v.as_array())
+        .is_some_and(|list| !list.is_empty());
+    let tags = if has_tags {
+        normalize_and_dedupe_tag_ids(args, 5).map_err(ToolError::InvalidArgs)?
+    } else {
+        Vec::new()
+    };
+
+    let mut projects = read_state();
+    let Some(idx) = find_project_index(&projects, project_name) else {
+        return Err(ToolError::NotFound(format!("未找到匹配项目「{project_name}」。")));
+    };
+
+    let target = &mut projects[idx];
+    let target_name = target.name.clone();
+    let now = iso_now();
+    let task = Task {
+        id: new_task_id(&target.tasks),
+        title: title.to_string(),
+        details,
+        details_doc: None,
+        status: status.to_string(),
+        target_worker_kind: None,
+        parent_id: None,
+        depends_on: Vec::new(),
+        external_ref: None,
+        file_refs: Vec::new(),
+        needs_confirmation: false,
+        blocker: None,
+        tags: tags.clone(),
+        created_at: now.clone(),
+        updated_at: now,
+        reports: Vec::new(),
+    };
+    check_wip_limit(target, None, status).map_err(ToolError::Refused)?;
+    target.tasks.push(task.clone());
+
+    let enriched = ensure_tag_catalog_for_tags(&mut target.tag_catalog, &tags);
+    let catalog_snapshot = target.tag_catalog.clone();
+
+    write_state(&projects);
+    let _ = state.app_handle.emit(
+        "maple://task-updated",
+        TaskUpdatedEvent {
+            project_name: target_name.clone(),
+            task: task.clone(),
+        },
+    );
+    if enriched > 0 {
+        let _ = state.app_handle.emit(
+            "maple://tag-catalog-updated",
+            TagCatalogUpdatedEvent {
+                project_name: target_name.clone(),
+                tag_catalog: catalog_snapshot,
+            },
+        );
+    }
+
+    Ok(json!({ "content": [{ "type": "text", "text":
+        format!("已在「{target_name}」中创建任务「{}」（状态：{status}），任务 ID：{}", task.title, task.id)
+    }]}))
+}
+
+pub(super) fn tool_set_task_status(args: &Value, state: &McpHttpState) -> Result<Value, ToolError> {
+    let project_name = args
+        .get("project")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let task_id = args
+        .get("task_id")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let status = args
+        .get("status")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .unwrap_or("");
+
+    if !TASK_STATUSES.contains(&status) {
+        return Err(ToolError::InvalidArgs(format!(
+            "无效状态「{status}」。可选：{}",
+            TASK_STATUSES.join("、")
+        )));
+    }
+
+    let mut projects = read_state();
+    let Some(idx) = find_project_index(&projects, project_name) else {
+        return Err(ToolError::NotFound(format!("未找到匹配项目「{project_name}」。")));
+    };
+
+    let target = &mut projects[idx];
+    let target_name = target.name.clone();
+
+    let Some(task_index) = target.tasks.iter().position(|t| t.id == task_id) else {
+        return Err(ToolError::NotFound(format!("项目「{target_name}」中未找到任务 ID「{task_id}」。")));
+    };
+
+    let previous = target.tasks[task_index].status.clone();
+    let config = read_status_config();
+    check_status_transition(&config, &previous, status)
+        .and_then(|_| check_wip_limit(target, Some(task_index), status))
+        .map_err(ToolError::Refused)?;
+
+    {
+        let task = &mut target.tasks[task_index];
+        task.set_status(status);
+        task.updated_at = iso_now();
+    }
+
+    let task_snapshot = target.tasks[task_index].clone();
+    write_state(&projects);
+    let _ = state.app_handle.emit(
+        "maple://task-updated",
+        TaskUpdatedEvent {
+            project_name: target_name.clone(),
+            task: task_snapshot.clone(),
+        },
+    );
+
+    Ok(json!({ "content": [{ "type": "text", "text":
+        format!("已将「{target_name}」任务「{}」状态从「{previous}」更新为「{status}」。", task_snapshot.title)
+    }]}))
+}
+
+pub(super) fn tool_update_task_statuses(args: &Value, state: &McpHttpState) -> Result<Value, ToolError> {
+    let project_name = args
+        .get("project")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let Some(updates) = args
+        .get("updates")
+        .and_then(|v| v.as_array())
+        .filter(|items| !items.is_empty())
+    else {
+        return Err(ToolError::InvalidArgs("缺少参数：updates（[{ task_id, status }]）。".to_string()));
+    };
+
+    let mut projects = read_state();
+    let Some(idx) = find_project_index(&projects, project_name) else {
+        return Err(ToolError::NotFound(format!("未找到匹配项目「{project_name}」。")));
+    };
+
+    let target = &mut projects[idx];
+    let target_name = target.name.clone();
+    let config = read_status_config();
+    let now = iso_now();
+    let mut updated: Vec<Task> = Vec::new();
+    let mut results: Vec<Value> = Vec::new();
+    let mut lines: Vec<String> = Vec::new();
+    for (position, update) in updates.iter().enumerate() {
+        let task_id = update
+            .get("task_id")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .unwrap_or("");
+        let status = update
+            .get("status")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .unwrap_or("");
+
+        // Each step sees the tasks already moved earlier in this batch, so the WIP limit holds.
+        let outcome = if !TASK_STATUSES.contains(&status) {
+            Err(format!("无效状态「{status}」。可选：{}", TASK_STATUSES.join("、")))
+        } else if let Some(task_index) = target.tasks.iter().position(|t| t.id == task_id) {
+            let previous = target.tasks[task_index].status.clone();
+            if previous == status {
+                Ok(format!("「{}」已是「{status}」，未变化", target.tasks[task_index].title))
+            } else {
+                check_status_transition(&config, &previous, status)
+                    .and_then(|_| check_wip_limit(target, Some(task_index), status))
+                    .map_err(|err| err.lines().next().unwrap_or_default().to_string())
+                    .map(|_| {
+                        let task = &mut target.tasks[task_index];
+                        task.set_status(status);
+                        task.updated_at = now.clone();
+                        updated.push(task.clone());
+                        format!("「{}」{previous} → {status}", task.title)
+                    })
+            }
+        } else {
+            Err(format!("未找到任务 ID「{task_id}」"))
+        };
+
+        match &outcome {
+            Ok(message) => lines.push(format!("{}. ✓ {message}", position + 1)),
+            Err(error) => lines.push(format!("{}. ✗ {task_id}：{error}", position + 1)),
+        }
+        results.push(json!({
+            "taskId": task_id,
+            "status": status,
+            "ok": outcome.is_ok(),
+            "error": outcome.err(),
+        }));
+    }
+
+    if !updated.is_empty() {
+        write_state(&projects);
+        for task in &updated {
+            let _ = state.app_handle.emit(
+                "maple://task-updated",
+                TaskUpdatedEvent {
+                    project_name: target_name.clone(),
+                    task: task.clone(),
+                },
+            );
+        }
+    }
+
+    let failed = results.iter().filter(|item| item["ok"] == false).count();
+    let summary = format!(
+        "项目「{target_name}」批量更新状态：{} 个已更新，{failed} 个失败（共 {} 项）。",
+        updated.len(),
+        results.len()
+    );
+    let result = json!({
+        "content": [{ "type": "text", "text": format!("{summary}\n{}", lines.join("\n")) }],
+        "isError": failed == results.len()
+    });
+    Ok(with_json_block(args, result, json!({ "project": target_name, "results": results })))
+}
+
+pub(super) fn tool_bulk_status_by_tag(args: &Value, state: &McpHttpState) -> Result<Value, ToolError> {
+    let project_name = args
+        .get("project")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let tag_id = normalize_tag_id(args.get("tag").and_then(|v| v.as_str()).unwrap_or(""));
+    let status = args
+        .get("status")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .unwrap_or("");
+    let report = args
+        .get("report")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|v| !v.is_empty());
+
+    if tag_id.is_empty() {
+        return Err(ToolError::InvalidArgs("缺少参数：tag。".to_string()));
+    }
+    if !TASK_STATUSES.contains(&status) {
+        return Err(ToolError::InvalidArgs(format!(
+            "无效状态「{status}」。可选：{}",
+            TASK_STATUSES.join("、")
+        )));
+    }
+
+    let mut projects = read_state();
+    let Some(idx) = find_project_index(&projects, project_name) else {
+        return Err(ToolError::NotFound(format!("未找到匹配项目「{project_name}」。")));
+    };
+
+    let target = &mut projects[idx];
+    let target_name = target.name.clone();
+    let matching: Vec<usize> = target
+        .tasks
+        .iter()
+        .enumerate()
+        .filter(|(_, task)| task.tags.iter().any(|tag| normalize_tag_id(tag) == tag_id))
+        .map(|(index, _)| index)
+        .collect();
+    if matching.is_empty() {
+        return Ok(json!({ "content": [{ "type": "text", "text":
+            format!("项目「{target_name}」中没有带 Tag「{tag_id}」的任务。")
+        }]}));
+    }
+
+    let config = read_status_config();
+    let now = iso_now();
+    let ts = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let mut updated: Vec<Task> = Vec::new();
+    let mut unchanged = 0usize;
+    let mut skipped: Vec<String> = Vec::new();
+    for task_index in matching {
+        let previous = target.tasks[task_index].status.clone();
+        if previous == status && report.is_none() {
+            unchanged += 1;
+            continue;
+        }
+        // Checked one by one so the WIP limit sees the tasks already moved in this batch.
+        if let Err(err) = check_status_transition(&config, &previous, status)
+            .and_then(|_| check_wip_limit(target, Some(task_index), status))
+        {
+            let reason = err.lines().next().unwrap_or_default().to_string();
+            let task = &target.tasks[task_index];
+            skipped.push(format!("- {}  (id: {})：{reason}", task.title, task.id));
+            continue;
+        }
+        let task = &mut target.tasks[task_index];
+        task.set_status(status);
+        task.updated_at = now.clone();
+        if let Some(content) = report {
+            task.reports.push(TaskReport {
+                id: format!("report-{ts}-{}", updated.len()),
+                author: "mcp".into(),
+                content: content.to_string(),
+                created_at: now.clone(),
+            });
+        }
+        updated.push(task.clone());
+    }
+
+    if !updated.is_empty() {
+        write_state(&projects);
+        let _ = state.app_handle.emit(
+            "maple://tasks-updated",
+            TasksUpdatedEvent {
+                project_name: target_name.clone(),
+                tasks: updated.clone(),
+            },
+        );
+    }
+
+    let mut lines = vec![format!(
+        "已将「{target_name}」中带 Tag「{tag_id}」的 {} 个任务更新为「{status}」（{unchanged} 个原本已是该状态）。",
+        updated.len()
+    )];
+    if !skipped.is_empty() {
+        lines.push(format!("以下 {} 个任务因状态规则被跳过：", skipped.len()));
+        lines.extend(skipped);
+    }
+    Ok(json!({ "content": [{ "type": "text", "text": lines.join("\n") }] }))
+}
+
+pub(super) fn tool_duplicate_task(args: &Value, state: &McpHttpState) -> Result<Value, ToolError> {
+    let project_name = args
+        .get("project")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let task_id = args
+        .get("task_id")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+
+    let mut projects = read_state();
+    let Some(idx) = find_project_index(&projects, project_name) else {
+        return Err(ToolError::NotFound(format!("未找到匹配项目「{project_name}」。")));
+    };
+
+    let target = &mut projects[idx];
+    let target_name = target.name.clone();
+
+    let Some(task_index) = target.tasks.iter().position(|t| t.id == task_id) else {
+        return Err(ToolError::NotFound(format!("项目「{target_name}」中未找到任务 ID「{task_id}」。")));
+    };
+
+    let now = iso_now();
+    let mut copy = target.tasks[task_index].clone();
+    copy.id = new_task_id(&target.tasks);
+    copy.title = format!("{} (copy)", copy.title);
+    copy.set_status("待办");
+    copy.reports = Vec::new();
+    copy.external_ref = None;
+    copy.created_at = now.clone();
+    copy.updated_at = now;
+
+    let new_id = copy.id.clone();
+    target.tasks.insert(task_index + 1, copy.clone());
+    write_state(&projects);
+    let _ = state.app_handle.emit(
+        "maple://task-updated",
+        TaskUpdatedEvent {
+            project_name: target_name.clone(),
+            task: copy,
+        },
+    );
+
+    Ok(json!({ "content": [{ "type": "text", "text":
+        format!("已在「{target_name}」中复制任务「{task_id}」，新任务 ID：{new_id}")
+    }]}))
+}
+
+pub(super) fn tool_delete_task(args: &Value, state: &McpHttpState) -> Result<Value, ToolError> {
+    let project_name = args
+        .get("project")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let task_id = args
+        .get("task_id")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let confirmed = args
+        .get("confirm")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    if !confirmed {
+        return Err(ToolError::InvalidArgs("删除任务不可恢复，请传入 confirm: true 以确认删除。".to_string()));
+    }
+
+    let mut projects = read_state();
+    let Some(idx) = find_project_index(&projects, project_name) else {
+        return Err(ToolError::NotFound(format!("未找到匹配项目「{project_name}」。")));
+    };
+
+    let target = &mut projects[idx];
+    let target_name = target.name.clone();
+
+    let Some(task_index) = target.tasks.iter().position(|t| t.id == task_id) else {
+        return Err(ToolError::NotFound(format!("项目「{target_name}」中未找到任务 ID「{task_id}」。")));
+    };
+
+    let removed = target.tasks.remove(task_index);
+    write_state(&projects);
+    let _ = state.app_handle.emit(
+        "maple://task-removed",
+        TaskRemovedEvent {
+            project_name: target_name.clone(),
+            task_id: removed.id.clone(),
+        },
+    );
+
+    Ok(json!({ "content": [{ "type": "text", "text":
+        format!("已从「{target_name}」中删除任务「{}」（id: {}）。", removed.title, removed.id)
+    }]}))
+}
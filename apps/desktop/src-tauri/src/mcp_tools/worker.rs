@@ -0,0 +1,129 @@
+use serde_json::{json, Value};
+use std::fs;
+use tauri::Emitter;
+
+use crate::maple_fs;
+use crate::mcp_http::{
+    describe_blocker, find_project_index, is_terminal_task_status, iso_now, read_state, state_dir,
+    McpHttpState, Task, WorkerFinishedEvent,
+};
+use super::ToolError;
+
+pub(super) fn tool_finish_worker(args: &Value, state: &McpHttpState) -> Result<Value, ToolError> {
+    let project_name = args
+        .get("project")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let worker_kind = args
+        .get("worker_kind")
+        .and_then(|v| v.as_str())
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .map(|v| v.to_lowercase());
+    let summary = args
+        .get("summary")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let force = args.get("force").and_then(|v| v.as_bool()).unwrap_or(false);
+
+    let projects = read_state();
+    let Some(idx) = find_project_index(&projects, project_name) else {
+        return Err(ToolError::NotFound(format!("未找到匹配项目「{project_name}」。")));
+    };
+
+    let target = &projects[idx];
+    let unresolved_tasks: Vec<&Task> = target
+        .tasks
+        .iter()
+        .filter(|task| !is_terminal_task_status(&task.status))
+        .filter(|task| match worker_kind.as_deref() {
+            None => true,
+            Some(kind) => {
+                if let Some(target_kind) = task.target_worker_kind.as_deref() {
+                    target_kind.trim().eq_ignore_ascii_case(kind)
+                } else if let Some(default_kind) = target.worker_kind.as_deref() {
+                    default_kind.trim().eq_ignore_ascii_case(kind)
+                } else {
+                    true
+                }
+            }
+        })
+        .collect();
+
+    let unresolved_lines: Vec<String> = unresolved_tasks
+        .iter()
+        .enumerate()
+        .map(|(index, task)| {
+            let blocker = task
+                .blocker
+                .as_ref()
+                .map(|blocker| format!("  阻塞原因：{}", describe_blocker(blocker)))
+                .unwrap_or_default();
+            format!(
+                "{}. [{}] {}  (id: {}){}",
+                index + 1,
+                task.status,
+                task.title,
+                task.id,
+                blocker
+            )
+        })
+        .collect();
+
+    if !unresolved_tasks.is_empty() && !force {
+        let mut lines: Vec<String> = vec![
+            format!(
+                "项目「{}」仍有 {} 个任务未收敛，禁止 finish_worker。",
+                target.name,
+                unresolved_tasks.len()
+            ),
+            "请先对每条任务调用 submit_task_report，将状态更新为：草稿 / 已完成 / 已阻塞 / 需要更多信息。".into(),
+            "如确需保留这些任务并结束本轮执行，可传入 force: true。".into(),
+            String::new(),
+        ];
+        lines.extend(unresolved_lines);
+        return Err(ToolError::Refused(lines.join("\n")));
+    }
+    let forced = force && !unresolved_tasks.is_empty();
+
+    let dir = state_dir();
+    let _ = fs::create_dir_all(&dir);
+    let signal_path = dir.join("worker-signal.json");
+    // Continue from the previous signal so consumers can tell a new signal from a re-read.
+    let sequence = fs::read_to_string(&signal_path)
+        .ok()
+        .and_then(|raw| serde_json::from_str::<Value>(&raw).ok())
+        .and_then(|previous| previous.get("sequence").and_then(|v| v.as_u64()))
+        .unwrap_or(0)
+        + 1;
+    let signal = json!({
+        "sequence": sequence,
+        "project": target.name,
+        "workerKind": worker_kind,
+        "summary": summary,
+        "timestamp": iso_now(),
+        "action": "finish",
+        "forced": forced,
+        "unresolvedTaskIds": unresolved_tasks.iter().map(|task| task.id.as_str()).collect::<Vec<_>>()
+    });
+    if let Err(e) = maple_fs::write_file_atomic(
+        &signal_path,
+        serde_json::to_string_pretty(&signal).unwrap_or_default().as_bytes(),
+    ) {
+        eprintln!("Failed to write Maple worker signal: {e}");
+    }
+    let _ = state.app_handle.emit(
+        "maple://worker-finished",
+        WorkerFinishedEvent {
+            project: target.name.clone(),
+            summary: summary.to_string(),
+        },
+    );
+
+    let mut lines = vec![format!("已通知 Maple 项目「{}」的 Worker 执行完毕。", target.name)];
+    if forced {
+        lines.push(format!("已强制结束，仍有 {} 个任务未收敛：", unresolved_tasks.len()));
+        lines.extend(unresolved_lines);
+    }
+    Ok(json!({ "content": [{ "type": "text", "text": lines.join("\n") }]}))
+}
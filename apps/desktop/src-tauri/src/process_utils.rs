@@ -1,3 +1,4 @@
+use std::io::Write;
 use std::process::Command;
 
 #[cfg(target_os = "windows")]
@@ -124,7 +125,18 @@ pub fn apply_no_window(command: &mut Command) {
   }
 }
 
+/// Sends SIGTERM to the tree on Unix so each process can still clean up; Windows
+/// has no such signal and uses a forced `taskkill`.
 pub fn kill_process_tree(pid: u32) {
+  #[cfg(target_os = "windows")]
+  force_kill_process_tree(pid);
+
+  #[cfg(not(target_os = "windows"))]
+  signal_pids(&process_tree(pid), "TERM");
+}
+
+/// Last resort for `terminate_process_tree` once the grace period has run out.
+fn force_kill_process_tree(pid: u32) {
   #[cfg(target_os = "windows")]
   {
     let mut command = Command::new("taskkill");
@@ -134,8 +146,119 @@ pub fn kill_process_tree(pid: u32) {
   }
 
   #[cfg(not(target_os = "windows"))]
+  signal_pids(&process_tree(pid), "KILL");
+}
+
+/// `pid` followed by all of its descendants, parents before children.
+#[cfg(not(target_os = "windows"))]
+fn process_tree(pid: u32) -> Vec<u32> {
+  let mut tree = vec![pid];
+  let Ok(output) = Command::new("ps").args(["-A", "-o", "pid=,ppid="]).output() else {
+    return tree;
+  };
+  let table: Vec<(u32, u32)> = String::from_utf8_lossy(&output.stdout)
+    .lines()
+    .filter_map(|line| {
+      let mut parts = line.split_whitespace();
+      Some((parts.next()?.parse().ok()?, parts.next()?.parse().ok()?))
+    })
+    .collect();
+  let mut index = 0;
+  while index < tree.len() {
+    let parent = tree[index];
+    for &(child, ppid) in &table {
+      if ppid == parent && !tree.contains(&child) {
+        tree.push(child);
+      }
+    }
+    index += 1;
+  }
+  tree
+}
+
+/// A worker leads the session of its own pseudo terminal and its helpers may
+/// move to other process groups, so signals go to each process of the tree
+/// instead of a group, deepest first.
+#[cfg(not(target_os = "windows"))]
+fn signal_pids(pids: &[u32], signal: &str) {
+  let pids: Vec<String> = pids.iter().rev().map(u32::to_string).collect();
+  let _ = Command::new("kill").arg(format!("-{signal}")).args(&pids).output();
+}
+
+pub fn is_process_alive(pid: u32) -> bool {
+  #[cfg(target_os = "windows")]
   {
-    let _ = Command::new("kill").arg("-TERM").arg(pid.to_string()).output();
+    let mut command = Command::new("tasklist");
+    command
+      .arg("/FI")
+      .arg(format!("PID eq {pid}"))
+      .arg("/FO")
+      .arg("CSV")
+      .arg("/NH");
+    apply_no_window(&mut command);
+    command
+      .output()
+      .map(|output| String::from_utf8_lossy(&output.stdout).contains(&format!("\"{pid}\"")))
+      .unwrap_or(false)
+  }
+
+  // A killed process stays a zombie until its parent reaps it, which `kill -0`
+  // would still report as alive.
+  #[cfg(not(target_os = "windows"))]
+  {
+    Command::new("ps")
+      .args(["-o", "stat=", "-p"])
+      .arg(pid.to_string())
+      .output()
+      .map(|output| {
+        output.status.success() && !String::from_utf8_lossy(&output.stdout).trim_start().starts_with('Z')
+      })
+      .unwrap_or(false)
+  }
+}
+
+/// Asks the worker to stop so CLIs can flush their session files, then force-kills
+/// whatever is still alive after `grace`. Blocks up to `grace`.
+///
+/// Unix sends SIGINT to the whole tree. Windows console processes ignore a
+/// non-forced `taskkill`, so Ctrl+C is written to `pty_input` (the ConPTY writer);
+/// without one there is no graceful path and the tree is killed straight away.
+pub fn terminate_process_tree(
+  pid: u32,
+  grace: std::time::Duration,
+  pty_input: Option<Box<dyn Write + Send>>,
+) {
+  #[cfg(target_os = "windows")]
+  let watched = {
+    let interrupted =
+      pty_input.is_some_and(|mut input| input.write_all(b"\x03").and_then(|_| input.flush()).is_ok());
+    if !interrupted {
+      force_kill_process_tree(pid);
+      return;
+    }
+    vec![pid]
+  };
+
+  // The whole tree is watched in case a helper outlives the CLI.
+  #[cfg(not(target_os = "windows"))]
+  let watched = {
+    let _ = pty_input;
+    let tree = process_tree(pid);
+    signal_pids(&tree, "INT");
+    tree
+  };
+
+  let deadline = std::time::Instant::now() + grace;
+  while std::time::Instant::now() < deadline {
+    if !watched.iter().any(|&pid| is_process_alive(pid)) {
+      return;
+    }
+    std::thread::sleep(std::time::Duration::from_millis(100));
+  }
+  for pid in watched {
+    if is_process_alive(pid) {
+      force_kill_process_tree(pid);
+    }
   }
 }
 
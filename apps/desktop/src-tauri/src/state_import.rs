@@ -0,0 +1,359 @@
+use base64::Engine;
+use chrono::Utc;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::fs;
+use tauri::Emitter;
+
+use crate::maple_fs;
+use crate::mcp_http::{
+    emit_state_reloaded, ensure_tag_catalog_for_tags, find_project_index, iso_now, lock_state,
+    new_task_id, normalize_tag_id, read_state, state_dir, write_state, write_state_json_locked,
+    ExternalRef, Project, TagCatalogUpdatedEvent, Task, TaskUpdatedEvent, STATE_CACHE,
+};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GithubImportReport {
+    pub project: String,
+    pub created: usize,
+    pub updated: usize,
+    pub skipped: usize,
+}
+
+fn github_issue_labels(issue: &Value) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for label in issue.get("labels").and_then(|v| v.as_array()).into_iter().flatten() {
+        let name = label
+            .as_str()
+            .or_else(|| label.get("name").and_then(|v| v.as_str()))
+            .unwrap_or("");
+        let tag_id = normalize_tag_id(name);
+        if !tag_id.is_empty() && !tags.contains(&tag_id) {
+            tags.push(tag_id);
+        }
+    }
+    tags
+}
+
+pub fn import_github_issues(
+    app_handle: &tauri::AppHandle,
+    project: &str,
+    issues_json: &str,
+) -> Result<GithubImportReport, String> {
+    let issues: Vec<Value> =
+        serde_json::from_str(issues_json).map_err(|e| format!("解析 GitHub issues JSON 失败: {e}"))?;
+
+    let _state_guard = lock_state();
+    let mut projects = read_state();
+    let idx = find_project_index(&projects, project).ok_or_else(|| format!("未找到匹配项目「{project}」。"))?;
+    let target = &mut projects[idx];
+    let target_name = target.name.clone();
+    let now = iso_now();
+
+    let mut report = GithubImportReport {
+        project: target_name.clone(),
+        created: 0,
+        updated: 0,
+        skipped: 0,
+    };
+    let mut changed: Vec<Task> = Vec::new();
+    let mut enriched = 0usize;
+
+    for issue in &issues {
+        // The issues API also lists pull requests; those are not tasks.
+        if issue.get("pull_request").is_some() {
+            report.skipped += 1;
+            continue;
+        }
+        let Some(number) = issue.get("number").and_then(|v| v.as_u64()) else {
+            report.skipped += 1;
+            continue;
+        };
+        let url = issue
+            .get("html_url")
+            .and_then(|v| v.as_str())
+            .map(str::to_string);
+        let external_ref = ExternalRef {
+            source: "github".to_string(),
+            id: url.clone().unwrap_or_else(|| number.to_string()),
+            url,
+        };
+        let title = issue
+            .get("title")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .trim()
+            .to_string();
+        let details = issue
+            .get("body")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .trim()
+            .to_string();
+        let tags = github_issue_labels(issue);
+        let closed = issue.get("state").and_then(|v| v.as_str()) == Some("closed");
+        // Labels become tags, so they need catalog entries just like create_task's.
+        enriched += ensure_tag_catalog_for_tags(&mut target.tag_catalog, &tags);
+
+        let existing = target.tasks.iter_mut().find(|task| {
+            task.external_ref
+                .as_ref()
+                .is_some_and(|r| r.source == external_ref.source && r.id == external_ref.id)
+        });
+
+        match existing {
+            Some(task) => {
+                // Only follow GitHub's open/closed transitions so local progress is kept.
+                let next_status = if closed {
+                    "已完成".to_string()
+                } else if task.status == "已完成" {
+                    "待办".to_string()
+                } else {
+                    task.status.clone()
+                };
+                if task.title == title
+                    && task.details == details
+                    && task.tags == tags
+                    && task.status == next_status
+                    && task.external_ref.as_ref() == Some(&external_ref)
+                {
+                    report.skipped += 1;
+                    continue;
+                }
+                if task.details != details {
+                    task.details_doc = None;
+                }
+                task.title = title;
+                task.details = details;
+                task.tags = tags;
+                task.set_status(&next_status);
+                task.external_ref = Some(external_ref);
+                task.updated_at = now.clone();
+                changed.push(task.clone());
+                report.updated += 1;
+            }
+            None => {
+                let task = Task {
+                    id: new_task_id(&target.tasks),
+                    title,
+                    details,
+                    details_doc: None,
+                    status: if closed { "已完成" } else { "待办" }.to_string(),
+                    target_worker_kind: None,
+                    parent_id: None,
+                    depends_on: Vec::new(),
+                    external_ref: Some(external_ref),
+                    file_refs: Vec::new(),
+                    needs_confirmation: false,
+                    blocker: None,
+                    tags,
+                    created_at: now.clone(),
+                    updated_at: now.clone(),
+                    reports: Vec::new(),
+                };
+                changed.push(task.clone());
+                target.tasks.push(task);
+                report.created += 1;
+            }
+        }
+    }
+
+    let catalog_snapshot = (enriched > 0).then(|| target.tag_catalog.clone());
+    if !changed.is_empty() || catalog_snapshot.is_some() {
+        write_state(&projects);
+        for task in changed {
+            let _ = app_handle.emit(
+                "maple://task-updated",
+                TaskUpdatedEvent {
+                    project_name: target_name.clone(),
+                    task,
+                },
+            );
+        }
+    }
+    if let Some(tag_catalog) = catalog_snapshot {
+        let _ = app_handle.emit(
+            "maple://tag-catalog-updated",
+            TagCatalogUpdatedEvent {
+                project_name: target_name.clone(),
+                tag_catalog,
+            },
+        );
+    }
+
+    Ok(report)
+}
+
+pub const PROJECT_BUNDLE_FORMAT: &str = "maple-project";
+
+pub const PROJECT_BUNDLE_VERSION: u64 = 1;
+
+/// Asset names (`<sha256>.<ext>`) mentioned anywhere in `value`, whatever URI
+/// scheme or markup wraps them.
+fn referenced_asset_names(value: &Value) -> Vec<String> {
+    let text = value.to_string();
+    let bytes = text.as_bytes();
+    let mut names: Vec<String> = Vec::new();
+    let mut start = 0;
+    while start + 64 < bytes.len() {
+        let is_hex = |b: &u8| matches!(b, b'0'..=b'9' | b'a'..=b'f');
+        if !is_hex(&bytes[start]) || (start > 0 && is_hex(&bytes[start - 1])) {
+            start += 1;
+            continue;
+        }
+        let hex_len = bytes[start..].iter().take_while(|b| is_hex(b)).count();
+        if hex_len == 64 && bytes.get(start + 64) == Some(&b'.') {
+            let ext_len = bytes[start + 65..]
+                .iter()
+                .take_while(|b| b.is_ascii_alphanumeric())
+                .count();
+            let name = &text[start..start + 65 + ext_len];
+            if maple_fs::is_valid_asset_file_name(name) && !names.iter().any(|n| n == name) {
+                names.push(name.to_string());
+            }
+        }
+        start += hex_len.max(1);
+    }
+    names
+}
+
+fn read_state_document() -> Result<Vec<Value>, String> {
+    let path = state_dir().join("state.json");
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let raw = fs::read_to_string(&path).map_err(|e| format!("读取状态文件失败: {e}"))?;
+    match serde_json::from_str(&raw).map_err(|e| format!("当前状态文件不是合法 JSON: {e}"))? {
+        Value::Array(projects) => Ok(projects),
+        _ => Err("当前状态文件不是项目数组。".to_string()),
+    }
+}
+
+/// Serializes one project plus every asset it references (base64) into a
+/// self-contained bundle. Missing asset files are skipped.
+pub fn export_project(name: &str) -> Result<String, String> {
+    let projects = read_state();
+    let idx = find_project_index(&projects, name).ok_or_else(|| format!("未找到项目「{name}」。"))?;
+    let raw = read_state_document()?
+        .into_iter()
+        .find(|value| value.get("id").and_then(|v| v.as_str()) == Some(projects[idx].id.as_str()))
+        .ok_or_else(|| format!("未找到项目「{name}」。"))?;
+
+    let dir = maple_fs::asset_dir()?;
+    let mut assets = serde_json::Map::new();
+    for file_name in referenced_asset_names(&raw) {
+        if let Ok(bytes) = fs::read(dir.join(&file_name)) {
+            assets.insert(
+                file_name,
+                Value::String(base64::engine::general_purpose::STANDARD.encode(bytes)),
+            );
+        }
+    }
+
+    serde_json::to_string_pretty(&json!({
+        "format": PROJECT_BUNDLE_FORMAT,
+        "version": PROJECT_BUNDLE_VERSION,
+        "exportedAt": Utc::now().to_rfc3339(),
+        "project": raw,
+        "assets": assets,
+    }))
+    .map_err(|e| format!("序列化项目失败: {e}"))
+}
+
+/// Parses a bundle's project without touching the state, so callers can reject a
+/// bad bundle before writing any of its assets.
+pub fn validate_project(project: &Value) -> Result<Project, String> {
+    if !project.is_object() {
+        return Err("项目数据必须是对象。".to_string());
+    }
+    serde_json::from_value(project.clone()).map_err(|e| format!("项目数据不合法，已拒绝: {e}"))
+}
+
+/// Adds an exported project to the state. On a name clash the project is
+/// either renamed ("名称 (2)", with a fresh id) or merged: tasks and tag
+/// definitions that the existing project lacks are appended. Returns the
+/// resulting project name.
+pub fn import_project(
+    app_handle: &tauri::AppHandle,
+    project: Value,
+    rename_on_conflict: bool,
+) -> Result<String, String> {
+    let mut incoming = validate_project(&project)?;
+    let Value::Object(mut incoming_raw) = project else {
+        return Err("项目数据必须是对象。".to_string());
+    };
+
+    let _state_guard = lock_state();
+    let mut cache = STATE_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    let mut doc = read_state_document()?;
+    let mut projects: Vec<Project> =
+        serde_json::from_value(Value::Array(doc.clone())).map_err(|e| format!("当前状态不是合法的项目列表: {e}"))?;
+
+    let clash = projects
+        .iter()
+        .position(|p| p.name.trim().to_lowercase() == incoming.name.trim().to_lowercase());
+    let result_name = match clash {
+        Some(idx) if !rename_on_conflict => {
+            let existing = &mut projects[idx];
+            let existing_raw = &mut doc[idx];
+            let mut task_ids: HashSet<String> = existing.tasks.iter().map(|t| t.id.clone()).collect();
+            let raw_tasks = incoming_raw
+                .get("tasks")
+                .and_then(|v| v.as_array())
+                .cloned()
+                .unwrap_or_default();
+            for (task, raw_task) in incoming.tasks.into_iter().zip(raw_tasks) {
+                if task_ids.insert(task.id.clone()) {
+                    if let Some(tasks) = existing_raw.get_mut("tasks").and_then(|v| v.as_array_mut()) {
+                        tasks.push(raw_task);
+                    }
+                    existing.tasks.push(task);
+                }
+            }
+            if let Some(Value::Object(tags)) = incoming_raw.get("tagCatalog") {
+                let catalog = existing_raw
+                    .as_object_mut()
+                    .map(|obj| obj.entry("tagCatalog").or_insert_with(|| json!({})));
+                if let Some(Value::Object(catalog)) = catalog {
+                    for (key, definition) in tags {
+                        catalog.entry(key.clone()).or_insert_with(|| definition.clone());
+                    }
+                }
+            }
+            existing.name.clone()
+        }
+        clash => {
+            if clash.is_some() {
+                let base = incoming.name.trim().to_string();
+                let mut suffix = 2;
+                while projects.iter().any(|p| p.name.trim().eq_ignore_ascii_case(&format!("{base} ({suffix})"))) {
+                    suffix += 1;
+                }
+                incoming.name = format!("{base} ({suffix})");
+                incoming_raw.insert("name".to_string(), Value::String(incoming.name.clone()));
+            }
+            if incoming.id.trim().is_empty() || projects.iter().any(|p| p.id == incoming.id) {
+                let mut id = new_task_id(&[]);
+                while projects.iter().any(|p| p.id == id) {
+                    id = new_task_id(&[]);
+                }
+                incoming.id = id;
+                incoming_raw.insert("id".to_string(), Value::String(incoming.id.clone()));
+            }
+            let name = incoming.name.clone();
+            doc.push(Value::Object(incoming_raw));
+            projects.push(incoming);
+            name
+        }
+    };
+
+    let project_count = projects.len();
+    let json = serde_json::to_string_pretty(&doc).map_err(|e| format!("序列化状态失败: {e}"))?;
+    write_state_json_locked(&mut cache, &json, projects)?;
+    drop(cache);
+
+    emit_state_reloaded(app_handle, "import", project_count);
+    Ok(result_name)
+}
@@ -0,0 +1,98 @@
+use serde_json::Value;
+use std::fs;
+
+use crate::mcp_http::{lock_state, state_dir, write_state_json_locked, Project, STATE_CACHE};
+
+/// Project index addressed by the first segment of an op's `path`/`from` pointer in a
+/// state of `len` projects. `-` is the append position, which RFC 6902 only allows
+/// as the `path` of an `add`.
+fn patch_target_index(op: &str, key: &str, pointer: &str, len: usize) -> Result<Option<usize>, String> {
+    let Some(first) = pointer.strip_prefix('/').and_then(|rest| rest.split('/').next()) else {
+        return Ok(None);
+    };
+    if first == "-" {
+        return if op == "add" && key == "path" {
+            Ok(Some(len))
+        } else {
+            Err(format!("JSON Patch 的 {op} 操作不能以「-」作为 {key}：{pointer}"))
+        };
+    }
+    Ok(first.parse::<usize>().ok())
+}
+
+/// Applies an RFC 6902 patch to the raw state document and returns the names of touched projects.
+pub fn apply_state_patch(patch_json: &str) -> Result<Vec<String>, String> {
+    let patch: json_patch::Patch =
+        serde_json::from_str(patch_json).map_err(|e| format!("解析 JSON Patch 失败: {e}"))?;
+
+    let _state_guard = lock_state();
+    let mut cache = STATE_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    let path = state_dir().join("state.json");
+    let raw = if path.exists() {
+        fs::read_to_string(&path).map_err(|e| format!("读取状态文件失败: {e}"))?
+    } else {
+        "[]".to_string()
+    };
+    let mut doc: Value = serde_json::from_str(&raw).map_err(|e| format!("当前状态文件不是合法 JSON: {e}"))?;
+    let before: Vec<Project> = serde_json::from_value(doc.clone()).unwrap_or_default();
+
+    // An empty pointer addresses the whole document, touching every project.
+    let mut touches_all = false;
+    let mut indices: Vec<usize> = Vec::new();
+    for op in &patch.0 {
+        let value = serde_json::to_value(op).unwrap_or(Value::Null);
+        let op_name = value.get("op").and_then(|v| v.as_str()).unwrap_or("");
+        for key in ["path", "from"] {
+            match value.get(key).and_then(|v| v.as_str()) {
+                Some("") => touches_all = true,
+                Some(pointer) => indices.extend(patch_target_index(op_name, key, pointer, before.len())?),
+                None => {}
+            }
+        }
+    }
+
+    json_patch::patch(&mut doc, &patch.0).map_err(|e| format!("应用 JSON Patch 失败: {e}"))?;
+    let after: Vec<Project> = serde_json::from_value(doc.clone())
+        .map_err(|e| format!("补丁结果不是合法的项目列表，已拒绝: {e}"))?;
+
+    let mut affected: Vec<String> = Vec::new();
+    let mut push_name = |name: &str| {
+        if !affected.iter().any(|n| n == name) {
+            affected.push(name.to_string());
+        }
+    };
+    if touches_all {
+        before.iter().chain(after.iter()).for_each(|p| push_name(&p.name));
+    }
+    for idx in indices {
+        before.get(idx).into_iter().chain(after.get(idx)).for_each(|p| push_name(&p.name));
+    }
+
+    let json = serde_json::to_string_pretty(&doc).map_err(|e| format!("序列化状态失败: {e}"))?;
+    write_state_json_locked(&mut cache, &json, after)?;
+    Ok(affected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patch_pointers_resolve_to_project_indices() {
+        assert_eq!(patch_target_index("replace", "path", "/1/name", 3), Ok(Some(1)));
+        assert_eq!(patch_target_index("remove", "path", "/0", 3), Ok(Some(0)));
+        assert_eq!(patch_target_index("move", "from", "/2/tasks/0", 3), Ok(Some(2)));
+        assert_eq!(patch_target_index("replace", "path", "/name", 3), Ok(None));
+        assert_eq!(patch_target_index("replace", "path", "name", 3), Ok(None));
+    }
+
+    #[test]
+    fn patch_dash_appends_only_for_add() {
+        assert_eq!(patch_target_index("add", "path", "/-", 3), Ok(Some(3)));
+        assert_eq!(patch_target_index("add", "path", "/-", 0), Ok(Some(0)));
+        for op in ["remove", "replace", "test"] {
+            assert!(patch_target_index(op, "path", "/-", 3).is_err(), "{op} must reject /-");
+        }
+        assert!(patch_target_index("copy", "from", "/-", 3).is_err());
+    }
+}
@@ -0,0 +1,69 @@
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs;
+
+use crate::maple_fs;
+use crate::mcp_http::{
+    backup_state_before_write, emit_state_reloaded, lock_state, state_backup_path, state_dir,
+    state_file_stamp, CachedState, Project, STATE_BACKUP_COUNT, STATE_CACHE,
+};
+use crate::state_watcher;
+
+/// Validates `json` as a full project list, rotates the current file into the
+/// `state.json.1..5` backups, then swaps it in atomically. Returns the project count.
+pub fn replace_state(app_handle: &tauri::AppHandle, json: &str) -> Result<usize, String> {
+    let doc: Value = serde_json::from_str(json).map_err(|e| format!("状态不是合法 JSON: {e}"))?;
+    if !doc.is_array() {
+        return Err("状态必须是项目数组。".to_string());
+    }
+    let projects: Vec<Project> =
+        serde_json::from_value(doc.clone()).map_err(|e| format!("状态不是合法的项目列表，已拒绝: {e}"))?;
+
+    let mut project_ids: HashSet<&str> = HashSet::new();
+    for project in &projects {
+        if !project_ids.insert(project.id.as_str()) {
+            return Err(format!("项目 ID「{}」重复，已拒绝。", project.id));
+        }
+        let mut task_ids: HashSet<&str> = HashSet::new();
+        for task in &project.tasks {
+            if !task_ids.insert(task.id.as_str()) {
+                return Err(format!("项目「{}」中任务 ID「{}」重复，已拒绝。", project.name, task.id));
+            }
+        }
+    }
+
+    let pretty = serde_json::to_string_pretty(&doc).map_err(|e| format!("序列化状态失败: {e}"))?;
+    let project_count = projects.len();
+
+    let _state_guard = lock_state();
+    let mut cache = STATE_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    let dir = state_dir();
+    fs::create_dir_all(&dir).map_err(|e| format!("创建 .maple 目录失败: {e}"))?;
+    let path = dir.join("state.json");
+    backup_state_before_write(pretty.as_bytes())?;
+    state_watcher::record_self_write(pretty.as_bytes());
+    if let Err(e) = maple_fs::write_file_atomic(&path, pretty.as_bytes()) {
+        *cache = None;
+        return Err(format!("写入状态文件失败: {e}"));
+    }
+    *cache = state_file_stamp(&path).map(|(modified, len)| CachedState {
+        modified,
+        len,
+        projects,
+    });
+    drop(cache);
+
+    emit_state_reloaded(app_handle, "replace", project_count);
+    Ok(project_count)
+}
+
+/// Restores `state.json.<index>` (1 = newest) through `replace_state`, so the
+/// backup is validated and the state being replaced is itself backed up first.
+pub fn restore_state_backup(app_handle: &tauri::AppHandle, index: usize) -> Result<usize, String> {
+    if !(1..=STATE_BACKUP_COUNT).contains(&index) {
+        return Err(format!("备份序号必须在 1-{STATE_BACKUP_COUNT} 之间。"));
+    }
+    let path = state_backup_path(&state_dir(), index);
+    let json = fs::read_to_string(&path).map_err(|_| format!("状态备份 {index} 不存在。"))?;
+    replace_state(app_handle, &json)
+}
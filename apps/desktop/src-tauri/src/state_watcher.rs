@@ -0,0 +1,83 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+
+use crate::{maple_fs, mcp_http};
+
+const STATE_FILE_NAME: &str = "state.json";
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Hash of the last `state.json` content Maple wrote itself, so the watcher can
+/// tell its own writes apart from external edits.
+static LAST_SELF_WRITE: Mutex<Option<u64>> = Mutex::new(None);
+static WATCHER: Mutex<Option<Debouncer<RecommendedWatcher>>> = Mutex::new(None);
+
+fn content_hash(bytes: &[u8]) -> u64 {
+  let mut hasher = DefaultHasher::new();
+  bytes.hash(&mut hasher);
+  hasher.finish()
+}
+
+/// Call right before writing `state.json` from inside the app.
+pub fn record_self_write(bytes: &[u8]) {
+  *LAST_SELF_WRITE.lock().unwrap_or_else(|e| e.into_inner()) = Some(content_hash(bytes));
+}
+
+fn handle_change(app_handle: &tauri::AppHandle, path: &std::path::Path) {
+  let Ok(bytes) = std::fs::read(path) else {
+    return;
+  };
+  let hash = content_hash(&bytes);
+  {
+    let mut last = LAST_SELF_WRITE.lock().unwrap_or_else(|e| e.into_inner());
+    if *last == Some(hash) {
+      return;
+    }
+    // Remember it so follow-up events for the same content don't fire again.
+    *last = Some(hash);
+  }
+
+  let project_count = match serde_json::from_slice::<serde_json::Value>(&bytes) {
+    Ok(serde_json::Value::Array(projects)) => projects.len(),
+    // Likely caught mid-write by another tool; the next event will pick it up.
+    _ => return,
+  };
+  mcp_http::invalidate_state_cache();
+  mcp_http::emit_state_reloaded(app_handle, "external", project_count);
+}
+
+/// Watches the Maple home directory (not the file itself, so atomic renames are
+/// seen) and emits `maple://state-reloaded` when `state.json` changes externally.
+pub fn start(app_handle: tauri::AppHandle) -> Result<(), String> {
+  let dir = maple_fs::maple_home_dir()?;
+  std::fs::create_dir_all(&dir).map_err(|e| format!("创建 .maple 目录失败: {e}"))?;
+  let state_path = dir.join(STATE_FILE_NAME);
+  if let Ok(bytes) = std::fs::read(&state_path) {
+    record_self_write(&bytes);
+  }
+
+  let watched_path = state_path.clone();
+  let mut debouncer = new_debouncer(DEBOUNCE, move |result: DebounceEventResult| {
+    let Ok(events) = result else {
+      return;
+    };
+    if events
+      .iter()
+      .any(|event| event.path.file_name() == watched_path.file_name())
+    {
+      handle_change(&app_handle, &watched_path);
+    }
+  })
+  .map_err(|e| format!("创建状态文件监听失败: {e}"))?;
+  debouncer
+    .watcher()
+    .watch(&dir, RecursiveMode::NonRecursive)
+    .map_err(|e| format!("监听 {} 失败: {e}", dir.display()))?;
+
+  *WATCHER.lock().unwrap_or_else(|e| e.into_inner()) = Some(debouncer);
+  Ok(())
+}
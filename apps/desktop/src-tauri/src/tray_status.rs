@@ -1,14 +1,31 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
 use tauri::{
     image::Image,
+    menu::{Menu, MenuItem, PredefinedMenuItem, Submenu},
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
-    AppHandle, Manager,
+    AppHandle, Emitter, Manager,
 };
 
 const TRAY_ID: &str = "maple-task-status";
+const MENU_SHOW_ID: &str = "show";
+const MENU_QUIT_ID: &str = "quit";
+const MENU_PROJECT_PREFIX: &str = "project:";
 const ICON_SIZE: u32 = 128;
-
-#[derive(Debug, Clone, Deserialize)]
+const SPINNER_FRAME_INTERVAL: Duration = Duration::from_millis(200);
+const SPINNER_FRAME_COUNT: u32 = 12;
+const SPINNER_SWEEP: f32 = std::f32::consts::PI / 3.0;
+
+static MCP_PAUSED: AtomicBool = AtomicBool::new(false);
+/// Whether the last snapshot asked for a template icon, so `reset` keeps the same style.
+static TEMPLATE_ICON: AtomicBool = AtomicBool::new(false);
+static LAST_SNAPSHOT: Mutex<Option<TrayTaskSnapshot>> = Mutex::new(None);
+/// Set while the spinner thread is alive; only changed with `LAST_SNAPSHOT` held.
+static SPINNER_RUNNING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TrayTaskPalette {
     pub in_progress: Option<String>,
@@ -19,7 +36,7 @@ pub struct TrayTaskPalette {
     pub attention: Option<String>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TrayTaskSnapshot {
     pub unresolved_count: u32,
@@ -30,7 +47,50 @@ pub struct TrayTaskSnapshot {
     pub confirm_count: u32,
     pub blocked_count: u32,
     pub completed_count: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub palette: Option<TrayTaskPalette>,
+    /// Per-project unresolved counts for the tray menu's project submenu.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub projects: Vec<TrayProjectCount>,
+    /// Render a monochrome template image that macOS tints for light/dark
+    /// menu bars. Ignored on other platforms.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub template_icon: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrayProjectCount {
+    pub id: String,
+    pub name: String,
+    pub unresolved_count: u32,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TrayFocusProjectEvent {
+    project_id: String,
+}
+
+impl TrayTaskSnapshot {
+    /// Buckets one task the same way as `buildTrayTaskSnapshot` in the frontend.
+    pub fn count(&mut self, status: &str, needs_confirmation: bool) {
+        match status {
+            "进行中" => self.in_progress_count += 1,
+            "队列中" => self.queued_count += 1,
+            "需要更多信息" => self.need_info_count += 1,
+            "已阻塞" => self.blocked_count += 1,
+            "已完成" if needs_confirmation => self.confirm_count += 1,
+            "已完成" => self.completed_count += 1,
+            _ => self.todo_count += 1,
+        }
+        self.unresolved_count = self.in_progress_count
+            + self.queued_count
+            + self.todo_count
+            + self.need_info_count
+            + self.confirm_count
+            + self.blocked_count;
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -75,10 +135,13 @@ pub fn init(app_handle: &AppHandle) -> tauri::Result<()> {
         return Ok(());
     }
 
+    let menu = build_menu(app_handle, &[])?;
     let _tray = TrayIconBuilder::with_id(TRAY_ID)
         .icon(render_idle_icon())
         .tooltip("Maple · 暂无任务")
+        .menu(&menu)
         .show_menu_on_left_click(false)
+        .on_menu_event(|app, event| handle_menu_event(app, event.id().as_ref()))
         .on_tray_icon_event(|tray, event| {
             if let TrayIconEvent::Click {
                 button: MouseButton::Left,
@@ -86,11 +149,7 @@ pub fn init(app_handle: &AppHandle) -> tauri::Result<()> {
                 ..
             } = event
             {
-                if let Some(window) = tray.app_handle().get_webview_window("main") {
-                    let _ = window.unminimize();
-                    let _ = window.show();
-                    let _ = window.set_focus();
-                }
+                show_main_window(tray.app_handle());
             }
         })
         .build(app_handle)?;
@@ -112,10 +171,24 @@ pub fn sync(app_handle: &AppHandle, snapshot: &TrayTaskSnapshot) -> tauri::Resul
         return Ok(());
     };
 
+    {
+        let mut last = LAST_SNAPSHOT.lock().unwrap_or_else(|e| e.into_inner());
+        *last = Some(snapshot.clone());
+        if wants_spinner(snapshot) && !SPINNER_RUNNING.swap(true, Ordering::SeqCst) {
+            spawn_spinner(app_handle.clone());
+        }
+    }
+
     let status = aggregate_status(snapshot);
-    let icon = render_tray_icon(snapshot, status);
+    let icon = render_tray_icon(snapshot, status, None);
+    #[cfg(target_os = "macos")]
+    {
+        TEMPLATE_ICON.store(snapshot.template_icon, Ordering::Relaxed);
+        let _ = tray.set_icon_as_template(snapshot.template_icon);
+    }
     tray.set_icon(Some(icon))?;
     tray.set_tooltip(Some(build_tooltip(snapshot, status).as_str()))?;
+    tray.set_menu(Some(build_menu(app_handle, &snapshot.projects)?))?;
 
     #[cfg(target_os = "macos")]
     {
@@ -126,6 +199,134 @@ pub fn sync(app_handle: &AppHandle, snapshot: &TrayTaskSnapshot) -> tauri::Resul
     Ok(())
 }
 
+pub fn reset(app_handle: &AppHandle) -> tauri::Result<()> {
+    if app_handle.tray_by_id(TRAY_ID).is_none() {
+        init(app_handle)?;
+    }
+
+    let Some(tray) = app_handle.tray_by_id(TRAY_ID) else {
+        return Ok(());
+    };
+
+    *LAST_SNAPSHOT.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    let idle = render_idle_icon();
+    if uses_template_icon(TEMPLATE_ICON.load(Ordering::Relaxed)) {
+        tray.set_icon(Some(to_template_icon(idle)))?;
+    } else {
+        tray.set_icon(Some(idle))?;
+    }
+    tray.set_tooltip(Some("Maple · 暂无任务"))?;
+    tray.set_menu(Some(build_menu(app_handle, &[])?))?;
+
+    #[cfg(target_os = "macos")]
+    {
+        let _ = tray.set_title(Some("0"));
+    }
+
+    Ok(())
+}
+
+pub fn set_mcp_paused(app_handle: &AppHandle, paused: bool) -> tauri::Result<()> {
+    MCP_PAUSED.store(paused, Ordering::Relaxed);
+
+    let Some(tray) = app_handle.tray_by_id(TRAY_ID) else {
+        return Ok(());
+    };
+    let last = LAST_SNAPSHOT.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    let tooltip = match last.as_ref() {
+        Some(snapshot) => build_tooltip(snapshot, aggregate_status(snapshot)),
+        None if paused => "Maple · 暂无任务\nMCP 已暂停".to_string(),
+        None => "Maple · 暂无任务".to_string(),
+    };
+    tray.set_tooltip(Some(tooltip.as_str()))
+}
+
+fn build_menu(app_handle: &AppHandle, projects: &[TrayProjectCount]) -> tauri::Result<Menu<tauri::Wry>> {
+    let show = MenuItem::with_id(app_handle, MENU_SHOW_ID, "显示主窗口", true, None::<&str>)?;
+    let project_menu = Submenu::new(app_handle, "项目", !projects.is_empty())?;
+    for project in projects {
+        let label = if project.unresolved_count > 0 {
+            format!("{} ({})", project.name, project.unresolved_count)
+        } else {
+            project.name.clone()
+        };
+        let item = MenuItem::with_id(
+            app_handle,
+            format!("{MENU_PROJECT_PREFIX}{}", project.id),
+            label,
+            true,
+            None::<&str>,
+        )?;
+        project_menu.append(&item)?;
+    }
+    let separator = PredefinedMenuItem::separator(app_handle)?;
+    let quit = MenuItem::with_id(app_handle, MENU_QUIT_ID, "退出", true, None::<&str>)?;
+    Menu::with_items(app_handle, &[&show, &project_menu, &separator, &quit])
+}
+
+fn handle_menu_event(app_handle: &AppHandle, id: &str) {
+    if id == MENU_SHOW_ID {
+        show_main_window(app_handle);
+    } else if id == MENU_QUIT_ID {
+        app_handle.exit(0);
+    } else if let Some(project_id) = id.strip_prefix(MENU_PROJECT_PREFIX) {
+        show_main_window(app_handle);
+        let _ = app_handle.emit(
+            "maple://tray-focus-project",
+            TrayFocusProjectEvent {
+                project_id: project_id.to_string(),
+            },
+        );
+    }
+}
+
+fn show_main_window(app_handle: &AppHandle) {
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+fn wants_spinner(snapshot: &TrayTaskSnapshot) -> bool {
+    snapshot.in_progress_count > 0 && !has_attention(snapshot)
+}
+
+/// Redraws the pie with a rotating highlight until nothing is in progress.
+/// The exit check and the flag reset happen under the snapshot lock, so a
+/// concurrent `sync` either sees the thread still running or starts a new one.
+fn spawn_spinner(app_handle: AppHandle) {
+    std::thread::spawn(move || {
+        let mut frame: u32 = 0;
+        loop {
+            std::thread::sleep(SPINNER_FRAME_INTERVAL);
+            let snapshot = {
+                let last = LAST_SNAPSHOT.lock().unwrap_or_else(|e| e.into_inner());
+                match last.as_ref().filter(|snapshot| wants_spinner(snapshot)) {
+                    Some(snapshot) => snapshot.clone(),
+                    None => {
+                        SPINNER_RUNNING.store(false, Ordering::SeqCst);
+                        break;
+                    }
+                }
+            };
+            let Some(tray) = app_handle.tray_by_id(TRAY_ID) else {
+                continue;
+            };
+
+            frame = (frame + 1) % SPINNER_FRAME_COUNT;
+            let offset = std::f32::consts::TAU * frame as f32 / SPINNER_FRAME_COUNT as f32;
+            let icon = render_tray_icon(&snapshot, aggregate_status(&snapshot), Some(offset));
+            let _ = tray.set_icon(Some(icon));
+        }
+        // Put the static icon back so the last spinner frame doesn't linger.
+        let last = LAST_SNAPSHOT.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        if let (Some(snapshot), Some(tray)) = (last, app_handle.tray_by_id(TRAY_ID)) {
+            let _ = tray.set_icon(Some(render_tray_icon(&snapshot, aggregate_status(&snapshot), None)));
+        }
+    });
+}
+
 fn aggregate_status(snapshot: &TrayTaskSnapshot) -> AggregateStatus {
     if snapshot.confirm_count > 0 {
         AggregateStatus::Confirm
@@ -145,6 +346,15 @@ fn aggregate_status(snapshot: &TrayTaskSnapshot) -> AggregateStatus {
 }
 
 fn build_tooltip(snapshot: &TrayTaskSnapshot, status: AggregateStatus) -> String {
+    let tooltip = build_status_tooltip(snapshot, status);
+    if MCP_PAUSED.load(Ordering::Relaxed) {
+        format!("{tooltip}\nMCP 已暂停")
+    } else {
+        tooltip
+    }
+}
+
+fn build_status_tooltip(snapshot: &TrayTaskSnapshot, status: AggregateStatus) -> String {
     if snapshot.unresolved_count == 0 {
         return format!(
             "Maple · {} · 全部完成\n已完成 {}",
@@ -180,14 +390,113 @@ fn has_attention(snapshot: &TrayTaskSnapshot) -> bool {
     snapshot.confirm_count > 0 || snapshot.need_info_count > 0
 }
 
+/// Parses `#rgb[a]`/`#rrggbb[aa]`, `rgb()`/`rgba()` and `hsl()`/`hsla()` colors,
+/// in both the comma and the space/slash syntax.
 fn parse_css_color(raw: &str) -> Option<[u8; 4]> {
     let value = raw.trim();
-    let hex = value.strip_prefix('#')?;
+    match value.strip_prefix('#') {
+        Some(hex) => parse_hex_color(hex),
+        None => parse_functional_color(value),
+    }
+}
+
+fn parse_functional_color(value: &str) -> Option<[u8; 4]> {
+    let lower = value.to_ascii_lowercase();
+    let (name, rest) = lower.split_once('(')?;
+    let inner = rest.trim_end().strip_suffix(')')?;
+    let parts: Vec<&str> = inner
+        .split(|ch: char| ch == ',' || ch == '/' || ch.is_whitespace())
+        .filter(|part| !part.is_empty())
+        .collect();
+    if parts.len() != 3 && parts.len() != 4 {
+        return None;
+    }
+    let alpha = match parts.get(3) {
+        Some(part) => parse_alpha(part)?,
+        None => 255,
+    };
+
+    match name.trim() {
+        "rgb" | "rgba" => Some([
+            parse_rgb_channel(parts[0])?,
+            parse_rgb_channel(parts[1])?,
+            parse_rgb_channel(parts[2])?,
+            alpha,
+        ]),
+        "hsl" | "hsla" => {
+            let hue = parts[0].strip_suffix("deg").unwrap_or(parts[0]).parse::<f64>().ok()?;
+            let saturation = parse_percentage(parts[1])?;
+            let lightness = parse_percentage(parts[2])?;
+            let [r, g, b] = hsl_to_rgb(hue, saturation, lightness);
+            Some([r, g, b, alpha])
+        }
+        _ => None,
+    }
+}
+
+fn unit_to_u8(value: f64) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// `0-255` or a percentage.
+fn parse_rgb_channel(part: &str) -> Option<u8> {
+    match part.strip_suffix('%') {
+        Some(pct) => Some(unit_to_u8(pct.parse::<f64>().ok()? / 100.0)),
+        None => Some(part.parse::<f64>().ok()?.clamp(0.0, 255.0).round() as u8),
+    }
+}
+
+/// `0-1` or a percentage.
+fn parse_alpha(part: &str) -> Option<u8> {
+    match part.strip_suffix('%') {
+        Some(pct) => Some(unit_to_u8(pct.parse::<f64>().ok()? / 100.0)),
+        None => Some(unit_to_u8(part.parse::<f64>().ok()?)),
+    }
+}
+
+/// Percentage as a `0-1` fraction; the `%` sign is optional.
+fn parse_percentage(part: &str) -> Option<f64> {
+    let number = part.strip_suffix('%').unwrap_or(part).parse::<f64>().ok()?;
+    Some((number / 100.0).clamp(0.0, 1.0))
+}
+
+fn hsl_to_rgb(hue: f64, saturation: f64, lightness: f64) -> [u8; 3] {
+    let hue = hue.rem_euclid(360.0) / 360.0;
+    if saturation == 0.0 {
+        let gray = unit_to_u8(lightness);
+        return [gray, gray, gray];
+    }
+    let q = if lightness < 0.5 {
+        lightness * (1.0 + saturation)
+    } else {
+        lightness + saturation - lightness * saturation
+    };
+    let p = 2.0 * lightness - q;
+    let channel = |offset: f64| {
+        let t = (hue + offset).rem_euclid(1.0);
+        let value = if t < 1.0 / 6.0 {
+            p + (q - p) * 6.0 * t
+        } else if t < 0.5 {
+            q
+        } else if t < 2.0 / 3.0 {
+            p + (q - p) * (2.0 / 3.0 - t) * 6.0
+        } else {
+            p
+        };
+        unit_to_u8(value)
+    };
+    [channel(1.0 / 3.0), channel(0.0), channel(-1.0 / 3.0)]
+}
 
+fn parse_hex_color(hex: &str) -> Option<[u8; 4]> {
     fn hex_u8(pair: &str) -> Option<u8> {
         u8::from_str_radix(pair, 16).ok()
     }
 
+    // The slices below are byte offsets; a multi-byte character would panic there.
+    if !hex.is_ascii() {
+        return None;
+    }
     match hex.len() {
         3 => {
             let r = hex_u8(&hex[0..1])? * 17;
@@ -240,11 +549,49 @@ fn render_idle_icon() -> Image<'static> {
     render_empty_circle_icon([160, 160, 160, 220])
 }
 
-fn render_tray_icon(snapshot: &TrayTaskSnapshot, status: AggregateStatus) -> Image<'static> {
-    if has_attention(snapshot) {
-        return render_check_icon(resolve_color(snapshot, status));
+/// `spin` is the spinner's current angle in radians, if it is animating.
+fn render_tray_icon(
+    snapshot: &TrayTaskSnapshot,
+    status: AggregateStatus,
+    spin: Option<f32>,
+) -> Image<'static> {
+    let icon = if has_attention(snapshot) {
+        render_check_icon(resolve_color(snapshot, status))
+    } else {
+        render_overview_pie_icon(snapshot, spin)
+    };
+    if uses_template_icon(snapshot.template_icon) {
+        return to_template_icon(icon);
+    }
+    // macOS shows the count as the tray title instead.
+    if cfg!(target_os = "macos") || snapshot.unresolved_count == 0 {
+        return icon;
     }
-    render_overview_pie_icon(snapshot)
+    let mut rgba = icon.rgba().to_vec();
+    draw_count_badge(&mut rgba, snapshot.unresolved_count);
+    Image::new_owned(rgba, ICON_SIZE, ICON_SIZE)
+}
+
+fn uses_template_icon(requested: bool) -> bool {
+    cfg!(target_os = "macos") && requested
+}
+
+/// Converts a coloured icon into a template image, where only alpha matters.
+/// White strokes (the check mark) are knocked out and darker colours stay more
+/// opaque, so pie segments remain distinguishable as shades of the tint.
+fn to_template_icon(icon: Image<'static>) -> Image<'static> {
+    let mut rgba = icon.rgba().to_vec();
+    for pixel in rgba.chunks_exact_mut(4) {
+        let [r, g, b, a] = [pixel[0], pixel[1], pixel[2], pixel[3]];
+        let alpha = if r >= 240 && g >= 240 && b >= 240 {
+            0.0
+        } else {
+            let luma = (0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32) / 255.0;
+            a as f32 * (0.55 + 0.45 * (1.0 - luma))
+        };
+        pixel.copy_from_slice(&[0, 0, 0, alpha.round() as u8]);
+    }
+    Image::new_owned(rgba, ICON_SIZE, ICON_SIZE)
 }
 
 fn render_empty_circle_icon(color: [u8; 4]) -> Image<'static> {
@@ -276,7 +623,7 @@ fn render_check_icon(bg_color: [u8; 4]) -> Image<'static> {
     Image::new_owned(rgba, ICON_SIZE, ICON_SIZE)
 }
 
-fn render_overview_pie_icon(snapshot: &TrayTaskSnapshot) -> Image<'static> {
+fn render_overview_pie_icon(snapshot: &TrayTaskSnapshot, spin: Option<f32>) -> Image<'static> {
     let segments: [(u32, [u8; 4]); 5] = [
         (
             snapshot.in_progress_count,
@@ -293,10 +640,14 @@ fn render_overview_pie_icon(snapshot: &TrayTaskSnapshot) -> Image<'static> {
             resolve_color(snapshot, AggregateStatus::Done),
         ),
     ];
-    render_pie_icon(&segments, false)
+    render_pie_icon(&segments, false, spin)
 }
 
-fn render_pie_icon(segments: &[(u32, [u8; 4])], highlight: bool) -> Image<'static> {
+fn render_pie_icon(
+    segments: &[(u32, [u8; 4])],
+    highlight: bool,
+    spin: Option<f32>,
+) -> Image<'static> {
     let total: u32 = segments.iter().map(|(value, _)| *value).sum();
     if total == 0 {
         return render_idle_icon();
@@ -357,6 +708,15 @@ fn render_pie_icon(segments: &[(u32, [u8; 4])], highlight: bool) -> Image<'stati
                     break;
                 }
             }
+
+            if let Some(offset) = spin {
+                // Fade the highlight out towards the arc's trailing edge.
+                let behind = (offset - angle).rem_euclid(tau);
+                if behind < SPINNER_SWEEP {
+                    let alpha = 150.0 * (1.0 - behind / SPINNER_SWEEP);
+                    blend_pixel(&mut rgba, x, y, [255, 255, 255, alpha as u8]);
+                }
+            }
         }
     }
 
@@ -385,6 +745,88 @@ fn draw_check_mark(rgba: &mut [u8], color: [u8; 4]) {
     draw_thick_line(rgba, mid, end, stroke_radius, color);
 }
 
+/// 3x5 bitmap glyphs for the badge: digits 0-9, then '+'.
+const BADGE_GLYPHS: [[u8; 5]; 11] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b010, 0b010, 0b010],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+    [0b000, 0b010, 0b111, 0b010, 0b000],
+];
+const BADGE_PLUS_GLYPH: usize = 10;
+
+/// Draws `count` ("9+" above nine) as white digits on a red pill in the
+/// bottom-right corner. Glyph cells are 8px so strokes stay 4px wide when the
+/// 128px icon is scaled down to 64px.
+fn draw_count_badge(rgba: &mut [u8], count: u32) {
+    let glyphs: Vec<usize> = if count > 9 {
+        vec![9, BADGE_PLUS_GLYPH]
+    } else {
+        vec![count as usize]
+    };
+
+    let cell = (ICON_SIZE / 16) as i32;
+    let glyph_gap = cell;
+    let padding = cell;
+    let text_width = glyphs.len() as i32 * 3 * cell + (glyphs.len() as i32 - 1) * glyph_gap;
+    let text_height = 5 * cell;
+    let badge_height = text_height + padding * 2;
+    let badge_width = (text_width + padding * 2).max(badge_height);
+    let size = ICON_SIZE as i32;
+    let left = size - badge_width;
+    let top = size - badge_height;
+
+    fill_pill(rgba, left, top, badge_width, badge_height, [255, 255, 255, 255]);
+    let border = (cell / 2).max(1);
+    fill_pill(
+        rgba,
+        left + border,
+        top + border,
+        badge_width - border * 2,
+        badge_height - border * 2,
+        [214, 58, 58, 255],
+    );
+
+    let mut x = left + (badge_width - text_width) / 2;
+    let y = top + (badge_height - text_height) / 2;
+    for glyph in glyphs {
+        for (row, bits) in BADGE_GLYPHS[glyph].iter().enumerate() {
+            for col in 0..3 {
+                if bits & (0b100 >> col) == 0 {
+                    continue;
+                }
+                let px = x + col * cell;
+                let py = y + row as i32 * cell;
+                for dy in 0..cell {
+                    for dx in 0..cell {
+                        blend_pixel(rgba, px + dx, py + dy, [255, 255, 255, 255]);
+                    }
+                }
+            }
+        }
+        x += 3 * cell + glyph_gap;
+    }
+}
+
+/// Fills a horizontal capsule whose ends are half-circles of `height / 2`.
+fn fill_pill(rgba: &mut [u8], left: i32, top: i32, width: i32, height: i32, color: [u8; 4]) {
+    let radius = height / 2;
+    let cy = top + radius;
+    draw_circle(rgba, left + radius, cy, radius, color);
+    draw_circle(rgba, left + width - radius - 1, cy, radius, color);
+    for y in top..(top + height) {
+        for x in (left + radius)..(left + width - radius) {
+            blend_pixel(rgba, x, y, color);
+        }
+    }
+}
+
 fn draw_thick_line(
     rgba: &mut [u8],
     start: (f32, f32),
@@ -453,3 +895,64 @@ fn blend_pixel(rgba: &mut [u8], x: i32, y: i32, color: [u8; 4]) {
     rgba[idx + 2] = ((color[2] as u16 * src_a + dst_b * inv_a) / 255) as u8;
     rgba[idx + 3] = (src_a + (dst_a * inv_a) / 255) as u8;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hex_colors() {
+        assert_eq!(parse_css_color("#0f0"), Some([0, 255, 0, 255]));
+        assert_eq!(parse_css_color("#0f08"), Some([0, 255, 0, 136]));
+        assert_eq!(parse_css_color(" #22C55E "), Some([34, 197, 94, 255]));
+        assert_eq!(parse_css_color("#22c55e80"), Some([34, 197, 94, 128]));
+    }
+
+    #[test]
+    fn parses_rgb_and_rgba() {
+        assert_eq!(parse_css_color("rgb(34,197,94)"), Some([34, 197, 94, 255]));
+        assert_eq!(parse_css_color("rgb(34, 197, 94)"), Some([34, 197, 94, 255]));
+        assert_eq!(parse_css_color("RGB(34 197 94)"), Some([34, 197, 94, 255]));
+        assert_eq!(parse_css_color("rgb(100%, 0%, 50%)"), Some([255, 0, 128, 255]));
+        assert_eq!(parse_css_color("rgb(300, -5, 12.4)"), Some([255, 0, 12, 255]));
+        assert_eq!(parse_css_color("rgba(255,0,0,50%)"), Some([255, 0, 0, 128]));
+        assert_eq!(parse_css_color("rgba(255, 0, 0, 0.25)"), Some([255, 0, 0, 64]));
+        assert_eq!(parse_css_color("rgb(255 0 0 / 0.5)"), Some([255, 0, 0, 128]));
+        assert_eq!(parse_css_color("rgb(255 0 0 / 20%)"), Some([255, 0, 0, 51]));
+    }
+
+    #[test]
+    fn parses_hsl_and_hsla() {
+        assert_eq!(parse_css_color("hsl(120 100% 50%)"), Some([0, 255, 0, 255]));
+        assert_eq!(parse_css_color("hsl(0, 100%, 50%)"), Some([255, 0, 0, 255]));
+        assert_eq!(parse_css_color("hsl(240deg, 100%, 50%)"), Some([0, 0, 255, 255]));
+        assert_eq!(parse_css_color("hsl(-120, 100%, 50%)"), Some([0, 0, 255, 255]));
+        assert_eq!(parse_css_color("hsl(142,71%,45%)"), Some([33, 196, 93, 255]));
+        assert_eq!(parse_css_color("hsl(0, 0%, 50%)"), Some([128, 128, 128, 255]));
+        assert_eq!(parse_css_color("hsla(120, 100%, 50%, 0.5)"), Some([0, 255, 0, 128]));
+        assert_eq!(parse_css_color("hsl(120 100% 50% / 25%)"), Some([0, 255, 0, 64]));
+    }
+
+    #[test]
+    fn rejects_unparseable_colors() {
+        for value in [
+            "",
+            "green",
+            "#",
+            "#12",
+            "#12345",
+            "#ggg",
+            "#é1",
+            "rgb(1, 2)",
+            "rgb(1, 2, 3, 4, 5)",
+            "rgb(a, b, c)",
+            "rgb(1, 2, 3",
+            "rgba(1, 2, 3, x)",
+            "cmyk(1, 2, 3)",
+            "hsl(x, 50%, 50%)",
+            "hsl(120, y, 50%)",
+        ] {
+            assert_eq!(parse_css_color(value), None, "{value:?}");
+        }
+    }
+}
@@ -0,0 +1,473 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+use crate::maple_fs;
+
+/// Whether worker output is written to `~/.maple/worker-logs`. Persisted in
+/// `~/.maple/worker-log-settings.json`; defaults to enabled when the file is
+/// missing or unreadable.
+static PERSIST_WORKER_LOGS: OnceLock<AtomicBool> = OnceLock::new();
+
+fn settings_path() -> Result<PathBuf, String> {
+  Ok(maple_fs::maple_home_dir()?.join("worker-log-settings.json"))
+}
+
+fn persist_flag() -> &'static AtomicBool {
+  PERSIST_WORKER_LOGS.get_or_init(|| {
+    let enabled = settings_path()
+      .ok()
+      .and_then(|path| std::fs::read_to_string(path).ok())
+      .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+      .and_then(|parsed| parsed.get("persistWorkerLogs").and_then(|v| v.as_bool()))
+      .unwrap_or(true);
+    AtomicBool::new(enabled)
+  })
+}
+
+pub fn is_persistence_enabled() -> bool {
+  persist_flag().load(Ordering::Relaxed)
+}
+
+/// Turning persistence off only stops new writes; logs already on disk are kept.
+pub fn set_persistence_enabled(enabled: bool) -> Result<bool, String> {
+  let path = settings_path()?;
+  if let Some(parent) = path.parent() {
+    std::fs::create_dir_all(parent).map_err(|e| format!("创建 Maple 目录失败: {e}"))?;
+  }
+  let payload = serde_json::to_string_pretty(&serde_json::json!({ "persistWorkerLogs": enabled }))
+    .map_err(|e| format!("序列化设置失败: {e}"))?;
+  std::fs::write(&path, payload).map_err(|e| format!("写入 Worker 日志设置失败: {e}"))?;
+  persist_flag().store(enabled, Ordering::Relaxed);
+  Ok(enabled)
+}
+
+pub fn worker_log_dir() -> Result<PathBuf, String> {
+  let dir = maple_fs::maple_home_dir()?.join("worker-logs");
+  std::fs::create_dir_all(&dir).map_err(|e| format!("创建 worker-logs 目录失败: {e}"))?;
+  Ok(dir)
+}
+
+fn sanitize_worker_id(worker_id: &str) -> String {
+  let sanitized: String = worker_id
+    .trim()
+    .chars()
+    .map(|ch| if ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.') { ch } else { '_' })
+    .collect();
+  if sanitized.is_empty() || sanitized.chars().all(|ch| ch == '.') {
+    "worker".to_string()
+  } else {
+    sanitized
+  }
+}
+
+pub fn worker_log_path(worker_id: &str) -> Result<PathBuf, String> {
+  Ok(worker_log_dir()?.join(format!("{}.log", sanitize_worker_id(worker_id))))
+}
+
+/// Truncates the log so that it only holds the output of the run that is starting.
+pub fn reset(worker_id: &str) {
+  if !is_persistence_enabled() {
+    return;
+  }
+  if let Ok(path) = worker_log_path(worker_id) {
+    let _ = std::fs::write(path, b"");
+  }
+}
+
+pub fn append(worker_id: &str, chunk: &str) {
+  if !is_persistence_enabled() {
+    return;
+  }
+  let Ok(path) = worker_log_path(worker_id) else {
+    return;
+  };
+  if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(path) {
+    let _ = file.write_all(chunk.as_bytes());
+  }
+}
+
+pub fn read(worker_id: &str) -> Result<String, String> {
+  if !is_persistence_enabled() {
+    return Err("Worker 日志持久化已关闭，未保存该 Worker 的输出。可在设置中重新开启。".to_string());
+  }
+  let path = worker_log_path(worker_id)?;
+  if !path.exists() {
+    return Err(format!("Worker 日志不存在: {worker_id}"));
+  }
+  let bytes = std::fs::read(&path).map_err(|e| format!("读取 Worker 日志失败: {e}"))?;
+  Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+pub fn export_html(worker_id: &str, dest_path: &str) -> Result<String, String> {
+  let dest = dest_path.trim();
+  if dest.is_empty() {
+    return Err("dest_path 不能为空".to_string());
+  }
+  let raw = read(worker_id)?;
+  let html = render_html_document(worker_id, &raw);
+  let path = PathBuf::from(dest);
+  if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+    std::fs::create_dir_all(parent).map_err(|e| format!("创建目标目录失败: {e}"))?;
+  }
+  std::fs::write(&path, html.as_bytes()).map_err(|e| format!("写入 HTML 失败: {e}"))?;
+  Ok(path.to_string_lossy().to_string())
+}
+
+// ── ANSI → HTML ──
+
+const ANSI_PALETTE: [&str; 16] = [
+  "#1e1e1e", "#cd3131", "#0dbc79", "#e5e510", "#2472c8", "#bc3fbc", "#11a8cd", "#e5e5e5",
+  "#666666", "#f14c4c", "#23d18b", "#f5f543", "#3b8eea", "#d670d6", "#29b8db", "#ffffff",
+];
+
+#[derive(Clone, Default, PartialEq)]
+struct CellStyle {
+  fg: Option<String>,
+  bg: Option<String>,
+  bold: bool,
+  dim: bool,
+  italic: bool,
+  underline: bool,
+  inverse: bool,
+}
+
+impl CellStyle {
+  fn css(&self) -> String {
+    let (fg, bg) = if self.inverse {
+      (
+        Some(self.bg.clone().unwrap_or_else(|| "#1e1e1e".to_string())),
+        Some(self.fg.clone().unwrap_or_else(|| "#e5e5e5".to_string())),
+      )
+    } else {
+      (self.fg.clone(), self.bg.clone())
+    };
+    let mut parts: Vec<String> = Vec::new();
+    if let Some(fg) = fg {
+      parts.push(format!("color:{fg}"));
+    }
+    if let Some(bg) = bg {
+      parts.push(format!("background-color:{bg}"));
+    }
+    if self.bold {
+      parts.push("font-weight:bold".to_string());
+    }
+    if self.dim {
+      parts.push("opacity:0.7".to_string());
+    }
+    if self.italic {
+      parts.push("font-style:italic".to_string());
+    }
+    if self.underline {
+      parts.push("text-decoration:underline".to_string());
+    }
+    parts.join(";")
+  }
+}
+
+fn xterm_256_color(index: u16) -> String {
+  match index {
+    0..=15 => ANSI_PALETTE[index as usize].to_string(),
+    16..=231 => {
+      let value = index - 16;
+      let level = |v: u16| if v == 0 { 0 } else { 55 + v * 40 };
+      format!("#{:02x}{:02x}{:02x}", level(value / 36), level((value / 6) % 6), level(value % 6))
+    }
+    _ => {
+      let gray = 8 + (index.min(255) - 232) * 10;
+      format!("#{gray:02x}{gray:02x}{gray:02x}")
+    }
+  }
+}
+
+/// Reads an extended color (`5;n` or `2;r;g;b`) and advances the cursor past it.
+fn parse_extended_color(params: &[u16], cursor: &mut usize) -> Option<String> {
+  match params.get(*cursor + 1) {
+    Some(5) => {
+      let index = *params.get(*cursor + 2)?;
+      *cursor += 2;
+      Some(xterm_256_color(index))
+    }
+    Some(2) => {
+      let r = *params.get(*cursor + 2)?;
+      let g = *params.get(*cursor + 3)?;
+      let b = *params.get(*cursor + 4)?;
+      *cursor += 4;
+      Some(format!("#{:02x}{:02x}{:02x}", r.min(255), g.min(255), b.min(255)))
+    }
+    _ => None,
+  }
+}
+
+fn apply_sgr(style: &mut CellStyle, params: &[u16]) {
+  if params.is_empty() {
+    *style = CellStyle::default();
+    return;
+  }
+  let mut cursor = 0usize;
+  while cursor < params.len() {
+    let code = params[cursor];
+    match code {
+      0 => *style = CellStyle::default(),
+      1 => style.bold = true,
+      2 => style.dim = true,
+      3 => style.italic = true,
+      4 => style.underline = true,
+      7 => style.inverse = true,
+      22 => {
+        style.bold = false;
+        style.dim = false;
+      }
+      23 => style.italic = false,
+      24 => style.underline = false,
+      27 => style.inverse = false,
+      30..=37 => style.fg = Some(ANSI_PALETTE[(code - 30) as usize].to_string()),
+      90..=97 => style.fg = Some(ANSI_PALETTE[(code - 90 + 8) as usize].to_string()),
+      39 => style.fg = None,
+      40..=47 => style.bg = Some(ANSI_PALETTE[(code - 40) as usize].to_string()),
+      100..=107 => style.bg = Some(ANSI_PALETTE[(code - 100 + 8) as usize].to_string()),
+      49 => style.bg = None,
+      38 => style.fg = parse_extended_color(params, &mut cursor).or(style.fg.take()),
+      48 => style.bg = parse_extended_color(params, &mut cursor).or(style.bg.take()),
+      _ => {}
+    }
+    cursor += 1;
+  }
+}
+
+/// Right margin for cursor-forward moves. The log has no fixed width, so this
+/// only stops `ESC[<n>C` from padding a line with tens of thousands of spaces.
+const SCREEN_MAX_COL: usize = 1024;
+
+/// Minimal terminal screen: replays carriage returns, erases and cursor
+/// movement so that progress bars collapse to their final state.
+struct Screen {
+  lines: Vec<Vec<(char, CellStyle)>>,
+  row: usize,
+  col: usize,
+  style: CellStyle,
+}
+
+impl Screen {
+  fn new() -> Self {
+    Self {
+      lines: vec![Vec::new()],
+      row: 0,
+      col: 0,
+      style: CellStyle::default(),
+    }
+  }
+
+  fn ensure_row(&mut self) {
+    while self.lines.len() <= self.row {
+      self.lines.push(Vec::new());
+    }
+  }
+
+  /// Cursor-down moves stop at the last line, as they do at a terminal's bottom margin.
+  fn move_down(&mut self, count: usize) {
+    let last = self.lines.len().saturating_sub(1).max(self.row);
+    self.row = self.row.saturating_add(count).min(last);
+  }
+
+  fn put(&mut self, ch: char) {
+    self.ensure_row();
+    let line = &mut self.lines[self.row];
+    while line.len() < self.col {
+      line.push((' ', CellStyle::default()));
+    }
+    if self.col < line.len() {
+      line[self.col] = (ch, self.style.clone());
+    } else {
+      line.push((ch, self.style.clone()));
+    }
+    self.col += 1;
+  }
+
+  fn erase_line(&mut self, mode: u16) {
+    self.ensure_row();
+    let line = &mut self.lines[self.row];
+    match mode {
+      1 => {
+        for cell in line.iter_mut().take(self.col + 1) {
+          *cell = (' ', CellStyle::default());
+        }
+      }
+      2 => line.clear(),
+      _ => line.truncate(self.col),
+    }
+  }
+
+  fn apply_csi(&mut self, params: &[u16], final_byte: char) {
+    let count = params.first().copied().unwrap_or(0).max(1) as usize;
+    match final_byte {
+      'm' => apply_sgr(&mut self.style, params),
+      'K' => self.erase_line(params.first().copied().unwrap_or(0)),
+      'A' => self.row = self.row.saturating_sub(count),
+      'B' => self.move_down(count),
+      'C' => self.col = (self.col + count).min(SCREEN_MAX_COL.max(self.col)),
+      'D' => self.col = self.col.saturating_sub(count),
+      'G' => self.col = (count - 1).min(SCREEN_MAX_COL),
+      'E' => {
+        self.move_down(count);
+        self.col = 0;
+      }
+      'F' => {
+        self.row = self.row.saturating_sub(count);
+        self.col = 0;
+      }
+      'J' if params.first().copied().unwrap_or(0) == 0 => {
+        self.erase_line(0);
+        self.lines.truncate(self.row + 1);
+      }
+      _ => {}
+    }
+  }
+
+  fn feed(&mut self, text: &str) {
+    let mut chars = text.chars().peekable();
+    while let Some(ch) = chars.next() {
+      match ch {
+        '\u{1b}' => match chars.peek().copied() {
+          Some('[') => {
+            chars.next();
+            let mut raw = String::new();
+            let mut final_byte = None;
+            for next in chars.by_ref() {
+              if ('\u{40}'..='\u{7e}').contains(&next) {
+                final_byte = Some(next);
+                break;
+              }
+              raw.push(next);
+            }
+            let Some(final_byte) = final_byte else { break };
+            if raw.starts_with(['?', '>', '=']) {
+              continue;
+            }
+            let params: Vec<u16> = if raw.is_empty() {
+              Vec::new()
+            } else {
+              raw
+                .split([';', ':'])
+                .map(|part| part.parse::<u16>().unwrap_or(0))
+                .collect()
+            };
+            self.apply_csi(&params, final_byte);
+          }
+          Some(']') => {
+            chars.next();
+            while let Some(next) = chars.next() {
+              if next == '\u{07}' {
+                break;
+              }
+              if next == '\u{1b}' && chars.peek() == Some(&'\\') {
+                chars.next();
+                break;
+              }
+            }
+          }
+          Some(_) => {
+            chars.next();
+          }
+          None => {}
+        },
+        '\r' => self.col = 0,
+        '\n' => {
+          self.row += 1;
+          self.col = 0;
+          self.ensure_row();
+        }
+        '\u{08}' => self.col = self.col.saturating_sub(1),
+        '\t' => {
+          let next_stop = (self.col / 8 + 1) * 8;
+          while self.col < next_stop {
+            self.put(' ');
+          }
+        }
+        ch if ch.is_control() => {}
+        ch => self.put(ch),
+      }
+    }
+  }
+}
+
+fn escape_html(text: &str) -> String {
+  let mut out = String::with_capacity(text.len());
+  for ch in text.chars() {
+    match ch {
+      '&' => out.push_str("&amp;"),
+      '<' => out.push_str("&lt;"),
+      '>' => out.push_str("&gt;"),
+      '"' => out.push_str("&quot;"),
+      '\'' => out.push_str("&#39;"),
+      _ => out.push(ch),
+    }
+  }
+  out
+}
+
+fn render_ansi_html(raw: &str) -> String {
+  let mut screen = Screen::new();
+  screen.feed(&raw.replace("\r\n", "\n"));
+
+  let mut html = String::new();
+  for line in &screen.lines {
+    let mut index = 0usize;
+    while index < line.len() {
+      let style = &line[index].1;
+      let mut text = String::new();
+      while index < line.len() && &line[index].1 == style {
+        text.push(line[index].0);
+        index += 1;
+      }
+      let css = style.css();
+      if css.is_empty() {
+        html.push_str(&escape_html(&text));
+      } else {
+        html.push_str(&format!("<span style=\"{css}\">{}</span>", escape_html(&text)));
+      }
+    }
+    html.push('\n');
+  }
+  html
+}
+
+fn render_html_document(worker_id: &str, raw: &str) -> String {
+  let title = escape_html(&format!("Maple Worker Log — {worker_id}"));
+  format!(
+    "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>\nbody {{ margin: 0; background: #1e1e1e; color: #e5e5e5; }}\nh1 {{ font: 600 14px -apple-system, 'Segoe UI', sans-serif; margin: 0; padding: 12px 16px; border-bottom: 1px solid #333; }}\npre {{ margin: 0; padding: 16px; font: 13px/1.45 'SF Mono', Menlo, Consolas, monospace; white-space: pre-wrap; word-break: break-all; }}\n</style>\n</head>\n<body>\n<h1>{title}</h1>\n<pre>{}</pre>\n</body>\n</html>\n",
+    render_ansi_html(raw)
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn text_of(screen: &Screen) -> Vec<String> {
+    screen.lines.iter().map(|line| line.iter().map(|(ch, _)| ch).collect()).collect()
+  }
+
+  #[test]
+  fn huge_cursor_moves_are_clamped() {
+    let mut screen = Screen::new();
+    screen.feed("one\ntwo\u{1b}[65535B\u{1b}[65535Cx");
+    assert_eq!(screen.lines.len(), 2);
+    assert_eq!(screen.row, 1);
+    assert_eq!(screen.lines[1].len(), SCREEN_MAX_COL + 1);
+
+    screen.feed("\u{1b}[65535E\u{1b}[65535Gy");
+    assert_eq!(screen.lines.len(), 2);
+    assert_eq!(screen.lines[1].len(), SCREEN_MAX_COL + 1);
+    assert_eq!(screen.lines[1][SCREEN_MAX_COL].0, 'y');
+  }
+
+  #[test]
+  fn cursor_moves_within_the_screen_still_apply() {
+    let mut screen = Screen::new();
+    screen.feed("a\nb\nc\u{1b}[2A\u{1b}[2CX\u{1b}[1BY");
+    assert_eq!(text_of(&screen), ["a  X", "b   Y", "c"]);
+  }
+}
@@ -0,0 +1,90 @@
+use serde::Serialize;
+
+use crate::process_utils;
+
+/// `(kind, default executable, oldest version with the MCP support Maple relies on)`.
+const KNOWN_WORKERS: &[(&str, &str, (u64, u64, u64))] = &[
+  ("codex", "codex", (0, 20, 0)),
+  ("claude", "claude", (1, 0, 0)),
+  ("iflow", "iflow", (0, 2, 0)),
+  ("gemini", "gemini", (0, 1, 0)),
+];
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkerProbeResult {
+  pub kind: String,
+  pub found: bool,
+  pub version: Option<String>,
+  pub minimum_version: String,
+  pub meets_minimum: bool,
+}
+
+/// Finds the first `major.minor[.patch]` in `text`, e.g. "codex-cli 0.23.1" or
+/// "1.0.61 (Claude Code)". A missing patch component counts as 0.
+pub fn parse_version(text: &str) -> Option<(u64, u64, u64)> {
+  let bytes = text.as_bytes();
+  let mut start = 0;
+  while start < bytes.len() {
+    if !bytes[start].is_ascii_digit() || (start > 0 && bytes[start - 1].is_ascii_digit()) {
+      start += 1;
+      continue;
+    }
+    let candidate: String = text[start..]
+      .chars()
+      .take_while(|c| c.is_ascii_digit() || *c == '.')
+      .collect();
+    let parts: Vec<u64> = candidate
+      .split('.')
+      .take(3)
+      .map_while(|part| part.parse::<u64>().ok())
+      .collect();
+    if parts.len() >= 2 {
+      return Some((parts[0], parts[1], parts.get(2).copied().unwrap_or(0)));
+    }
+    start += candidate.len().max(1);
+  }
+  None
+}
+
+pub fn format_version((major, minor, patch): (u64, u64, u64)) -> String {
+  format!("{major}.{minor}.{patch}")
+}
+
+pub fn minimum_version(kind: &str) -> Option<(u64, u64, u64)> {
+  let normalized = kind.trim().to_ascii_lowercase();
+  KNOWN_WORKERS
+    .iter()
+    .find(|(known, _, _)| *known == normalized)
+    .map(|(_, _, minimum)| *minimum)
+}
+
+/// Runs `<executable> --version` for a known worker kind and checks it against
+/// the minimum Maple supports. `executable` overrides the kind's default binary.
+pub fn probe(kind: &str, executable: Option<&str>) -> Result<WorkerProbeResult, String> {
+  let normalized = kind.trim().to_ascii_lowercase();
+  let (_, default_executable, minimum) = KNOWN_WORKERS
+    .iter()
+    .find(|(known, _, _)| *known == normalized)
+    .copied()
+    .ok_or_else(|| format!("未知的 Worker 类型: {kind}"))?;
+  let executable = executable
+    .map(str::trim)
+    .filter(|value| !value.is_empty())
+    .unwrap_or(default_executable);
+
+  let output = process_utils::build_cli_command(executable, &["--version".to_string()]).output();
+  let version = output.as_ref().ok().and_then(|output| {
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    parse_version(&stdout).or_else(|| parse_version(&stderr))
+  });
+
+  Ok(WorkerProbeResult {
+    kind: normalized,
+    found: output.is_ok_and(|output| output.status.success()),
+    version: version.map(format_version),
+    minimum_version: format_version(minimum),
+    meets_minimum: version.is_some_and(|version| version >= minimum),
+  })
+}
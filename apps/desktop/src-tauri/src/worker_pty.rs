@@ -0,0 +1,136 @@
+use std::io::{Read, Write};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+
+use portable_pty::{native_pty_system, Child, CommandBuilder, MasterPty, PtySize};
+
+const DEFAULT_ROWS: u16 = 40;
+const DEFAULT_COLS: u16 = 120;
+
+type SharedMaster = Arc<Mutex<Option<Box<dyn MasterPty + Send>>>>;
+/// The process plus its merged output and its input.
+type SpawnedPty = (PtyProcess, Box<dyn Read + Send>, Box<dyn Write + Send>);
+
+/// A worker running inside a pseudo terminal (ConPTY on Windows).
+pub struct PtyProcess {
+  child: Box<dyn Child + Send + Sync>,
+  master: SharedMaster,
+}
+
+/// Resizes the pseudo terminal of a running worker (`TIOCSWINSZ` on Unix, the
+/// ConPTY resize API on Windows); a no-op once it has exited.
+#[derive(Clone)]
+pub struct PtyResizer {
+  master: SharedMaster,
+}
+
+impl PtyResizer {
+  pub fn resize(&self, cols: u16, rows: u16) -> Result<bool, String> {
+    let guard = self.master.lock().map_err(|_| "PTY 锁不可用".to_string())?;
+    let Some(master) = guard.as_ref() else {
+      return Ok(false);
+    };
+    master
+      .resize(PtySize {
+        rows: rows.max(1),
+        cols: cols.max(1),
+        pixel_width: 0,
+        pixel_height: 0,
+      })
+      .map_err(|error| format!("调整 PTY 尺寸失败: {error}"))?;
+    Ok(true)
+  }
+}
+
+impl PtyProcess {
+  pub fn pid(&self) -> u32 {
+    self.child.process_id().unwrap_or(0)
+  }
+
+  pub fn resizer(&self) -> PtyResizer {
+    PtyResizer {
+      master: Arc::clone(&self.master),
+    }
+  }
+
+  /// Waits for the worker to exit, then closes the pseudo terminal so the output
+  /// reader sees EOF (ConPTY keeps it open until the console itself goes away).
+  pub fn wait(mut self) -> Result<(bool, Option<i32>), String> {
+    let status = self
+      .child
+      .wait()
+      .map_err(|error| format!("等待 Worker 退出失败: {error}"))?;
+    drop(self.master.lock().unwrap_or_else(|e| e.into_inner()).take());
+    Ok((status.success(), Some(status.exit_code() as i32)))
+  }
+}
+
+/// Spawns `command` (as prepared by `process_utils::build_cli_command`) in a new
+/// pseudo terminal.
+pub fn spawn(command: &Command) -> Result<SpawnedPty, String> {
+  let pair = native_pty_system()
+    .openpty(PtySize {
+      rows: DEFAULT_ROWS,
+      cols: DEFAULT_COLS,
+      pixel_width: 0,
+      pixel_height: 0,
+    })
+    .map_err(|error| format!("创建 PTY 失败: {error}"))?;
+
+  let mut builder = CommandBuilder::new(command.get_program());
+  builder.args(command.get_args());
+  for (key, value) in command.get_envs() {
+    match value {
+      Some(value) => builder.env(key, value),
+      None => builder.env_remove(key),
+    }
+  }
+  if let Some(dir) = command.get_current_dir() {
+    builder.cwd(dir);
+  }
+
+  let child = pair
+    .slave
+    .spawn_command(builder)
+    .map_err(|error| format!("在 PTY 中启动 Worker 失败: {error}"))?;
+  // The child holds its own handle; keeping ours would stop EOF from ever arriving.
+  drop(pair.slave);
+
+  let reader = pair
+    .master
+    .try_clone_reader()
+    .map_err(|error| format!("读取 PTY 输出失败: {error}"))?;
+  let writer = pair
+    .master
+    .take_writer()
+    .map_err(|error| format!("获取 PTY 输入失败: {error}"))?;
+
+  let process = PtyProcess {
+    child,
+    master: Arc::new(Mutex::new(Some(pair.master))),
+  };
+  Ok((process, reader, writer))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn resize_sets_the_window_size_the_worker_sees() {
+    let mut command = Command::new("sh");
+    command.args(["-c", "sleep 0.5; stty size"]);
+    let (process, mut reader, _writer) = spawn(&command).expect("spawn in a pty");
+    assert!(process.resizer().resize(132, 50).unwrap());
+
+    let reading = std::thread::spawn(move || {
+      let mut output = Vec::new();
+      let _ = reader.read_to_end(&mut output);
+      String::from_utf8_lossy(&output).into_owned()
+    });
+    let (success, _) = process.wait().unwrap();
+    let output = reading.join().unwrap();
+    assert!(success);
+    assert_eq!(output.trim(), "50 132");
+  }
+}
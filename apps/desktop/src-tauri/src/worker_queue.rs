@@ -0,0 +1,99 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Condvar, Mutex};
+
+const DEFAULT_MAX_RUNNING: usize = 4;
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkerStats {
+  pub running: usize,
+  pub queued: usize,
+  pub limit: usize,
+}
+
+struct QueueState {
+  running: usize,
+  limit: usize,
+  next_ticket: u64,
+  waiting: VecDeque<u64>,
+}
+
+/// Caps how many worker processes run at once. Launches beyond the cap wait
+/// in FIFO order until a running worker releases its slot.
+pub struct WorkerQueue {
+  state: Mutex<QueueState>,
+  changed: Condvar,
+}
+
+impl Default for WorkerQueue {
+  fn default() -> Self {
+    Self {
+      state: Mutex::new(QueueState {
+        running: 0,
+        limit: DEFAULT_MAX_RUNNING,
+        next_ticket: 0,
+        waiting: VecDeque::new(),
+      }),
+      changed: Condvar::new(),
+    }
+  }
+}
+
+/// Held for as long as the worker runs; dropping it frees the slot.
+pub struct WorkerSlot<'a> {
+  queue: &'a WorkerQueue,
+}
+
+impl Drop for WorkerSlot<'_> {
+  fn drop(&mut self) {
+    let mut state = self.queue.state.lock().unwrap_or_else(|e| e.into_inner());
+    state.running = state.running.saturating_sub(1);
+    self.queue.changed.notify_all();
+  }
+}
+
+impl WorkerQueue {
+  /// Blocks until a slot is free. `on_queued` is called with the 1-based queue
+  /// position only when the launch actually has to wait.
+  pub fn acquire(&self, on_queued: impl FnOnce(usize)) -> WorkerSlot<'_> {
+    let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+    let ticket = state.next_ticket;
+    state.next_ticket += 1;
+    state.waiting.push_back(ticket);
+
+    if state.running >= state.limit || state.waiting.front() != Some(&ticket) {
+      let position = state.waiting.len();
+      drop(state);
+      on_queued(position);
+      state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+    }
+
+    while state.running >= state.limit || state.waiting.front() != Some(&ticket) {
+      state = self.changed.wait(state).unwrap_or_else(|e| e.into_inner());
+    }
+    state.waiting.pop_front();
+    state.running += 1;
+    // The next ticket may also fit if the limit was raised meanwhile.
+    self.changed.notify_all();
+    WorkerSlot { queue: self }
+  }
+
+  pub fn stats(&self) -> WorkerStats {
+    let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+    WorkerStats {
+      running: state.running,
+      queued: state.waiting.len(),
+      limit: state.limit,
+    }
+  }
+
+  pub fn set_limit(&self, limit: usize) -> WorkerStats {
+    {
+      let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+      state.limit = limit.max(1);
+    }
+    self.changed.notify_all();
+    self.stats()
+  }
+}
@@ -61,8 +61,13 @@ import { normalizeTagCatalog } from "./lib/tag-catalog";
 import type {
   DetailMode,
   McpTaskUpdatedEvent,
+  McpTasksUpdatedEvent,
+  McpTaskRemovedEvent,
   McpTagCatalogUpdatedEvent,
+  McpProjectSettingsUpdatedEvent,
+  McpStateReloadedEvent,
   McpWorkerFinishedEvent,
+  TrayFocusProjectEvent,
   McpServerStatus,
   Project,
   Task,
@@ -76,6 +81,7 @@ import type {
 } from "./domain";
 import type { WorkerProbe } from "./components/WorkerConfigCard";
 import type { InstallTargetId } from "./lib/install-targets";
+import { isMac } from "./domain";
 
 function areTagListsEqual(a: string[], b: string[]): boolean {
   if (a.length !== b.length) return false;
@@ -419,7 +425,7 @@ export function App() {
   useEffect(() => {
     if (!isTauri) return;
     const palette = buildTrayTaskPalette();
-    const snapshot = buildTrayTaskSnapshot(projects, palette);
+    const snapshot = { ...buildTrayTaskSnapshot(projects, palette), templateIcon: isMac };
     invoke("sync_tray_task_badge", { snapshot }).catch(() => {});
   }, [isTauri, projects, theme]);
   useEffect(() => {
@@ -625,35 +631,98 @@ export function App() {
   useEffect(() => {
     if (!isTauri) return;
     let disposed = false;
-    let cleanup: (() => void) | undefined;
-    void listen<McpTaskUpdatedEvent>("maple://task-updated", (event) => {
-      const { projectName, task } = event.payload;
+    const cleanups: Array<() => void> = [];
+    const applyTaskUpdates = (projectName: string, incoming: Task[]) => {
       const needle = projectName.trim().toLowerCase();
-      if (!needle || !task?.id) return;
+      const updates = incoming.filter((task) => task?.id);
+      if (!needle || updates.length === 0) return;
       setProjects((prev) => {
         let changed = false;
         const next = prev.map((project) => {
           const normalized = project.name.toLowerCase();
           const matches = normalized === needle || normalized.includes(needle);
           if (!matches) return project;
-          const index = project.tasks.findIndex((item) => item.id === task.id);
-          if (index < 0) return project;
           const tasks = [...project.tasks];
-          const existing = tasks[index];
-          const shouldMarkConfirm =
-            existing.status !== "已完成" && task.status === "已完成";
-          tasks[index] = {
-            ...existing,
-            ...task,
-            needsConfirmation: shouldMarkConfirm
-              ? true
-              : existing.needsConfirmation,
-          };
+          for (const task of updates) {
+            const index = tasks.findIndex((item) => item.id === task.id);
+            if (index < 0) {
+              // Tasks created over MCP (duplicate/import) are not known to the UI yet.
+              tasks.push(task);
+              continue;
+            }
+            const existing = tasks[index];
+            const shouldMarkConfirm =
+              existing.status !== "已完成" && task.status === "已完成";
+            tasks[index] = {
+              ...existing,
+              ...task,
+              needsConfirmation: shouldMarkConfirm
+                ? true
+                : existing.needsConfirmation,
+            };
+          }
           changed = true;
           return { ...project, tasks };
         });
         return changed ? next : prev;
       });
+    };
+    const register = (promise: Promise<() => void>) => {
+      void promise.then((unlisten) => {
+        if (disposed) {
+          unlisten();
+        } else {
+          cleanups.push(unlisten);
+        }
+      });
+    };
+    register(listen<McpTaskUpdatedEvent>("maple://task-updated", (event) => {
+      applyTaskUpdates(event.payload.projectName, [event.payload.task]);
+    }));
+    register(listen<McpTasksUpdatedEvent>("maple://tasks-updated", (event) => {
+      applyTaskUpdates(event.payload.projectName, event.payload.tasks ?? []);
+    }));
+    register(listen<McpTaskRemovedEvent>("maple://task-removed", (event) => {
+      const { projectName, taskId } = event.payload;
+      const needle = projectName.trim().toLowerCase();
+      if (!needle || !taskId) return;
+      setProjects((prev) => {
+        let changed = false;
+        const next = prev.map((project) => {
+          const normalized = project.name.toLowerCase();
+          const matches = normalized === needle || normalized.includes(needle);
+          if (!matches || !project.tasks.some((task) => task.id === taskId)) return project;
+          changed = true;
+          return { ...project, tasks: project.tasks.filter((task) => task.id !== taskId) };
+        });
+        return changed ? next : prev;
+      });
+    }));
+    return () => {
+      disposed = true;
+      cleanups.forEach((unlisten) => unlisten());
+    };
+  }, [isTauri]);
+
+  useEffect(() => {
+    if (!isTauri) return;
+    let disposed = false;
+    let cleanup: (() => void) | undefined;
+    void listen<McpTagCatalogUpdatedEvent>("maple://tag-catalog-updated", (event) => {
+      const { projectName, tagCatalog } = event.payload;
+      const needle = projectName.trim().toLowerCase();
+      if (!needle) return;
+      setProjects((prev) => {
+        let changed = false;
+        const next = prev.map((project) => {
+          const normalized = project.name.toLowerCase();
+          const matches = normalized === needle || normalized.includes(needle);
+          if (!matches) return project;
+          changed = true;
+          return { ...project, tagCatalog: normalizeTagCatalog(tagCatalog) };
+        });
+        return changed ? next : prev;
+      });
     }).then((unlisten) => {
       if (disposed) {
         unlisten();
@@ -671,8 +740,34 @@ export function App() {
     if (!isTauri) return;
     let disposed = false;
     let cleanup: (() => void) | undefined;
-    void listen<McpTagCatalogUpdatedEvent>("maple://tag-catalog-updated", (event) => {
-      const { projectName, tagCatalog } = event.payload;
+    void listen<McpStateReloadedEvent>("maple://state-reloaded", () => {
+      invoke<string>("read_state_file")
+        .then((raw) => {
+          if (disposed) return;
+          const parsed = JSON.parse(raw.trim() || "[]") as Project[];
+          if (!Array.isArray(parsed)) return;
+          setProjects(normalizeProjects(parsed));
+        })
+        .catch(() => undefined);
+    }).then((unlisten) => {
+      if (disposed) {
+        unlisten();
+      } else {
+        cleanup = unlisten;
+      }
+    });
+    return () => {
+      disposed = true;
+      cleanup?.();
+    };
+  }, [isTauri]);
+
+  useEffect(() => {
+    if (!isTauri) return;
+    let disposed = false;
+    let cleanup: (() => void) | undefined;
+    void listen<McpProjectSettingsUpdatedEvent>("maple://project-settings-updated", (event) => {
+      const { projectName, wipLimit, disabledTools } = event.payload;
       const needle = projectName.trim().toLowerCase();
       if (!needle) return;
       setProjects((prev) => {
@@ -682,7 +777,11 @@ export function App() {
           const matches = normalized === needle || normalized.includes(needle);
           if (!matches) return project;
           changed = true;
-          return { ...project, tagCatalog: normalizeTagCatalog(tagCatalog) };
+          return {
+            ...project,
+            wipLimit: wipLimit ?? undefined,
+            disabledTools: disabledTools.length > 0 ? disabledTools : undefined,
+          };
         });
         return changed ? next : prev;
       });
@@ -720,18 +819,41 @@ export function App() {
     };
   }, [isTauri]);
 
+  useEffect(() => {
+    if (!isTauri) return;
+    let disposed = false;
+    let cleanup: (() => void) | undefined;
+    void listen<TrayFocusProjectEvent>("maple://tray-focus-project", (event) => {
+      const { projectId } = event.payload;
+      if (!projectsRef.current.some((p) => p.id === projectId)) return;
+      setBoardProjectId(projectId);
+      setView("board");
+      setSelectedTaskId(null);
+    }).then((unlisten) => {
+      if (disposed) {
+        unlisten();
+      } else {
+        cleanup = unlisten;
+      }
+    });
+    return () => {
+      disposed = true;
+      cleanup?.();
+    };
+  }, [isTauri]);
+
   useEffect(() => {
     if (!isTauri) return;
     let disposed = false;
     let cleanup: (() => void) | undefined;
     void listen<WorkerDoneEvent>("maple://worker-done", (event) => {
-      const { workerId, success, code } = event.payload;
+      const { workerId, success, code, cancelled } = event.payload;
       const parsed = parseWorkerId(workerId);
       const kindLabel = parsed.kind ? WORKER_KINDS.find((w) => w.kind === parsed.kind)?.label : null;
       const projectName = parsed.projectId ? projectsRef.current.find((p) => p.id === parsed.projectId)?.name : null;
       const label = kindLabel && projectName ? `${kindLabel} · ${projectName}` : kindLabel ?? workerId;
       const currentRunId = activeWorkerRunIdRef.current[workerId];
-      const interrupted = isWorkerRunInterrupted(currentRunId);
+      const interrupted = cancelled || isWorkerRunInterrupted(currentRunId);
       appendWorkerLog(workerId, `\n[exit ${code ?? "?"}] ${success ? "完成" : "失败"}\n`);
       setNotice(interrupted ? `${label} 已中断。` : `${label} 会话已结束（exit ${code ?? "?"}）`);
     }).then((unlisten) => {
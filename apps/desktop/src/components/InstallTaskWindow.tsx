@@ -18,6 +18,8 @@ export type InstallTargetResult = {
   stdout: string;
   stderr: string;
   error: string | null;
+  warning?: string;
+  durationMs?: number;
 };
 
 type InstallTaskWindowProps = {
@@ -34,6 +36,12 @@ type InstallTaskWindowProps = {
   onClose: () => void;
 };
 
+function formatDuration(ms: number): string {
+  if (ms < 1000) return `${ms}ms`;
+  if (ms < 60_000) return `${(ms / 1000).toFixed(1)}s`;
+  return `${Math.floor(ms / 60_000)}m${Math.round((ms % 60_000) / 1000)}s`;
+}
+
 function stateIcon(
   state: InstallTargetState,
   installing: boolean,
@@ -152,13 +160,15 @@ export function InstallTaskWindow({
 
                 const baseSubtitleText =
                   result?.skipped
-                    ? t("未检测到 CLI，已跳过", "CLI not found; skipped")
+                    ? result.cliFound === false
+                      ? t("未检测到 CLI，已跳过", "CLI not found; skipped")
+                      : t("已安装且配置一致，已跳过", "Already installed; skipped")
                     : result?.cliFound === false
                       ? t("未检测到 CLI", "CLI not found")
                       : state === "running"
                         ? t("正在处理…", "Running…")
                         : state === "success"
-                          ? t("已完成", "Done")
+                          ? (result?.warning ?? t("已完成", "Done"))
                           : state === "error"
                             ? (result?.error ?? t("出现错误", "Error"))
                             : t("等待开始", "Waiting");
@@ -185,6 +195,9 @@ export function InstallTaskWindow({
                     <span className="worker-console-pool-item-mode flex items-center gap-1">
                       <Icon icon={meta.icon} className="text-[14px]" style={{ color: meta.color }} />
                       <span className="text-[11px] text-muted">{meta.label}</span>
+                      {result?.durationMs != null ? (
+                        <span className="text-[11px] text-muted font-mono opacity-70">{formatDuration(result.durationMs)}</span>
+                      ) : null}
                     </span>
                   </div>
                 );
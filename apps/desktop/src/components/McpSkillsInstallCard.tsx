@@ -17,6 +17,7 @@ type InstallTargetProbe = {
   runtime: "native" | "wsl";
   cliFound: boolean;
   installed: boolean;
+  distro?: string;
 };
 
 type McpSkillsInstallCardProps = {
@@ -326,8 +327,8 @@ export function McpSkillsInstallCard({
 
               <div className="flex flex-wrap gap-1.5">
                 {(supportsWslRuntime
-                  ? (["codex", "claude", "iflow", "gemini", "opencode", "wsl:codex", "wsl:claude", "wsl:iflow", "wsl:gemini", "wsl:opencode", "windsurf"] as const)
-                  : (["codex", "claude", "iflow", "gemini", "opencode", "windsurf"] as const)
+                  ? (["codex", "claude", "iflow", "gemini", "opencode", "wsl:codex", "wsl:claude", "wsl:iflow", "wsl:gemini", "wsl:opencode", "windsurf", "cursor", "vscode"] as const)
+                  : (["codex", "claude", "iflow", "gemini", "opencode", "windsurf", "cursor", "vscode"] as const)
                 ).map((id) => {
                   const probe = probeById[id];
                   const selectable = id === "windsurf" ? true : Boolean(probe?.cliFound);
@@ -427,6 +428,8 @@ export function McpSkillsInstallCard({
                             wslGemini: supportsWslRuntime && targets["wsl:gemini"],
                             wslOpencode: supportsWslRuntime && targets["wsl:opencode"],
                             windsurf: targets.windsurf,
+                            cursor: targets.cursor,
+                            vscode: targets.vscode,
                             installId: nextInstallId
                           }
                         });
@@ -589,8 +592,8 @@ export function McpSkillsInstallCard({
 
             <p className="m-0 mt-2 text-[11px] text-muted font-sans opacity-80">
               {t(
-                "请在 Maple 仓库根目录执行。安装后重启对应 Worker（Codex / Claude / iFlow / Windsurf）。",
-                "Run from the Maple repo root. Restart the worker (Codex / Claude / iFlow / Windsurf) after install."
+                "请在 Maple 仓库根目录执行。安装后重启对应 Worker（Codex / Claude / iFlow / Windsurf / Cursor）。",
+                "Run from the Maple repo root. Restart the worker (Codex / Claude / iFlow / Windsurf / Cursor) after install."
               )}
             </p>
           </>
@@ -202,6 +202,8 @@ export function WorkerConfigCard({
         wslGemini: false,
         wslOpencode: false,
         windsurf: false,
+        cursor: false,
+        vscode: false,
         installId: nextInstallId,
       };
 
@@ -217,6 +219,8 @@ export function WorkerConfigCard({
         else if (id === "wsl:gemini") options.wslGemini = true;
         else if (id === "wsl:opencode") options.wslOpencode = true;
         else if (id === "windsurf") options.windsurf = true;
+        else if (id === "cursor") options.cursor = true;
+        else if (id === "vscode") options.vscode = true;
       }
 
       const report = await invoke<InstallMcpSkillsReport>("install_mcp_skills", { options });
@@ -1,10 +1,12 @@
 import { Icon } from "@iconify/react";
+import { invoke } from "@tauri-apps/api/core";
 import { useEffect, useRef } from "react";
 import { FitAddon } from "@xterm/addon-fit";
 import { Terminal, type ITheme } from "@xterm/xterm";
 import "@xterm/xterm/css/xterm.css";
 
 import { WORKER_KINDS, type ThemeMode } from "../lib/constants";
+import { hasTauriRuntime } from "../lib/utils";
 import type { WorkerKind } from "../domain";
 import { WorkerLogo } from "./WorkerLogo";
 
@@ -220,6 +222,17 @@ export function WorkerConsoleModal({
     lastFitRef.current = Date.now();
   }, [workerConsoleWorkerId]);
 
+  useEffect(() => {
+    const terminal = terminalRef.current;
+    if (!terminal || !hasTauriRuntime()) return;
+    const resizePty = (cols: number, rows: number) => {
+      void invoke<boolean>("resize_worker_pty", { workerId: workerConsoleWorkerId, cols, rows }).catch(() => undefined);
+    };
+    resizePty(terminal.cols, terminal.rows);
+    const subscription = terminal.onResize(({ cols, rows }) => resizePty(cols, rows));
+    return () => subscription.dispose();
+  }, [workerConsoleWorkerId]);
+
   useEffect(() => {
     const terminal = terminalRef.current;
     if (!terminal) return;
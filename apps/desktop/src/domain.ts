@@ -31,6 +31,7 @@ export type WorkerCommandResult = {
   code: number | null;
   stdout: string;
   stderr: string;
+  combined?: string;
 };
 
 export type WorkerLogEvent = {
@@ -38,12 +39,33 @@ export type WorkerLogEvent = {
   taskTitle: string;
   stream: "stdout" | "stderr";
   line: string;
+  lineIndex?: number;
 };
 
 export type WorkerDoneEvent = {
   workerId: string;
   success: boolean;
   code: number | null;
+  cancelled?: boolean;
+};
+
+export type WorkerProbeResult = {
+  kind: WorkerKind;
+  found: boolean;
+  version: string | null;
+  minimumVersion: string;
+  meetsMinimum: boolean;
+};
+
+export type WorkerQueuedEvent = {
+  workerId: string;
+  position: number;
+};
+
+export type WorkerStats = {
+  running: number;
+  queued: number;
+  limit: number;
 };
 
 export type McpServerStatus = {
@@ -78,7 +100,28 @@ export type Task = {
   status: TaskStatus;
   /** Optional: force this task to be handled by a specific worker kind. */
   targetWorkerKind?: WorkerKind;
+  /** Optional: id of the parent task when this is a subtask. */
+  parentId?: string;
+  /** Optional: ids of tasks that must be finished before this one. */
+  dependsOn?: string[];
+  /** Optional: link to the item this task was imported from (e.g. a GitHub issue). */
+  externalRef?: {
+    source: string;
+    id: string;
+    url?: string;
+  };
+  /** Optional: files in the project that agents referenced for this task. */
+  fileRefs?: {
+    path: string;
+    label?: string;
+  }[];
   needsConfirmation?: boolean;
+  /** Optional: structured reason while the task is blocked. */
+  blocker?: {
+    reason: string;
+    since: string;
+    blockingOn?: string;
+  };
   tags: string[];
   createdAt: string;
   updatedAt: string;
@@ -92,6 +135,10 @@ export type Project = {
   workerKind?: WorkerKind;
   tasks: Task[];
   tagCatalog?: TagCatalog;
+  /** MCP tools that agents may not call for this project. */
+  disabledTools?: string[];
+  /** Max number of tasks allowed in 进行中 at once. */
+  wipLimit?: number;
 };
 
 export type McpTaskUpdatedEvent = {
@@ -99,11 +146,36 @@ export type McpTaskUpdatedEvent = {
   task: Task;
 };
 
+export type McpTaskRemovedEvent = {
+  projectName: string;
+  taskId: string;
+};
+
+export type McpTasksUpdatedEvent = {
+  projectName: string;
+  tasks: Task[];
+};
+
 export type McpTagCatalogUpdatedEvent = {
   projectName: string;
   tagCatalog: TagCatalog;
 };
 
+export type McpStateReloadedEvent = {
+  reason: string;
+  projectCount: number;
+};
+
+export type McpProjectSettingsUpdatedEvent = {
+  projectName: string;
+  wipLimit: number | null;
+  disabledTools: string[];
+};
+
+export type TrayFocusProjectEvent = {
+  projectId: string;
+};
+
 export type McpWorkerFinishedEvent = {
   project: string;
   summary: string;
@@ -5,6 +5,8 @@ export type InstallTargetId =
   | "gemini"
   | "opencode"
   | "windsurf"
+  | "cursor"
+  | "vscode"
   | "wsl:codex"
   | "wsl:claude"
   | "wsl:iflow"
@@ -18,6 +20,8 @@ export const INSTALL_TARGETS: InstallTargetId[] = [
   "gemini",
   "opencode",
   "windsurf",
+  "cursor",
+  "vscode",
   "wsl:codex",
   "wsl:claude",
   "wsl:iflow",
@@ -36,6 +40,8 @@ export function formatInstallTargetLabel(target: InstallTargetId): string {
   if (target === "wsl:iflow") return "WSL · iFlow";
   if (target === "wsl:gemini") return "WSL · Gemini";
   if (target === "wsl:opencode") return "WSL · OpenCode";
+  if (target === "cursor") return "Cursor";
+  if (target === "vscode") return "VS Code";
   return "Windsurf";
 }
 
@@ -50,5 +56,7 @@ export function formatInstallTargetIcon(target: InstallTargetId): string {
   if (target === "wsl:iflow") return "mingcute:flash-line";
   if (target === "wsl:gemini") return "mingcute:ai-line";
   if (target === "wsl:opencode") return "mingcute:terminal-box-line";
+  if (target === "cursor") return "mingcute:cursor-3-line";
+  if (target === "vscode") return "mingcute:vscode-line";
   return "mingcute:wind-line";
 }
@@ -9,6 +9,12 @@ export type TrayTaskPalette = {
   attention: string;
 };
 
+export type TrayProjectCount = {
+  id: string;
+  name: string;
+  unresolvedCount: number;
+};
+
 export type TrayTaskSnapshot = {
   unresolvedCount: number;
   inProgressCount: number;
@@ -19,6 +25,9 @@ export type TrayTaskSnapshot = {
   blockedCount: number;
   completedCount: number;
   palette?: TrayTaskPalette;
+  projects?: TrayProjectCount[];
+  /** macOS only: render a monochrome template icon that follows the menu bar appearance. */
+  templateIcon?: boolean;
 };
 
 export function buildTrayTaskSnapshot(projects: Project[], palette?: TrayTaskPalette): TrayTaskSnapshot {
@@ -29,8 +38,10 @@ export function buildTrayTaskSnapshot(projects: Project[], palette?: TrayTaskPal
   let confirmCount = 0;
   let blockedCount = 0;
   let completedCount = 0;
+  const projectCounts: TrayProjectCount[] = [];
 
   for (const project of projects) {
+    const unresolvedBefore = inProgressCount + queuedCount + todoCount + needInfoCount + confirmCount + blockedCount;
     for (const task of project.tasks) {
       switch (task.status) {
         case "进行中":
@@ -63,6 +74,8 @@ export function buildTrayTaskSnapshot(projects: Project[], palette?: TrayTaskPal
           break;
       }
     }
+    const unresolvedAfter = inProgressCount + queuedCount + todoCount + needInfoCount + confirmCount + blockedCount;
+    projectCounts.push({ id: project.id, name: project.name, unresolvedCount: unresolvedAfter - unresolvedBefore });
   }
 
   return {
@@ -75,5 +88,6 @@ export function buildTrayTaskSnapshot(projects: Project[], palette?: TrayTaskPal
     blockedCount,
     completedCount,
     ...(palette ? { palette } : {}),
+    ...(projectCounts.length > 0 ? { projects: projectCounts } : {}),
   };
 }